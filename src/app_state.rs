@@ -1,7 +1,89 @@
-use crate::files::TypeDefinition;
+use crate::extractors::extractor_for;
+use crate::files::{SymbolSource, TypeDefinition, TypeKind};
 use crate::pane::PaneMetadata;
-use std::collections::BTreeMap;
-use std::path::PathBuf;
+use crate::roots::{normalize, ProjectRoot};
+use crate::workspace::{file_module_path, CargoWorkspace};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+#[derive(Debug, Clone, Default)]
+pub struct IndexStatus {
+    pub in_progress: bool,
+    pub indexed_count: usize,
+    pub truncated_at: Option<usize>, // Set if the file limit cut the index short
+}
+
+// What the index knows about files besides their path
+#[derive(Debug, Default)]
+pub struct FileTags {
+    pub workspace: CargoWorkspace,
+    pub symlinked_files: BTreeSet<PathBuf>,
+    pub roots: Vec<ProjectRoot>,
+    // Files placed elsewhere in the module tree by a `#[path]` attribute, and
+    // the module they end up as
+    pub path_modules: BTreeMap<PathBuf, Rc<str>>,
+}
+
+impl FileTags {
+    pub fn crate_for(&self, file: &Path) -> Option<Rc<str>> {
+        self.workspace.crate_for(file)
+    }
+
+    // The module `file` is in its crate, following Cargo's default layout
+    // relative to its package (or its root outside of Cargo projects)
+    pub fn file_module_for(&self, file: &Path) -> Option<Rc<str>> {
+        if file.extension().is_none_or(|extension| extension != "rs") {
+            return None; // Module paths are Rust's
+        }
+        if let Some(module) = self.path_modules.get(file) {
+            return Some(Rc::clone(module));
+        }
+        let base_dir = match self.workspace.package_for(file) {
+            Some(package) => package.dir.as_path(),
+            None => self.root_for(file).map_or(Path::new(""), |root| &root.dir),
+        };
+        let module_path = file_module_path(file.strip_prefix(base_dir).unwrap_or(file));
+        if module_path.is_empty() {
+            None
+        } else {
+            Some(module_path.join("::").into())
+        }
+    }
+
+    // Tags the definitions of `file` with its crate and module
+    fn tag(&self, file: &Path, definitions: &mut [TypeDefinition]) {
+        let crate_name = self.crate_for(file);
+        let file_module = self.file_module_for(file);
+        for definition in definitions {
+            definition.set_crate_name(crate_name.clone());
+            // Indexes spell out the whole module path
+            if definition.source != SymbolSource::Index {
+                definition.set_file_module(file_module.clone());
+            }
+        }
+    }
+
+    pub fn is_symlinked(&self, file: &Path) -> bool {
+        self.symlinked_files.contains(file)
+    }
+
+    // The innermost root containing `file` (relative to the host folder)
+    pub fn root_for(&self, file: &Path) -> Option<&ProjectRoot> {
+        self.roots
+            .iter()
+            .filter(|root| file.starts_with(&root.dir))
+            .max_by_key(|root| root.dir.components().count())
+    }
+
+    // Results only need telling apart when more than one root is indexed
+    pub fn root_label_for(&self, file: &Path) -> Option<Rc<str>> {
+        if self.roots.len() < 2 {
+            return None;
+        }
+        self.root_for(file).map(|root| Rc::clone(&root.label))
+    }
+}
 
 #[derive(Default)]
 pub struct AppState {
@@ -10,6 +92,14 @@ pub struct AppState {
     pub rust_assets: BTreeMap<PathBuf, Vec<TypeDefinition>>,
     pub cwd: PathBuf,
     pub user_selected_directory: bool, // Flag to track if directory was selected by user
+    pub index_status: IndexStatus,
+    pub file_tags: FileTags,
+    // Definitions read from tags files, for the files no built-in extractor
+    // scans
+    pub imported_symbols: BTreeMap<PathBuf, Vec<TypeDefinition>>,
+    // Definitions read from SCIP or LSIF indexes, which the compiler knows
+    // better than any scanner
+    pub index_symbols: BTreeMap<PathBuf, Vec<TypeDefinition>>,
 }
 
 impl AppState {
@@ -17,14 +107,200 @@ impl AppState {
         self.pane_metadata = panes;
     }
 
+    #[cfg(test)]
     pub fn update_files(&mut self, files: Vec<PathBuf>) {
         self.files = files;
     }
 
+    #[cfg(test)]
     pub fn update_rust_assets(&mut self, rust_assets: BTreeMap<PathBuf, Vec<TypeDefinition>>) {
         self.rust_assets = rust_assets;
     }
 
+    pub fn add_indexed_files(
+        &mut self,
+        files_and_rust_assets: BTreeMap<PathBuf, Vec<TypeDefinition>>,
+    ) {
+        for (file, definitions) in files_and_rust_assets {
+            self.upsert_file(file, definitions);
+        }
+    }
+
+    // Adds a file to the index, or replaces its definitions if already known
+    pub fn upsert_file(&mut self, file: PathBuf, mut definitions: Vec<TypeDefinition>) {
+        if let Some(indexed) = self.index_symbols.get(&file) {
+            definitions = indexed.clone();
+        } else if definitions.is_empty() {
+            if let Some(imported) = self.imported_symbols.get(&file) {
+                definitions = imported.clone();
+            }
+        }
+        self.file_tags.tag(&file, &mut definitions);
+        if self.rust_assets.insert(file.clone(), definitions).is_none() {
+            self.files.push(file.clone());
+        }
+        self.follow_path_attributes(file);
+    }
+
+    // Re-tags the files that `#[path]` modules declared in `file` point to,
+    // and in turn the ones they point to, as their module may have changed
+    fn follow_path_attributes(&mut self, file: PathBuf) {
+        let mut pending = vec![file];
+        let mut visited = BTreeSet::new();
+        while let Some(file) = pending.pop() {
+            if !visited.insert(file.clone()) {
+                continue; // Modules pointing at each other
+            }
+            let dir = file.parent().unwrap_or(Path::new(""));
+            let targets: Vec<(PathBuf, Rc<str>)> = self
+                .rust_assets
+                .get(&file)
+                .into_iter()
+                .flatten()
+                .filter(|definition| definition.type_kind == TypeKind::Module)
+                .filter_map(|module| {
+                    let target = normalize(&dir.join(module.path_attribute.as_ref()?));
+                    Some((target, module.qualified_name().into()))
+                })
+                .collect();
+            for (target, module) in targets {
+                if self.file_tags.path_modules.get(&target) == Some(&module) {
+                    continue;
+                }
+                self.file_tags.path_modules.insert(target.clone(), module);
+                if let Some(mut definitions) = self.rust_assets.remove(&target) {
+                    self.file_tags.tag(&target, &mut definitions);
+                    self.rust_assets.insert(target.clone(), definitions);
+                    pending.push(target);
+                }
+            }
+        }
+    }
+
+    // Replaces the definitions imported from the tags file of the project
+    // root at `root_dir`. Files indexed later get theirs when they are
+    pub fn import_symbols(
+        &mut self,
+        root_dir: &Path,
+        symbols: BTreeMap<PathBuf, Vec<TypeDefinition>>,
+    ) {
+        let previous: Vec<PathBuf> = self
+            .imported_symbols
+            .keys()
+            .filter(|file| file.starts_with(root_dir))
+            .cloned()
+            .collect();
+        for file in previous {
+            self.imported_symbols.remove(&file);
+            if let Some(definitions) = self.rust_assets.get_mut(&file) {
+                definitions.clear();
+            }
+        }
+        for (file, mut definitions) in symbols {
+            // The built-in extractors know more than ctags, eg. signatures
+            if extractor_for(&file).is_some() || self.index_symbols.contains_key(&file) {
+                continue;
+            }
+            self.file_tags.tag(&file, &mut definitions);
+            if let Some(indexed) = self.rust_assets.get_mut(&file) {
+                indexed.clone_from(&definitions);
+            }
+            self.imported_symbols.insert(file, definitions);
+        }
+    }
+
+    // Replaces the definitions read from the SCIP or LSIF index of the
+    // project root at `root_dir`, which take the place of scanned ones. Files
+    // the index doesn't cover keep theirs
+    pub fn import_index(
+        &mut self,
+        root_dir: &Path,
+        symbols: BTreeMap<PathBuf, Vec<TypeDefinition>>,
+    ) {
+        let previous: Vec<PathBuf> = self
+            .index_symbols
+            .keys()
+            .filter(|file| file.starts_with(root_dir))
+            .cloned()
+            .collect();
+        for file in previous {
+            self.discard_index_symbols(&file);
+        }
+        for (file, mut definitions) in symbols {
+            self.file_tags.tag(&file, &mut definitions);
+            if let Some(indexed) = self.rust_assets.get_mut(&file) {
+                indexed.clone_from(&definitions);
+            }
+            self.index_symbols.insert(file, definitions);
+        }
+    }
+
+    // Stops preferring the index for `file`, eg. once it was edited since.
+    // Its definitions are empty until it is scanned again
+    pub fn discard_index_symbols(&mut self, file: &Path) {
+        if self.index_symbols.remove(file).is_some() {
+            if let Some(definitions) = self.rust_assets.get_mut(file) {
+                definitions.clear();
+            }
+        }
+    }
+
+    // Removes a file, or everything below a folder, from the index
+    pub fn remove_path(&mut self, path: &Path) {
+        self.files.retain(|file| !file.starts_with(path));
+        self.rust_assets.retain(|file, _| !file.starts_with(path));
+        self.imported_symbols
+            .retain(|file, _| !file.starts_with(path));
+        self.index_symbols.retain(|file, _| !file.starts_with(path));
+        self.file_tags
+            .symlinked_files
+            .retain(|file| !file.starts_with(path));
+    }
+
+    pub fn mark_symlinked_files(&mut self, files: Vec<PathBuf>) {
+        self.file_tags.symlinked_files.extend(files);
+    }
+
+    // Re-tags every known definition with the package and module that now own
+    // it
+    pub fn set_workspace(&mut self, workspace: CargoWorkspace) {
+        self.file_tags.workspace = workspace;
+        self.file_tags.path_modules.clear();
+        for (file, definitions) in self.rust_assets.iter_mut() {
+            self.file_tags.tag(file, definitions);
+        }
+        let files: Vec<PathBuf> = self.rust_assets.keys().cloned().collect();
+        for file in files {
+            self.follow_path_attributes(file);
+        }
+    }
+
+    pub fn set_roots(&mut self, roots: Vec<ProjectRoot>) {
+        self.file_tags.roots = roots;
+    }
+
+    // Forgets everything indexed, eg. once paths are relative to another folder
+    pub fn clear_index(&mut self) {
+        self.files.clear();
+        self.rust_assets.clear();
+        self.file_tags.symlinked_files.clear();
+        self.file_tags.path_modules.clear();
+        self.imported_symbols.clear();
+        self.index_symbols.clear();
+    }
+
+    pub fn get_file_tags(&self) -> &FileTags {
+        &self.file_tags
+    }
+
+    pub fn set_index_status(&mut self, index_status: IndexStatus) {
+        self.index_status = index_status;
+    }
+
+    pub fn get_index_status(&self) -> &IndexStatus {
+        &self.index_status
+    }
+
     pub fn set_cwd(&mut self, cwd: PathBuf) {
         self.cwd = cwd;
     }
@@ -37,10 +313,19 @@ impl AppState {
         &self.files
     }
 
+    #[cfg(test)]
     pub fn get_rust_assets(&self) -> Vec<TypeDefinition> {
+        self.rust_asset_refs().into_iter().cloned().collect()
+    }
+
+    // Every definition without copying them, which searching on every
+    // keystroke can't afford
+    pub fn rust_asset_refs(&self) -> Vec<&TypeDefinition> {
         let mut all_assets = Vec::new();
-        for definitions in self.rust_assets.values() {
-            all_assets.extend(definitions.clone());
+        // Variants and fields are searched next to their enum or struct
+        for definition in self.rust_assets.values().flatten() {
+            all_assets.push(definition);
+            all_assets.extend(definition.children.iter());
         }
         all_assets
     }
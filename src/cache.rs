@@ -0,0 +1,176 @@
+use crate::config::ScanConfig;
+use crate::files::{scan_file_definitions, TypeDefinition};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct FileStamp {
+    modified_secs: u64,
+    modified_nanos: u32,
+    size: u64,
+}
+
+impl FileStamp {
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(FileStamp {
+            modified_secs: modified.as_secs(),
+            modified_nanos: modified.subsec_nanos(),
+            size: metadata.len(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedFile {
+    stamp: FileStamp,
+    definitions: Vec<TypeDefinition>,
+}
+
+// Bumped whenever scanning finds different definitions, eg. in more
+// languages, so files cached by an older version are scanned again
+const SCAN_VERSION: u32 = 3;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheContents {
+    #[serde(default)]
+    scan_version: u32,
+    // Of the scan settings, which change what is indexed and found
+    #[serde(default)]
+    scan_config_hash: u64,
+    repository_root: PathBuf,
+    files: BTreeMap<PathBuf, CachedFile>,
+}
+
+// On-disk index of one repository, so definitions of unchanged files don't
+// have to be scanned again on the next launch
+#[derive(Debug, Default)]
+pub struct IndexCache {
+    cache_file: PathBuf,
+    root: PathBuf,
+    prefix: PathBuf,
+    previous: CacheContents,
+    current: CacheContents,
+}
+
+impl IndexCache {
+    // `root` is where the indexed files are read from, `repository_root` is
+    // the host path of the repository and keys the cache file. Files are
+    // passed in relative to `root` but cached relative to `root/prefix`, so
+    // the cache stays valid when the repository is reached from another folder
+    pub fn load(
+        cache_dir: &Path,
+        root: &Path,
+        repository_root: &Path,
+        prefix: &Path,
+        config: &ScanConfig,
+    ) -> Self {
+        let scan_config_hash = fnv1a(&serde_json::to_vec(config).unwrap_or_default());
+        let cache_file = cache_dir.join(format!(
+            "index-{:016x}.json",
+            fnv1a(repository_root.to_string_lossy().as_bytes())
+        ));
+        let previous = fs::read_to_string(&cache_file)
+            .ok()
+            .and_then(|contents| serde_json::from_str::<CacheContents>(&contents).ok())
+            // Guard against hash collisions between repositories
+            .filter(|contents| contents.repository_root == repository_root)
+            .filter(|contents| {
+                contents.scan_version == SCAN_VERSION
+                    && contents.scan_config_hash == scan_config_hash
+            })
+            .unwrap_or_default();
+
+        IndexCache {
+            cache_file,
+            root: root.to_path_buf(),
+            prefix: prefix.to_path_buf(),
+            previous,
+            current: CacheContents {
+                scan_version: SCAN_VERSION,
+                scan_config_hash,
+                repository_root: repository_root.to_path_buf(),
+                files: BTreeMap::new(),
+            },
+        }
+    }
+
+    // Up to `count` files indexed last time, in order and following the file
+    // `after` if given, without validating them against the disk
+    pub fn cached_files(
+        &self,
+        after: Option<&Path>,
+        count: usize,
+    ) -> Vec<(PathBuf, Vec<TypeDefinition>)> {
+        let start = match after {
+            Some(file) => Bound::Excluded(file.strip_prefix(&self.prefix).unwrap_or(file)),
+            None => Bound::Unbounded,
+        };
+        self.previous
+            .files
+            .range::<Path, _>((start, Bound::Unbounded))
+            .take(count)
+            .map(|(file, cached)| (self.prefix.join(file), cached.definitions.clone()))
+            .collect()
+    }
+
+    // Returns the definitions of a file, rescanning it only if its
+    // modification time or size changed since it was cached
+    pub fn definitions_for(&mut self, file: &Path, config: &ScanConfig) -> Vec<TypeDefinition> {
+        let stamp = match FileStamp::of(&self.root.join(file)) {
+            Some(stamp) => stamp,
+            None => return scan_file_definitions(file, config),
+        };
+
+        let key = file.strip_prefix(&self.prefix).unwrap_or(file);
+        // Taken out of the previous cache, which then only holds files the
+        // walk hasn't seen (yet)
+        let definitions = match self.previous.files.remove(key) {
+            Some(cached) if cached.stamp == stamp => cached.definitions,
+            _ => scan_file_definitions(file, config),
+        };
+
+        self.current.files.insert(
+            key.to_path_buf(),
+            CachedFile {
+                stamp,
+                definitions: definitions.clone(),
+            },
+        );
+        definitions
+    }
+
+    // Files that were cached but not seen again by the current walk
+    pub fn removed_files(&self) -> Vec<PathBuf> {
+        self.previous
+            .files
+            .keys()
+            .filter(|file| !self.current.files.contains_key(*file))
+            .map(|file| self.prefix.join(file))
+            .collect()
+    }
+
+    // Replaces the cache file with what the current walk has seen
+    pub fn save(&self) -> std::io::Result<()> {
+        if let Some(cache_dir) = self.cache_file.parent() {
+            fs::create_dir_all(cache_dir)?;
+        }
+        let contents = serde_json::to_string(&self.current)?;
+        fs::write(&self.cache_file, contents)
+    }
+}
+
+// Stable across runs and Rust versions, unlike the std hasher
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for &byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
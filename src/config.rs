@@ -0,0 +1,202 @@
+use crate::editor::EditorCommand;
+use crate::files::{IndexSource, SymlinkPolicy, DEFAULT_FILE_LIMIT};
+use crate::pane::DEFAULT_EDITORS;
+use crate::symbol_index::SymbolIndexFile;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+// Files larger than this are listed but not scanned for definitions. None
+// are skipped unless a size is configured
+pub const DEFAULT_MAX_FILE_SIZE: u64 = u64::MAX;
+// Files are scanned this many bytes at a time rather than read whole
+pub const DEFAULT_SCAN_CHUNK_SIZE: u64 = 1_000_000;
+// No cap unless one is configured
+pub const DEFAULT_MAX_DEFINITIONS_PER_FILE: usize = usize::MAX;
+
+// Settings that affect walking and scanning, shared with the index worker
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanConfig {
+    pub ignore_patterns: Vec<String>, // Gitignore syntax, relative to the project root
+    pub file_limit: usize,
+    pub max_file_size: u64,
+    pub scan_chunk_size: u64,
+    pub max_definitions_per_file: usize,
+    pub symlinks: SymlinkPolicy,
+}
+
+impl Default for ScanConfig {
+    fn default() -> Self {
+        ScanConfig {
+            ignore_patterns: Vec::new(),
+            file_limit: DEFAULT_FILE_LIMIT,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            scan_chunk_size: DEFAULT_SCAN_CHUNK_SIZE,
+            max_definitions_per_file: DEFAULT_MAX_DEFINITIONS_PER_FILE,
+            symlinks: SymlinkPolicy::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub scan: ScanConfig,
+    pub index_source: IndexSource,
+    pub editors: Vec<String>, // Commands and titles that mark a pane as an editor
+    pub roots: Vec<PathBuf>,  // Indexed besides the current folder, relative to it unless absolute
+    // Opens symbols at their column, instead of letting Zellij open the file
+    // at their line in $EDITOR
+    pub editor: Option<EditorCommand>,
+    pub ctags: bool,                   // Import the `tags` file of each root
+    pub symbol_index: SymbolIndexFile, // The SCIP or LSIF index of each root to import
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            scan: ScanConfig::default(),
+            index_source: IndexSource::default(),
+            editors: DEFAULT_EDITORS.iter().map(|e| e.to_string()).collect(),
+            roots: Vec::new(),
+            editor: None,
+            ctags: false,
+            symbol_index: SymbolIndexFile::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    pub key: String,
+    pub value: String,
+    pub expected: Option<&'static str>, // None for an option that doesn't exist
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.expected {
+            Some(expected) => write!(
+                f,
+                "'{}' should be {} (got '{}')",
+                self.key, expected, self.value
+            ),
+            None => write!(f, "unknown option '{}'", self.key),
+        }
+    }
+}
+
+impl Config {
+    // Parses the plugin's KDL configuration, keeping the default of every
+    // invalid value and reporting it
+    pub fn from_configuration(
+        configuration: &BTreeMap<String, String>,
+    ) -> (Self, Vec<ConfigError>) {
+        let mut config = Config::default();
+        let mut errors = Vec::new();
+
+        for (key, value) in configuration {
+            let parsed = match key.as_str() {
+                "file_limit" => parse_positive(value).map(|limit| config.scan.file_limit = limit),
+                "max_file_size" => parse_size(value).map(|size| config.scan.max_file_size = size),
+                "scan_chunk_size" => {
+                    parse_size(value).map(|size| config.scan.scan_chunk_size = size)
+                }
+                "max_definitions_per_file" => {
+                    parse_positive(value).map(|max| config.scan.max_definitions_per_file = max)
+                }
+                "ignore" => {
+                    config.scan.ignore_patterns = split_list(value);
+                    Ok(())
+                }
+                "symlinks" => SymlinkPolicy::from_config(value)
+                    .map(|policy| config.scan.symlinks = policy)
+                    .ok_or("\"skip\", \"follow\" or \"follow_once\""),
+                "index_source" => IndexSource::from_config(value)
+                    .map(|source| config.index_source = source)
+                    .ok_or("\"walk\" or \"git\""),
+                "editors" => {
+                    let editors = split_list(value);
+                    if editors.is_empty() {
+                        Err("a list of editor commands")
+                    } else {
+                        config.editors = editors;
+                        Ok(())
+                    }
+                }
+                "editor" => EditorCommand::from_config(value)
+                    .map(|editor| config.editor = Some(editor))
+                    .ok_or("vim, nvim, hx, kak, emacs, nano, micro, code or subl"),
+                "ctags" => parse_bool(value).map(|ctags| config.ctags = ctags),
+                "symbol_index" => SymbolIndexFile::from_config(value)
+                    .map(|symbol_index| config.symbol_index = symbol_index)
+                    .ok_or("true, false or the path of a SCIP or LSIF index"),
+                "roots" => {
+                    config.roots = split_list(value).into_iter().map(PathBuf::from).collect();
+                    Ok(())
+                }
+                // Zellij's own settings start with an underscore
+                _ if key.starts_with('_') => Ok(()),
+                _ => {
+                    errors.push(ConfigError {
+                        key: key.clone(),
+                        value: value.clone(),
+                        expected: None,
+                    });
+                    Ok(())
+                }
+            };
+
+            if let Err(expected) = parsed {
+                errors.push(ConfigError {
+                    key: key.clone(),
+                    value: value.clone(),
+                    expected: Some(expected),
+                });
+            }
+        }
+
+        (config, errors)
+    }
+}
+
+fn parse_positive(value: &str) -> Result<usize, &'static str> {
+    match value.trim().parse() {
+        Ok(number) if number > 0 => Ok(number),
+        _ => Err("a positive number"),
+    }
+}
+
+fn parse_bool(value: &str) -> Result<bool, &'static str> {
+    match value.trim() {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err("true or false"),
+    }
+}
+
+// Bytes, optionally with a K or M suffix (eg. "512K", "2M")
+fn parse_size(value: &str) -> Result<u64, &'static str> {
+    let value = value.trim();
+    let upper = value.to_ascii_uppercase();
+    let upper = upper.strip_suffix('B').unwrap_or(&upper);
+    let (number, multiplier) = if let Some(number) = upper.strip_suffix('K') {
+        (number, 1_000)
+    } else if let Some(number) = upper.strip_suffix('M') {
+        (number, 1_000_000)
+    } else {
+        (upper, 1)
+    };
+    match number.trim().parse::<u64>() {
+        Ok(number) if number > 0 => Ok(number * multiplier),
+        _ => Err("a size in bytes, eg. 1000000 or 2M"),
+    }
+}
+
+// Whitespace or comma separated
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|item| !item.is_empty())
+        .map(|item| item.to_owned())
+        .collect()
+}
@@ -0,0 +1,232 @@
+use crate::files::{SymbolSource, TypeDefinition, TypeKind, Visibility};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+// Where ctags writes its tags file by default, relative to a project root
+pub const TAGS_FILE_NAMES: [&str; 2] = ["tags", ".tags"];
+
+// Reads the tags file of the project root at `root_dir` (relative to the
+// host folder at `host`), with the paths of its definitions relative to the
+// host folder too
+pub fn load_tags(host: &Path, root_dir: &Path) -> BTreeMap<PathBuf, Vec<TypeDefinition>> {
+    TAGS_FILE_NAMES
+        .iter()
+        .find_map(|name| fs::read(host.join(root_dir).join(name)).ok())
+        .map(|contents| parse_tags(&String::from_utf8_lossy(&contents), root_dir))
+        .unwrap_or_default()
+}
+
+// Parses a Universal Ctags file in the extended format, that is with a kind
+// and a `line:` field after the address of each tag. Tags without either, or
+// of a kind with no `TypeKind` counterpart, are left out
+pub fn parse_tags(contents: &str, root_dir: &Path) -> BTreeMap<PathBuf, Vec<TypeDefinition>> {
+    let mut definitions: BTreeMap<PathBuf, Vec<TypeDefinition>> = BTreeMap::new();
+    // Tags of the same file are usually far apart, as they are sorted by name
+    let mut file_paths: BTreeMap<&str, Rc<PathBuf>> = BTreeMap::new();
+    for line in contents.lines() {
+        if line.starts_with("!_TAG_") {
+            continue; // Pseudo-tags describing the file itself
+        }
+        let tag = match parse_tag_line(line) {
+            Some(tag) => tag,
+            None => continue,
+        };
+        let file_path = file_paths
+            .entry(tag.file)
+            .or_insert_with(|| Rc::new(root_dir.join(tag.file)));
+        let definition = TypeDefinition {
+            owner: tag.owner.map(Rc::from),
+            visibility: tag.visibility,
+            signature: tag.signature,
+            column: tag.column,
+            char_column: tag.char_column,
+            source: SymbolSource::Ctags,
+            ..TypeDefinition::new(
+                tag.kind,
+                tag.name.to_owned(),
+                Rc::clone(file_path),
+                tag.line_number,
+            )
+        };
+        definitions
+            .entry(file_path.to_path_buf())
+            .or_default()
+            .push(definition);
+    }
+    for file_definitions in definitions.values_mut() {
+        file_definitions.sort_by_key(|definition| definition.line_number);
+    }
+    definitions
+}
+
+struct Tag<'a> {
+    name: &'a str,
+    file: &'a str,
+    kind: TypeKind,
+    line_number: usize,
+    owner: Option<&'a str>,
+    visibility: Visibility,
+    signature: String,
+    column: usize,
+    char_column: usize,
+}
+
+// `name<TAB>file<TAB>address;"<TAB>kind<TAB>field:value...`
+fn parse_tag_line(line: &str) -> Option<Tag<'_>> {
+    let (name, rest) = line.split_once('\t')?;
+    let (file, address) = rest.split_once('\t')?;
+    if Path::new(file).is_absolute() {
+        return None; // Not below the project root
+    }
+    let address_end = address_end(address)?;
+    let address_line = address[..address_end].parse::<usize>().ok();
+    let (column, char_column) = name_columns(&address[..address_end], name);
+    let fields = address[address_end..].strip_prefix(";\"")?;
+
+    let mut kind_name = None;
+    let mut line_number = address_line;
+    let mut language = None;
+    let mut owner = None;
+    let mut visibility = Visibility::default();
+    let mut signature = String::new();
+    for field in fields.split('\t').filter(|field| !field.is_empty()) {
+        let (key, value) = match field.split_once(':') {
+            Some((key, value)) => (key, value),
+            None => {
+                kind_name = Some(field); // The kind alone, without `kind:`
+                continue;
+            }
+        };
+        match key {
+            "kind" => kind_name = Some(value),
+            "line" => line_number = value.parse().ok(),
+            "language" => language = Some(value),
+            "access" => {
+                visibility = match value {
+                    "public" => Visibility::Public,
+                    _ => Visibility::Private,
+                }
+            }
+            "signature" => signature = unescape(value),
+            // `scope:class:Name` with --fields=+Z
+            "scope" => owner = value.split_once(':').map(|(_, scope)| last_segment(scope)),
+            "class" | "struct" | "enum" | "union" | "interface" | "trait" | "implementation" => {
+                owner = Some(last_segment(value))
+            }
+            _ => {}
+        }
+    }
+
+    Some(Tag {
+        name,
+        file,
+        kind: type_kind(kind_name?, language)?,
+        line_number: line_number?,
+        owner,
+        visibility,
+        signature,
+        column,
+        char_column,
+    })
+}
+
+// Where `name` starts on the line a `/^...$/` address matches, 1-based in
+// bytes and in chars. Line number addresses have no column, so (0, 0)
+fn name_columns(address: &str, name: &str) -> (usize, usize) {
+    let pattern = match address
+        .strip_prefix("/^")
+        .or_else(|| address.strip_prefix("?^"))
+    {
+        Some(pattern) => &pattern[..pattern.len().saturating_sub(1)],
+        None => return (0, 0),
+    };
+    let mut line = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => line.extend(chars.next()),
+            c => line.push(c),
+        }
+    }
+    let is_identifier = |c: char| c.is_alphanumeric() || c == '_';
+    line.match_indices(name)
+        .find(|(start, _)| {
+            let before = line[..*start].chars().next_back();
+            let after = line[start + name.len()..].chars().next();
+            !before.is_some_and(is_identifier) && !after.is_some_and(is_identifier)
+        })
+        .map(|(start, _)| (start + 1, line[..start].chars().count() + 1))
+        .unwrap_or((0, 0))
+}
+
+// Where the address of a tag ends, either a line number or a search pattern
+// such as `/^fn main() {$/`, which may contain escaped delimiters or tabs
+fn address_end(address: &str) -> Option<usize> {
+    let delimiter = match address.as_bytes().first()? {
+        delimiter @ (b'/' | b'?') => *delimiter,
+        _ => return address.find(";\""),
+    };
+    let bytes = address.as_bytes();
+    let mut i = 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b if b == delimiter => return Some(i + 1),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+// Kinds by their full name (with --fields=+K) or their usual letter. Letters
+// vary between languages, so only the widespread meanings are kept
+fn type_kind(kind: &str, language: Option<&str>) -> Option<TypeKind> {
+    let type_kind = match kind {
+        "function" | "func" | "method" | "singletonMethod" | "constructor" | "procedure"
+        | "subroutine" | "f" | "P" => TypeKind::Function,
+        // Python calls methods members
+        "m" | "member" if language == Some("Python") => TypeKind::Function,
+        "class" | "c" => TypeKind::Class,
+        "struct" | "s" => TypeKind::Struct,
+        "enum" | "g" => TypeKind::Enum,
+        "interface" | "protocol" | "i" => TypeKind::Interface,
+        "trait" => TypeKind::Trait,
+        "implementation" => TypeKind::Impl,
+        "typedef" | "type" | "alias" | "typealias" | "t" => TypeKind::TypeAlias,
+        "constant" | "const" => TypeKind::Const,
+        "macro" | "define" | "d" | "M" => TypeKind::Macro,
+        "module" | "namespace" | "package" | "n" => TypeKind::Module,
+        "union" | "u" => TypeKind::Union,
+        "enumerator" | "enumConstant" | "variant" | "e" => TypeKind::Variant,
+        "field" | "member" | "property" | "m" => TypeKind::Field,
+        _ => return None,
+    };
+    Some(type_kind)
+}
+
+// "Outer::Inner" or "outer.Inner" are both owned by "Inner"
+fn last_segment(scope: &str) -> &str {
+    scope.rsplit(['.', ':']).next().unwrap_or(scope)
+}
+
+// Field values escape tabs, newlines and backslashes
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
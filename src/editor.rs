@@ -0,0 +1,67 @@
+use std::path::Path;
+
+// Editors that can be told the column to start at on their command line,
+// which `FileToOpen` has no room for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditorKind {
+    Vim, // And Neovim
+    Helix,
+    Kakoune,
+    Emacs,
+    Nano,
+    Micro,
+    VsCode,
+    Sublime,
+}
+
+// The `editor` setting: the command to run and how to pass it a position
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditorCommand {
+    pub command: String,
+    pub kind: EditorKind,
+}
+
+impl EditorCommand {
+    // Recognizes the editor by its executable, eg. "/usr/bin/nvim" or "hx"
+    pub fn from_config(value: &str) -> Option<Self> {
+        let command = value.trim();
+        let executable = Path::new(command).file_name()?.to_str()?;
+        let kind = match executable {
+            "vim" | "nvim" | "vi" => EditorKind::Vim,
+            "hx" | "helix" => EditorKind::Helix,
+            "kak" => EditorKind::Kakoune,
+            "emacs" | "emacsclient" => EditorKind::Emacs,
+            "nano" => EditorKind::Nano,
+            "micro" => EditorKind::Micro,
+            "code" | "codium" => EditorKind::VsCode,
+            "subl" => EditorKind::Sublime,
+            _ => return None,
+        };
+        Some(EditorCommand {
+            command: command.to_owned(),
+            kind,
+        })
+    }
+
+    // The arguments opening `file` with the cursor at `line`, and at the
+    // 1-based `column` in bytes or `char_column` in chars, whichever the
+    // editor counts in
+    pub fn args(&self, file: &Path, line: usize, column: usize, char_column: usize) -> Vec<String> {
+        let file = file.display().to_string();
+        match self.kind {
+            EditorKind::Vim => vec![format!("+call cursor({}, {})", line, column), file],
+            EditorKind::Kakoune => vec![format!("+{}:{}", line, column), file],
+            EditorKind::Emacs | EditorKind::Micro => {
+                vec![format!("+{}:{}", line, char_column), file]
+            }
+            EditorKind::Nano => vec![format!("+{},{}", line, char_column), file],
+            EditorKind::Helix | EditorKind::Sublime => {
+                vec![format!("{}:{}:{}", file, line, char_column)]
+            }
+            EditorKind::VsCode => vec![
+                "--goto".to_owned(),
+                format!("{}:{}:{}", file, line, char_column),
+            ],
+        }
+    }
+}
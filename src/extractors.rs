@@ -0,0 +1,715 @@
+use crate::files::{name_column, scan_with_bytes, TypeDefinition, TypeKind, Visibility};
+use crate::lexer::Lexer;
+use memchr::{memchr, memchr2, memmem};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+// Finds the definitions of one language in the contents of a file
+pub trait SymbolExtractor {
+    fn extract(
+        &self,
+        source: &[u8],
+        file_path: Rc<PathBuf>,
+        max_definitions: usize,
+    ) -> Vec<TypeDefinition>;
+
+    // Where a chunk of a large file can end so that scanning the rest on its
+    // own finds the same definitions: before the last line at column 0 that
+    // follows a blank line while nothing (a block, comment or string) is
+    // open. None if there is no such line
+    fn chunk_end(&self, source: &[u8]) -> Option<usize>;
+}
+
+// The extractor for the language of a file, chosen by its extension
+pub fn extractor_for(file_path: &Path) -> Option<&'static dyn SymbolExtractor> {
+    let extractor: &'static dyn SymbolExtractor = match file_path.extension()?.to_str()? {
+        "rs" => &RustExtractor,
+        "py" | "pyi" => &PythonExtractor,
+        "ts" | "tsx" | "mts" | "cts" | "js" | "jsx" | "mjs" | "cjs" => &TypeScriptExtractor,
+        "go" => &GoExtractor,
+        _ => return None,
+    };
+    Some(extractor)
+}
+
+pub struct RustExtractor;
+
+impl SymbolExtractor for RustExtractor {
+    fn extract(
+        &self,
+        source: &[u8],
+        file_path: Rc<PathBuf>,
+        max_definitions: usize,
+    ) -> Vec<TypeDefinition> {
+        scan_with_bytes(source, file_path, max_definitions).unwrap_or_default()
+    }
+
+    fn chunk_end(&self, source: &[u8]) -> Option<usize> {
+        let mut lexer = Lexer::default();
+        let mut tokens = Vec::new();
+        let mut depth = 0usize;
+        last_item_start(source, |line| {
+            let at_top_level = depth == 0;
+            let starts_in_code = lexer.lex_line(line, &mut tokens);
+            for &token in &tokens {
+                match token {
+                    b'{' => depth += 1,
+                    b'}' => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+            }
+            at_top_level && starts_in_code
+        })
+    }
+}
+
+// `def` and `class` statements, methods being the functions directly in a
+// class. Functions local to another function are left out
+pub struct PythonExtractor;
+
+impl SymbolExtractor for PythonExtractor {
+    fn extract(
+        &self,
+        source: &[u8],
+        file_path: Rc<PathBuf>,
+        max_definitions: usize,
+    ) -> Vec<TypeDefinition> {
+        let mut definitions = Vec::new();
+        // The `def` and `class` blocks a line is in, with their indentation.
+        // Classes keep their name, to own their methods
+        let mut scopes: Vec<(usize, Option<Rc<str>>)> = Vec::new();
+        let mut open_string: Option<&[u8]> = None; // The quotes closing a multi-line string
+        for (line_number, line) in numbered_lines(source) {
+            if definitions.len() >= max_definitions {
+                break;
+            }
+            if let Some(quotes) = open_string {
+                if memmem::find(line, quotes).is_some() {
+                    open_string = None;
+                }
+                continue;
+            }
+            let trimmed = trim_start(line);
+            if trimmed.is_empty() || trimmed.starts_with(b"#") {
+                continue;
+            }
+            open_string = opened_python_string(line);
+
+            let indent = line.len() - trimmed.len();
+            while scopes
+                .last()
+                .is_some_and(|(scope_indent, _)| *scope_indent >= indent)
+            {
+                scopes.pop();
+            }
+            let statement = after_keyword(trimmed, b"async").unwrap_or(trimmed);
+            let (type_kind, rest) = if let Some(rest) = after_keyword(statement, b"def") {
+                (TypeKind::Function, rest)
+            } else if let Some(rest) = after_keyword(statement, b"class") {
+                (TypeKind::Class, rest)
+            } else {
+                continue;
+            };
+            let name = match leading_identifier(rest) {
+                Some(name) => name,
+                None => continue,
+            };
+            let in_function = scopes.iter().any(|(_, class)| class.is_none());
+            let owner = scopes.last().and_then(|(_, class)| class.clone());
+            scopes.push((indent, (type_kind == TypeKind::Class).then(|| name.into())));
+            if in_function {
+                continue;
+            }
+
+            // Leading underscores mark private names, but not `__dunder__` ones
+            let visibility = if name.starts_with('_') && !name.ends_with("__") {
+                Visibility::Private
+            } else {
+                Visibility::Public
+            };
+            let mut definition = definition(
+                type_kind,
+                name,
+                &file_path,
+                line_number,
+                line,
+                rest,
+                visibility,
+            );
+            definition.owner = owner;
+            definition.signature = line_signature(&rest[name.len()..], b':');
+            definitions.push(definition);
+        }
+        definitions
+    }
+
+    fn chunk_end(&self, source: &[u8]) -> Option<usize> {
+        let mut open_string: Option<&[u8]> = None;
+        last_item_start(source, |line| match open_string {
+            Some(quotes) => {
+                if memmem::find(line, quotes).is_some() {
+                    open_string = None;
+                }
+                false
+            }
+            None => {
+                if !trim_start(line).starts_with(b"#") {
+                    open_string = opened_python_string(line);
+                }
+                true
+            }
+        })
+    }
+}
+
+// The quotes closing a triple-quoted string left open at the end of `line`
+fn opened_python_string(line: &[u8]) -> Option<&'static [u8]> {
+    [&b"\"\"\""[..], b"'''"]
+        .iter()
+        .copied()
+        .find(|quotes| memmem::find_iter(line, quotes).count() % 2 == 1)
+}
+
+// `function`, `class`, `interface`, `type` and `enum` declarations, and the
+// methods of classes. Exported ones are public
+pub struct TypeScriptExtractor;
+
+const TS_MODIFIERS: [&[u8]; 5] = [b"export", b"default", b"declare", b"abstract", b"async"];
+const TS_MEMBER_MODIFIERS: [&[u8]; 10] = [
+    b"public",
+    b"private",
+    b"protected",
+    b"static",
+    b"readonly",
+    b"async",
+    b"override",
+    b"abstract",
+    b"get",
+    b"set",
+];
+
+impl SymbolExtractor for TypeScriptExtractor {
+    fn extract(
+        &self,
+        source: &[u8],
+        file_path: Rc<PathBuf>,
+        max_definitions: usize,
+    ) -> Vec<TypeDefinition> {
+        let mut definitions: Vec<TypeDefinition> = Vec::new();
+        let mut lexer = CLikeLexer::new(true);
+        let mut braces = Vec::new();
+        let mut depth = 0;
+        let mut classes: Vec<(usize, Rc<str>)> = Vec::new(); // Open class bodies and the depth inside them
+        let mut pending_class: Option<Rc<str>> = None; // A class whose body hasn't opened yet
+        for (line_number, line) in numbered_lines(source) {
+            if definitions.len() >= max_definitions {
+                break;
+            }
+            let starts_in_code = lexer.lex_line(line, &mut braces);
+            let trimmed = trim_start(line);
+            if starts_in_code && !trimmed.is_empty() {
+                let class = classes
+                    .last()
+                    .filter(|(body_depth, _)| *body_depth == depth)
+                    .map(|(_, name)| Rc::clone(name));
+                let found = match class {
+                    Some(owner) => ts_method(line, trimmed, &file_path, line_number, owner),
+                    None => ts_declaration(line, trimmed, &file_path, line_number),
+                };
+                if let Some(definition) = found {
+                    if definition.type_kind == TypeKind::Class {
+                        pending_class = Some(definition.name.as_str().into());
+                    }
+                    definitions.push(definition);
+                }
+            }
+            for &brace in &braces {
+                if brace == b'{' {
+                    depth += 1;
+                    if let Some(name) = pending_class.take() {
+                        classes.push((depth, name));
+                    }
+                } else {
+                    depth = depth.saturating_sub(1);
+                    while classes
+                        .last()
+                        .is_some_and(|(body_depth, _)| *body_depth > depth)
+                    {
+                        classes.pop();
+                    }
+                }
+            }
+        }
+        definitions
+    }
+
+    fn chunk_end(&self, source: &[u8]) -> Option<usize> {
+        c_like_chunk_end(source, CLikeLexer::new(true))
+    }
+}
+
+fn ts_declaration(
+    line: &[u8],
+    trimmed: &[u8],
+    file_path: &Rc<PathBuf>,
+    line_number: usize,
+) -> Option<TypeDefinition> {
+    let mut rest = trimmed;
+    let mut exported = false;
+    while let Some((modifier, after)) = TS_MODIFIERS
+        .iter()
+        .find_map(|&modifier| after_keyword(rest, modifier).map(|after| (modifier, after)))
+    {
+        exported |= modifier == b"export";
+        rest = after;
+    }
+
+    let (type_kind, rest) = if let Some(rest) =
+        after_keyword(rest, b"function").or_else(|| rest.strip_prefix(b"function*").map(trim_start))
+    {
+        (TypeKind::Function, rest)
+    } else if let Some(rest) = after_keyword(rest, b"class") {
+        (TypeKind::Class, rest)
+    } else if let Some(rest) = after_keyword(rest, b"interface") {
+        (TypeKind::Interface, rest)
+    } else if let Some(rest) = after_keyword(rest, b"type") {
+        (TypeKind::TypeAlias, rest)
+    } else if let Some(rest) = after_keyword(rest, b"enum")
+        .or_else(|| after_keyword(rest, b"const").and_then(|rest| after_keyword(rest, b"enum")))
+    {
+        (TypeKind::Enum, rest)
+    } else {
+        return None;
+    };
+    let name = leading_identifier(rest)?;
+    let after_name = &rest[name.len()..];
+    // `type` is a common name too, eg. `type = "text"`
+    if type_kind == TypeKind::TypeAlias
+        && !matches!(trim_start(after_name).first(), Some(b'=' | b'<'))
+    {
+        return None;
+    }
+
+    let visibility = if exported {
+        Visibility::Public
+    } else {
+        Visibility::Private
+    };
+    let mut definition = definition(
+        type_kind,
+        name,
+        file_path,
+        line_number,
+        line,
+        rest,
+        visibility,
+    );
+    if definition.type_kind == TypeKind::Function {
+        definition.signature = line_signature(after_name, b'{');
+    }
+    Some(definition)
+}
+
+// A method directly in a class body, eg. `private async load(id: string) {`,
+// but not a field
+fn ts_method(
+    line: &[u8],
+    trimmed: &[u8],
+    file_path: &Rc<PathBuf>,
+    line_number: usize,
+    owner: Rc<str>,
+) -> Option<TypeDefinition> {
+    let mut rest = trimmed;
+    let mut private = false;
+    while let Some((modifier, after)) = TS_MEMBER_MODIFIERS
+        .iter()
+        .find_map(|&modifier| after_keyword(rest, modifier).map(|after| (modifier, after)))
+    {
+        private |= modifier == b"private" || modifier == b"protected";
+        rest = after;
+    }
+    // The `*` of a generator
+    let rest = rest.strip_prefix(b"*").unwrap_or(rest);
+    // `#name` is private to the class
+    let (rest, private) = match rest.strip_prefix(b"#") {
+        Some(rest) => (rest, true),
+        None => (rest, private),
+    };
+    let name = leading_identifier(rest)?;
+    let after_name = &rest[name.len()..];
+    if !matches!(trim_start(after_name).first(), Some(b'(' | b'<')) {
+        return None;
+    }
+
+    let visibility = if private {
+        Visibility::Private
+    } else {
+        Visibility::Public
+    };
+    let mut definition = definition(
+        TypeKind::Function,
+        name,
+        file_path,
+        line_number,
+        line,
+        rest,
+        visibility,
+    );
+    definition.owner = Some(owner);
+    definition.signature = line_signature(after_name, b'{');
+    Some(definition)
+}
+
+// `func` and `type` declarations, including those of `type ( .. )` groups.
+// Methods are owned by their receiver's type. Capitalized names are exported,
+// so public
+pub struct GoExtractor;
+
+impl SymbolExtractor for GoExtractor {
+    fn extract(
+        &self,
+        source: &[u8],
+        file_path: Rc<PathBuf>,
+        max_definitions: usize,
+    ) -> Vec<TypeDefinition> {
+        let mut definitions = Vec::new();
+        let mut lexer = CLikeLexer::new(false);
+        let mut braces = Vec::new();
+        let mut depth = 0;
+        let mut type_group: Option<usize> = None; // The depth of an open `type (` group
+        for (line_number, line) in numbered_lines(source) {
+            if definitions.len() >= max_definitions {
+                break;
+            }
+            let starts_in_code = lexer.lex_line(line, &mut braces);
+            let trimmed = trim_start(line);
+            if starts_in_code && !trimmed.is_empty() {
+                let found = if type_group == Some(depth) {
+                    if trimmed.starts_with(b")") {
+                        type_group = None;
+                        None
+                    } else {
+                        go_type_spec(line, trimmed, &file_path, line_number)
+                    }
+                } else if let Some(rest) = after_keyword(trimmed, b"type") {
+                    if rest.starts_with(b"(") {
+                        type_group = Some(depth);
+                        None
+                    } else {
+                        go_type_spec(line, rest, &file_path, line_number)
+                    }
+                } else if let Some(rest) = after_keyword(trimmed, b"func") {
+                    go_func(line, rest, &file_path, line_number)
+                } else {
+                    None
+                };
+                definitions.extend(found);
+            }
+            for &brace in &braces {
+                if brace == b'{' {
+                    depth += 1;
+                } else {
+                    depth = depth.saturating_sub(1);
+                }
+            }
+        }
+        definitions
+    }
+
+    fn chunk_end(&self, source: &[u8]) -> Option<usize> {
+        c_like_chunk_end(source, CLikeLexer::new(false))
+    }
+}
+
+// `Name struct {`, `Name interface {`, `Name = Other` or `Name[T any] Other`
+fn go_type_spec(
+    line: &[u8],
+    spec: &[u8],
+    file_path: &Rc<PathBuf>,
+    line_number: usize,
+) -> Option<TypeDefinition> {
+    let name = leading_identifier(spec)?;
+    let mut after_name = trim_start(&spec[name.len()..]);
+    if after_name.starts_with(b"[") {
+        // Type parameters
+        let close = memchr(b']', after_name)?;
+        after_name = trim_start(&after_name[close + 1..]);
+    }
+    let type_kind = if starts_with_word(after_name, b"struct") {
+        TypeKind::Struct
+    } else if starts_with_word(after_name, b"interface") {
+        TypeKind::Interface
+    } else {
+        TypeKind::TypeAlias
+    };
+    Some(definition(
+        type_kind,
+        name,
+        file_path,
+        line_number,
+        line,
+        spec,
+        go_visibility(name),
+    ))
+}
+
+// `Name(..)`, or a method's `(r *Receiver) Name(..)`
+fn go_func(
+    line: &[u8],
+    rest: &[u8],
+    file_path: &Rc<PathBuf>,
+    line_number: usize,
+) -> Option<TypeDefinition> {
+    let (owner, rest) = if rest.starts_with(b"(") {
+        let close = memchr(b')', rest)?;
+        let receiver = &rest[1..close];
+        // `s *Server[K, V]` is a method of Server
+        let receiver = &receiver[..memchr(b'[', receiver).unwrap_or(receiver.len())];
+        let receiver = receiver.trim_ascii_end();
+        let type_start = receiver
+            .iter()
+            .rposition(|&b| b == b' ' || b == b'*')
+            .map_or(0, |i| i + 1);
+        let owner = leading_identifier(&receiver[type_start..])?;
+        (Some(Rc::from(owner)), trim_start(&rest[close + 1..]))
+    } else {
+        (None, rest)
+    };
+    let name = leading_identifier(rest)?;
+    let mut definition = definition(
+        TypeKind::Function,
+        name,
+        file_path,
+        line_number,
+        line,
+        rest,
+        go_visibility(name),
+    );
+    definition.owner = owner;
+    definition.signature = line_signature(&rest[name.len()..], b'{');
+    Some(definition)
+}
+
+fn go_visibility(name: &str) -> Visibility {
+    if name.starts_with(|c: char| c.is_uppercase()) {
+        Visibility::Public
+    } else {
+        Visibility::Private
+    }
+}
+
+// Skips the comments and strings of TypeScript, JavaScript and Go one line
+// at a time, like the Rust lexer does
+struct CLikeLexer {
+    in_block_comment: bool,
+    in_backticks: bool,         // A template literal, or a Go raw string
+    escapes_in_backticks: bool, // Template literals have escapes, raw strings don't
+}
+
+impl CLikeLexer {
+    fn new(escapes_in_backticks: bool) -> Self {
+        CLikeLexer {
+            in_block_comment: false,
+            in_backticks: false,
+            escapes_in_backticks,
+        }
+    }
+
+    // Collects the braces of `line` that are code into `braces`. Returns
+    // whether the line started as code, and not inside a comment or string
+    fn lex_line(&mut self, line: &[u8], braces: &mut Vec<u8>) -> bool {
+        braces.clear();
+        let starts_in_code = !self.in_block_comment && !self.in_backticks;
+        let mut i = 0;
+        while i < line.len() {
+            if self.in_block_comment {
+                match memmem::find(&line[i..], b"*/") {
+                    Some(end) => {
+                        self.in_block_comment = false;
+                        i += end + 2;
+                    }
+                    None => break,
+                }
+                continue;
+            }
+            if self.in_backticks {
+                let escape = if self.escapes_in_backticks {
+                    b'\\'
+                } else {
+                    b'`'
+                };
+                match memchr2(b'`', escape, &line[i..]) {
+                    Some(offset) if line[i + offset] == b'\\' => i += offset + 2,
+                    Some(offset) => {
+                        self.in_backticks = false;
+                        i += offset + 1;
+                    }
+                    None => break,
+                }
+                continue;
+            }
+            match line[i] {
+                b'/' if line.get(i + 1) == Some(&b'/') => break,
+                b'/' if line.get(i + 1) == Some(&b'*') => {
+                    self.in_block_comment = true;
+                    i += 2;
+                }
+                b'`' => {
+                    self.in_backticks = true;
+                    i += 1;
+                }
+                quote @ (b'"' | b'\'') => i = skip_string(line, i, quote),
+                brace @ (b'{' | b'}') => {
+                    braces.push(brace);
+                    i += 1;
+                }
+                _ => i += 1,
+            }
+        }
+        starts_in_code
+    }
+}
+
+// Where a single-line string opening at `i` ends
+fn skip_string(line: &[u8], i: usize, quote: u8) -> usize {
+    let mut j = i + 1;
+    while j < line.len() {
+        match line[j] {
+            b'\\' => j += 2,
+            b if b == quote => return j + 1,
+            _ => j += 1,
+        }
+    }
+    line.len()
+}
+
+fn c_like_chunk_end(source: &[u8], mut lexer: CLikeLexer) -> Option<usize> {
+    let mut braces = Vec::new();
+    let mut depth = 0usize;
+    last_item_start(source, |line| {
+        let at_top_level = depth == 0;
+        let starts_in_code = lexer.lex_line(line, &mut braces);
+        for &brace in &braces {
+            if brace == b'{' {
+                depth += 1;
+            } else {
+                depth = depth.saturating_sub(1);
+            }
+        }
+        at_top_level && starts_in_code
+    })
+}
+
+// The start of the last line of `source` at column 0 that follows a blank
+// line, among those `at_top_level` accepts. It is called on every line in
+// turn, to tell whether the scanner has nothing open where the line starts
+fn last_item_start(source: &[u8], mut at_top_level: impl FnMut(&[u8]) -> bool) -> Option<usize> {
+    let mut item_start = None;
+    let mut line_start = 0;
+    let mut follows_blank_line = false;
+    for line in source.split(|&b| b == b'\n') {
+        let next_line_start = line_start + line.len() + 1;
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let starts_item = line.first().is_some_and(|b| !b.is_ascii_whitespace());
+        if at_top_level(line) && starts_item && follows_blank_line {
+            item_start = Some(line_start);
+        }
+        follows_blank_line = line.iter().all(|b| b.is_ascii_whitespace());
+        line_start = next_line_start;
+    }
+    item_start
+}
+
+// The lines of a file with their 1-based number, without line endings
+fn numbered_lines(source: &[u8]) -> impl Iterator<Item = (usize, &[u8])> {
+    source
+        .split(|&b| b == b'\n')
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.strip_suffix(b"\r").unwrap_or(line)))
+}
+
+// A definition whose name starts `rest`, the end of `line`
+fn definition(
+    type_kind: TypeKind,
+    name: &str,
+    file_path: &Rc<PathBuf>,
+    line_number: usize,
+    line: &[u8],
+    rest: &[u8],
+    visibility: Visibility,
+) -> TypeDefinition {
+    let (column, char_column) = name_column(line, rest, name);
+    TypeDefinition {
+        visibility,
+        column,
+        char_column,
+        ..TypeDefinition::new(
+            type_kind,
+            name.to_owned(),
+            Rc::clone(file_path),
+            line_number,
+        )
+    }
+}
+
+// What follows a name on its line up to the body, eg. "(a, b) -> int" of a
+// Python `def f(a, b) -> int:`. Empty when the parameters go on to the next
+// lines
+fn line_signature(after_name: &[u8], body_start: u8) -> String {
+    let mut depth = 0i32;
+    let mut end = after_name.len();
+    for (i, &b) in after_name.iter().enumerate() {
+        match b {
+            _ if b == body_start && depth == 0 => {
+                end = i;
+                break;
+            }
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => depth -= 1,
+            _ => {}
+        }
+    }
+    if depth != 0 {
+        return String::new();
+    }
+    String::from_utf8_lossy(&after_name[..end])
+        .trim()
+        .trim_end_matches(';')
+        .to_owned()
+}
+
+// The identifier at the start of `bytes`, `$` being part of JavaScript ones
+fn leading_identifier(bytes: &[u8]) -> Option<&str> {
+    let end = bytes
+        .iter()
+        .position(|&b| !(b.is_ascii_alphanumeric() || b == b'_' || b == b'$' || b >= 0x80))
+        .unwrap_or(bytes.len());
+    let identifier = std::str::from_utf8(&bytes[..end]).ok()?;
+    let starts_well = identifier
+        .chars()
+        .next()
+        .is_some_and(|c| !c.is_ascii_digit());
+    starts_well.then_some(identifier)
+}
+
+// The rest of `line` after `keyword` and the whitespace that has to follow it
+fn after_keyword<'a>(line: &'a [u8], keyword: &[u8]) -> Option<&'a [u8]> {
+    let rest = line.strip_prefix(keyword)?;
+    let rest_trimmed = trim_start(rest);
+    (rest_trimmed.len() < rest.len()).then_some(rest_trimmed)
+}
+
+fn starts_with_word(bytes: &[u8], word: &[u8]) -> bool {
+    bytes.starts_with(word)
+        && !bytes
+            .get(word.len())
+            .is_some_and(|&b| b.is_ascii_alphanumeric() || b == b'_')
+}
+
+fn trim_start(bytes: &[u8]) -> &[u8] {
+    let start = bytes
+        .iter()
+        .position(|&b| b != b' ' && b != b'\t')
+        .unwrap_or(bytes.len());
+    &bytes[start..]
+}
@@ -1,51 +1,486 @@
-use memchr::memchr;
+use crate::config::ScanConfig;
+use crate::extractors::{extractor_for, SymbolExtractor};
+use crate::ignore::{IgnoreMatch, IgnoreStack};
+use crate::lexer::Lexer;
+use memchr::{memchr, memchr_iter, memmem, memrchr};
+use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, VecDeque};
+use std::convert::TryFrom;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TypeDefinition {
     pub type_kind: TypeKind,
     pub name: String,
     pub file_path: Rc<PathBuf>,
     pub line_number: usize,
+    #[serde(default)]
+    pub crate_name: Option<Rc<str>>, // Set once the file is added to the index
+    #[serde(default)]
+    pub qualifiers: FnQualifiers,
+    // Self type of the impl block of a method, or the enum or struct of a
+    // variant or field
+    #[serde(default)]
+    pub owner: Option<Rc<str>>,
+    #[serde(default)]
+    pub children: Vec<TypeDefinition>, // Variants of an enum, named fields of a struct or union
+    // A `#[test]` function, or a `#[cfg(test)]` module
+    #[serde(default)]
+    pub is_test: bool,
+    // The inline modules the item is declared in within its file, eg. "tests"
+    #[serde(default)]
+    pub module_path: Option<Rc<str>>,
+    #[serde(default)]
+    pub visibility: Visibility,
+    // What follows the name up to the body, eg. "<T>(value: T) -> Option<T>"
+    // for functions or the generics of a type, empty for other items
+    #[serde(default)]
+    pub signature: String,
+    // Its `///` doc comment, or the `//!` one inside an inline module, without
+    // the comment markers
+    #[serde(default)]
+    pub docs: String,
+    // The module of its file, eg. "ui::table" for src/ui/table.rs, empty for
+    // crate roots. Set once the file is added to the index
+    #[serde(default)]
+    pub file_module: Option<Rc<str>>,
+    // The file a `#[path = "..."]` attribute points an out-of-line module to,
+    // relative to the directory of the declaring file
+    #[serde(default)]
+    pub path_attribute: Option<String>,
+    // Where the name starts on its line, 1-based like `line_number`, in bytes
+    // and in chars. 0 if unknown
+    #[serde(default)]
+    pub column: usize,
+    #[serde(default)]
+    pub char_column: usize,
+    #[serde(default)]
+    pub source: SymbolSource,
+    // Found in a file that was only partly scanned, eg. because of a line
+    // longer than a whole chunk, so it may be missing definitions
+    #[serde(default)]
+    pub partial_scan: bool,
 }
 
-#[derive(Debug, Clone)]
+impl TypeDefinition {
+    // A scanned definition with every optional field left empty
+    pub fn new(
+        type_kind: TypeKind,
+        name: String,
+        file_path: Rc<PathBuf>,
+        line_number: usize,
+    ) -> Self {
+        TypeDefinition {
+            type_kind,
+            name,
+            file_path,
+            line_number,
+            crate_name: None,
+            qualifiers: FnQualifiers::default(),
+            owner: None,
+            children: Vec::new(),
+            is_test: false,
+            module_path: None,
+            visibility: Visibility::default(),
+            signature: String::new(),
+            docs: String::new(),
+            file_module: None,
+            path_attribute: None,
+            column: 0,
+            char_column: 0,
+            source: SymbolSource::Scanner,
+            partial_scan: false,
+        }
+    }
+
+    // The full path of the item within its crate, eg. "ui::table::Type::method"
+    pub fn qualified_name(&self) -> String {
+        format!("{}{}", self.path_prefix(), self.name)
+    }
+
+    // The modules the item is in, eg. "ui::table::tests", if any
+    pub fn full_module_path(&self) -> Option<String> {
+        match (&self.file_module, &self.module_path) {
+            (Some(file_module), Some(module_path)) => {
+                Some(format!("{}::{}", file_module, module_path))
+            }
+            (Some(path), None) | (None, Some(path)) => Some(path.to_string()),
+            (None, None) => None,
+        }
+    }
+
+    // Everything `qualified_name` puts in front of the bare name
+    fn path_prefix(&self) -> String {
+        let mut prefix = String::new();
+        if let Some(module_path) = self.full_module_path() {
+            prefix.push_str(&module_path);
+            prefix.push_str("::");
+        }
+        if let Some(owner) = &self.owner {
+            prefix.push_str(owner);
+            prefix.push_str("::");
+        }
+        prefix
+    }
+
+    pub fn set_crate_name(&mut self, crate_name: Option<Rc<str>>) {
+        for child in &mut self.children {
+            child.set_crate_name(crate_name.clone());
+        }
+        self.crate_name = crate_name;
+    }
+
+    pub fn set_file_module(&mut self, file_module: Option<Rc<str>>) {
+        for child in &mut self.children {
+            child.set_file_module(file_module.clone());
+        }
+        self.file_module = file_module;
+    }
+
+    pub fn set_file_path(&mut self, file_path: &Rc<PathBuf>) {
+        for child in &mut self.children {
+            child.set_file_path(file_path);
+        }
+        self.file_path = Rc::clone(file_path);
+    }
+
+    // Moves it `offset` lines down, for definitions found in a chunk of a file
+    fn shift_lines(&mut self, offset: usize) {
+        for child in &mut self.children {
+            child.shift_lines(offset);
+        }
+        self.line_number += offset;
+    }
+
+    fn mark_partial_scan(&mut self) {
+        for child in &mut self.children {
+            child.mark_partial_scan();
+        }
+        self.partial_scan = true;
+    }
+
+    // The first line of the docs, which usually sums the item up
+    pub fn doc_summary(&self) -> &str {
+        self.docs.lines().next().unwrap_or("")
+    }
+
+    // Where the bare name starts in `qualified_name`, in chars
+    pub fn name_offset(&self) -> usize {
+        self.path_prefix().chars().count()
+    }
+
+    // Where "Type::method" starts in `qualified_name`, in chars
+    pub fn module_offset(&self) -> usize {
+        self.full_module_path()
+            .map(|module_path| module_path.chars().count() + 2)
+            .unwrap_or(0)
+    }
+}
+
+// Qualifiers written between a function's visibility and `fn`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FnQualifiers {
+    pub is_default: bool,
+    pub is_const: bool,
+    pub is_async: bool,
+    pub is_unsafe: bool,
+    pub extern_abi: Option<String>, // "C" for a bare `extern`
+}
+
+impl FnQualifiers {
+    pub fn is_empty(&self) -> bool {
+        *self == FnQualifiers::default()
+    }
+
+    // In the order Rust requires them, eg. ["const", "unsafe", "extern \"C\""]
+    pub fn keywords(&self) -> Vec<String> {
+        let mut keywords = Vec::new();
+        for (is_set, keyword) in [
+            (self.is_default, "default"),
+            (self.is_const, "const"),
+            (self.is_async, "async"),
+            (self.is_unsafe, "unsafe"),
+        ] {
+            if is_set {
+                keywords.push(keyword.to_owned());
+            }
+        }
+        if let Some(abi) = &self.extern_abi {
+            keywords.push(format!("extern \"{}\"", abi));
+        }
+        keywords
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Visibility {
+    #[default]
+    Private, // Also `pub(self)`
+    Public,
+    Crate,
+    Super,
+    In(String), // `pub(in path)`
+}
+
+// Where a definition was read from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SymbolSource {
+    #[default]
+    Scanner, // The built-in extractors
+    Ctags,
+    // A SCIP or LSIF index, whose module paths are complete and whose
+    // positions come from the compiler
+    Index,
+}
+
+impl Visibility {
+    // As it is written, eg. "pub(crate)", None for private items
+    pub fn keyword(&self) -> Option<String> {
+        match self {
+            Visibility::Private => None,
+            Visibility::Public => Some("pub".to_owned()),
+            Visibility::Crate => Some("pub(crate)".to_owned()),
+            Visibility::Super => Some("pub(super)".to_owned()),
+            Visibility::In(path) => Some(format!("pub(in {})", path)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TypeKind {
     Struct,
     Enum,
     Function,
+    Trait,
+    Impl,
+    TypeAlias,
+    Const,
+    Static,
+    Macro,
+    Module,
+    Union,
+    Variant,   // Child of an enum
+    Field,     // Child of a struct or union
+    Class,     // Python, TypeScript and JavaScript
+    Interface, // TypeScript and Go
 }
 
-pub fn get_all_files<P: AsRef<std::path::Path>>(
-    dir: P,
-) -> std::io::Result<BTreeMap<PathBuf, Vec<TypeDefinition>>> {
-    let mut files = Vec::with_capacity(1000);
-    let mut queue = VecDeque::new();
-    queue.push_back(dir.as_ref().to_path_buf());
+// Safety limit for the number of indexed files, overridable through the
+// `file_limit` plugin configuration
+pub const DEFAULT_FILE_LIMIT: usize = 100_000;
+pub const INDEX_BATCH_SIZE: usize = 500;
 
-    while let Some(current_dir) = queue.pop_front() {
-        if files.len() >= 1000 {
-            break;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IndexSource {
+    #[default]
+    Walk, // Walk the folder, honoring ignore files
+    Git, // Ask `git ls-files` for tracked and untracked, non-ignored files
+}
+
+impl IndexSource {
+    pub fn from_config(value: &str) -> Option<Self> {
+        match value {
+            "walk" => Some(IndexSource::Walk),
+            "git" => Some(IndexSource::Git),
+            _ => None,
         }
+    }
+}
 
-        let entries = match fs::read_dir(&current_dir) {
-            Ok(entries) => entries,
-            Err(_) => continue,
-        };
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SymlinkPolicy {
+    Skip,   // Never index symlinks or anything below them
+    Follow, // Follow every symlink that doesn't lead back into the current path
+    #[default]
+    FollowOnce, // Follow symlinks to targets that aren't indexed under another name
+}
 
-        let mut dirs_in_level = Vec::new();
+impl SymlinkPolicy {
+    pub fn from_config(value: &str) -> Option<Self> {
+        match value {
+            "skip" => Some(SymlinkPolicy::Skip),
+            "follow" => Some(SymlinkPolicy::Follow),
+            "follow_once" => Some(SymlinkPolicy::FollowOnce),
+            _ => None,
+        }
+    }
+}
 
-        for entry in entries {
-            if files.len() >= 1000 {
-                break;
+struct PendingDir {
+    path: PathBuf,
+    ignores: Rc<IgnoreStack>,
+    // Canonical targets of the symlinks followed to reach this directory
+    symlink_targets: Rc<Vec<PathBuf>>,
+}
+
+// Walks the project breadth-first in batches, so indexing can be spread over
+// several plugin events and resumed where the previous batch stopped
+pub struct FileIndexer {
+    root: PathBuf,
+    canonical_root: PathBuf,
+    queue: VecDeque<PendingDir>,
+    current_dir: Option<(fs::ReadDir, PendingDir)>,
+    listed_files: VecDeque<PathBuf>, // Files known upfront, eg. from git
+    use_fallback_ignores: bool,
+    config: ScanConfig,
+    indexed_count: usize,
+    truncated: bool,
+    followed_targets: Vec<PathBuf>, // Canonical targets already indexed once
+    symlinked_files: Vec<PathBuf>,  // Reached through a symlink, since the last take
+}
+
+impl FileIndexer {
+    pub fn new<P: AsRef<Path>>(root: P, config: &ScanConfig) -> Self {
+        Self::for_directory(root, Path::new(""), config)
+    }
+
+    // Only walks `dir` (relative to `root`), applying the ignore files of the
+    // whole project, eg. to index a folder that appeared after the initial scan
+    pub fn for_directory<P: AsRef<Path>>(root: P, dir: &Path, config: &ScanConfig) -> Self {
+        let root = root.as_ref().to_path_buf();
+        // The hard-coded list is only used when the project has no ignore files
+        let use_fallback_ignores = IgnoreStack::for_root(&root).is_empty();
+
+        let mut queue = VecDeque::new();
+        queue.push_back(PendingDir {
+            path: root.join(dir),
+            ignores: IgnoreStack::for_directory(&root, dir, &config.ignore_patterns),
+            symlink_targets: Rc::new(Vec::new()),
+        });
+
+        FileIndexer {
+            canonical_root: fs::canonicalize(&root).unwrap_or_else(|_| root.clone()),
+            root,
+            queue,
+            current_dir: None,
+            listed_files: VecDeque::new(),
+            use_fallback_ignores,
+            config: config.clone(),
+            indexed_count: 0,
+            truncated: false,
+            followed_targets: Vec::new(),
+            symlinked_files: Vec::new(),
+        }
+    }
+
+    // Indexes a list of files (relative to `root`) instead of walking the
+    // folder, files that no longer exist or match `ignore_patterns` are skipped
+    pub fn from_file_list<P: AsRef<Path>>(
+        root: P,
+        files: Vec<PathBuf>,
+        config: &ScanConfig,
+    ) -> Self {
+        let ignores = Rc::new(IgnoreStack::default()).with_patterns(&config.ignore_patterns);
+        let listed_files = files
+            .into_iter()
+            .filter(|file| !ignores.is_ignored(file))
+            .collect();
+        let root = root.as_ref().to_path_buf();
+        FileIndexer {
+            canonical_root: fs::canonicalize(&root).unwrap_or_else(|_| root.clone()),
+            root,
+            queue: VecDeque::new(),
+            current_dir: None,
+            listed_files,
+            use_fallback_ignores: false,
+            config: config.clone(),
+            indexed_count: 0,
+            truncated: false,
+            followed_targets: Vec::new(),
+            symlinked_files: Vec::new(),
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.truncated
+            || (self.current_dir.is_none() && self.queue.is_empty() && self.listed_files.is_empty())
+    }
+
+    // True if the walk stopped at the file limit before covering the whole tree
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    pub fn indexed_count(&self) -> usize {
+        self.indexed_count
+    }
+
+    pub fn file_limit(&self) -> usize {
+        self.config.file_limit
+    }
+
+    pub fn scan_config(&self) -> &ScanConfig {
+        &self.config
+    }
+
+    // Files returned so far that were reached through a symlink
+    pub fn take_symlinked_files(&mut self) -> Vec<PathBuf> {
+        std::mem::take(&mut self.symlinked_files)
+    }
+
+    // Finds up to `batch_size` more files and scans the Rust ones among them
+    pub fn next_batch(&mut self, batch_size: usize) -> BTreeMap<PathBuf, Vec<TypeDefinition>> {
+        let files = self.next_files(batch_size);
+
+        files
+            .into_iter()
+            .map(|file_path| {
+                let definitions = scan_file_definitions(&file_path, &self.config);
+                (file_path, definitions)
+            })
+            .collect()
+    }
+
+    // Finds up to `batch_size` more files without scanning them
+    pub fn next_files(&mut self, batch_size: usize) -> Vec<PathBuf> {
+        let mut files = Vec::with_capacity(batch_size);
+
+        while files.len() < batch_size && !self.is_done() {
+            if let Some(file) = self.listed_files.pop_front() {
+                let path = self.root.join(&file);
+                if !path.is_file() {
+                    continue;
+                }
+                let is_symlink = path.is_symlink();
+                if is_symlink && self.follow_symlink(&path, &[]).is_none() {
+                    continue;
+                }
+                if self.indexed_count >= self.config.file_limit {
+                    self.truncated = true;
+                    break;
+                }
+                if is_symlink {
+                    self.symlinked_files.push(file.clone());
+                }
+                files.push(file);
+                self.indexed_count += 1;
+                continue;
             }
 
-            let entry = match entry {
-                Ok(entry) => entry,
-                Err(_) => continue,
+            let (entries, dir) = match self.current_dir.as_mut() {
+                Some(current_dir) => current_dir,
+                None => {
+                    let dir = match self.queue.pop_front() {
+                        Some(next) => next,
+                        None => break,
+                    };
+                    if let Ok(entries) = fs::read_dir(&dir.path) {
+                        self.current_dir = Some((entries, dir));
+                    }
+                    continue;
+                }
+            };
+
+            let entry = match entries.next() {
+                Some(Ok(entry)) => entry,
+                Some(Err(_)) => continue,
+                None => {
+                    self.current_dir = None;
+                    continue;
+                }
             };
 
             let path = entry.path();
@@ -54,11 +489,37 @@ pub fn get_all_files<P: AsRef<std::path::Path>>(
                 None => continue,
             };
 
-            if should_ignore(file_name) {
+            if file_name == ".git" || (self.use_fallback_ignores && should_ignore(file_name)) {
                 continue;
             }
 
+            let is_symlink = entry
+                .file_type()
+                .is_ok_and(|file_type| file_type.is_symlink());
+            let is_dir = path.is_dir();
+            let relative_path = path.strip_prefix(&self.root).unwrap_or(&path);
+            if dir.ignores.matched(relative_path, is_dir) == IgnoreMatch::Ignore {
+                continue;
+            }
+
+            let ignores = Rc::clone(&dir.ignores);
+            let mut symlink_targets = Rc::clone(&dir.symlink_targets);
+            if is_symlink {
+                let target = match self.follow_symlink(&path, &symlink_targets) {
+                    Some(target) => target,
+                    None => continue,
+                };
+                let mut targets = symlink_targets.to_vec();
+                targets.push(target);
+                symlink_targets = Rc::new(targets);
+            }
+            let via_symlink = !symlink_targets.is_empty();
+
             if path.is_file() {
+                if self.indexed_count >= self.config.file_limit {
+                    self.truncated = true;
+                    break;
+                }
                 let clean_path = if let Some(path_str) = path.to_str() {
                     if path_str.starts_with("/host/") {
                         PathBuf::from(&path_str[6..])
@@ -68,65 +529,234 @@ pub fn get_all_files<P: AsRef<std::path::Path>>(
                 } else {
                     path
                 };
+                if via_symlink {
+                    self.symlinked_files.push(clean_path.clone());
+                }
                 files.push(clean_path);
-            } else if path.is_dir() {
-                dirs_in_level.push(path);
+                self.indexed_count += 1;
+            } else if is_dir {
+                let ignores = ignores.descend(&self.root, relative_path);
+                self.queue.push_back(PendingDir {
+                    path,
+                    ignores,
+                    symlink_targets,
+                });
             }
         }
 
-        for dir in dirs_in_level {
-            queue.push_back(dir);
+        files
+    }
+
+    // Decides whether to follow the symlink at `path`, returning its canonical
+    // target if so. `ancestors` are the targets followed to reach it.
+    fn follow_symlink(&mut self, path: &Path, ancestors: &[PathBuf]) -> Option<PathBuf> {
+        let target = fs::canonicalize(path).ok()?;
+        match self.config.symlinks {
+            SymlinkPolicy::Skip => None,
+            SymlinkPolicy::Follow => {
+                // A link to a directory containing the link is a cycle
+                let link_dir = path.parent().and_then(|dir| fs::canonicalize(dir).ok());
+                let is_cycle = link_dir
+                    .iter()
+                    .chain(ancestors)
+                    .chain(std::iter::once(&self.canonical_root))
+                    .any(|dir| dir.starts_with(&target));
+                (!is_cycle).then_some(target)
+            }
+            SymlinkPolicy::FollowOnce => {
+                // Targets inside the project are indexed under their real name
+                let is_indexed = target.starts_with(&self.canonical_root)
+                    || self
+                        .followed_targets
+                        .iter()
+                        .any(|followed| target.starts_with(followed));
+                if is_indexed {
+                    return None;
+                }
+                self.followed_targets.push(target.clone());
+                Some(target)
+            }
         }
     }
+}
 
-    let mut result = BTreeMap::new();
+// Whether the walker would pick up `relative_path`, used to patch the index
+// for single paths reported by filesystem events
+pub fn is_indexable_path(
+    root: &Path,
+    relative_path: &Path,
+    is_dir: bool,
+    config: &ScanConfig,
+) -> bool {
+    let ignores = IgnoreStack::for_root(root);
+    let use_fallback_ignores = ignores.is_empty();
+    let mut ignores = ignores.with_patterns(&config.ignore_patterns);
 
-    let rust_files: Vec<_> = files
-        .iter()
-        .filter(|file_path| file_path.extension().and_then(|ext| ext.to_str()) == Some("rs"))
-        .collect();
+    let component_count = relative_path.components().count();
+    let mut current = PathBuf::new();
+    for (i, component) in relative_path.components().enumerate() {
+        let name = match component.as_os_str().to_str() {
+            Some(name) => name,
+            None => return false,
+        };
+        if name == ".git" || (use_fallback_ignores && should_ignore(name)) {
+            return false;
+        }
+
+        current.push(name);
+        let is_last = i + 1 == component_count;
+        if ignores.matched(&current, is_dir || !is_last) == IgnoreMatch::Ignore {
+            return false;
+        }
+        if !is_last {
+            ignores = ignores.descend(root, &current);
+        }
+    }
+    true
+}
+
+// Paths from filesystem events are reported under /host, the index keeps
+// them relative to the project root like the walker does
+pub fn host_relative_path(path: &Path, cwd: &Path) -> PathBuf {
+    path.strip_prefix("/host")
+        .or_else(|_| path.strip_prefix(cwd))
+        .unwrap_or(path)
+        .to_path_buf()
+}
 
-    for file_path in &rust_files {
-        let rc_path = Rc::new((*file_path).clone());
-        let definitions = scan_rust_file_fast(&rc_path).unwrap_or_default();
-        result.insert((*file_path).clone(), definitions);
+// Output of `git ls-files -z`
+pub fn parse_git_ls_files(stdout: &[u8]) -> Vec<PathBuf> {
+    stdout
+        .split(|&b| b == 0)
+        .filter(|path| !path.is_empty())
+        .map(|path| PathBuf::from(String::from_utf8_lossy(path).into_owned()))
+        .collect()
+}
+
+// Definitions of any file a symbol extractor knows the language of
+pub fn scan_file_definitions(file_path: &Path, config: &ScanConfig) -> Vec<TypeDefinition> {
+    let extractor = match extractor_for(file_path) {
+        Some(extractor) => extractor,
+        None => return Vec::new(),
+    };
+    scan_in_chunks(file_path, config, extractor)
+}
+
+// Runs `extractor` over a file read `scan_chunk_size` bytes at a time, so
+// large files are scanned too without being held in memory whole. Chunks end
+// where the extractor has no state to carry over, and the file counts as
+// partly scanned if one has to be cut anywhere else
+fn scan_in_chunks(
+    file_path: &Path,
+    config: &ScanConfig,
+    extractor: &dyn SymbolExtractor,
+) -> Vec<TypeDefinition> {
+    let rc_path = Rc::new(file_path.to_path_buf());
+    let mut file = match fs::File::open(PathBuf::from("/host").join(file_path)) {
+        Ok(file) => file,
+        Err(_) => return Vec::new(), // Skip files we can't read
+    };
+    // Skip files configured as too large to be worth scanning
+    if file
+        .metadata()
+        .is_ok_and(|metadata| metadata.len() > config.max_file_size)
+    {
+        return Vec::new();
     }
+    let chunk_size = usize::try_from(config.scan_chunk_size).unwrap_or(usize::MAX);
+    let mut definitions: Vec<TypeDefinition> = Vec::new();
+    let mut partial = false;
+    let mut buffer = Vec::new();
+    let mut line_offset = 0;
+    loop {
+        let wanted = chunk_size.saturating_sub(buffer.len()) as u64;
+        let read = (&mut file).take(wanted).read_to_end(&mut buffer);
+        partial |= read.is_err();
+        let at_end = read.is_err() || buffer.len() < chunk_size;
+        let end = if at_end {
+            buffer.len()
+        } else {
+            extractor.chunk_end(&buffer).unwrap_or_else(|| {
+                // Cut after the last full line, or inside the only one
+                partial = true;
+                memrchr(b'\n', &buffer).map_or(buffer.len(), |newline| newline + 1)
+            })
+        };
+
+        let chunk = &buffer[..end];
+        let max_definitions = config
+            .max_definitions_per_file
+            .saturating_sub(definitions.len());
+        definitions.extend(
+            extractor
+                .extract(chunk, Rc::clone(&rc_path), max_definitions)
+                .into_iter()
+                .map(|mut definition| {
+                    definition.shift_lines(line_offset);
+                    definition
+                }),
+        );
+        line_offset += memchr_iter(b'\n', chunk).count();
+        buffer.drain(..end);
 
-    // Add non-Rust files with empty definitions
-    for file_path in files {
-        if !result.contains_key(&file_path) {
-            result.insert(file_path, Vec::new());
+        if definitions.len() >= config.max_definitions_per_file {
+            partial = true;
+            break;
+        }
+        if at_end {
+            break;
+        }
+    }
+    if partial {
+        for definition in &mut definitions {
+            definition.mark_partial_scan();
         }
     }
+    definitions
+}
 
+// Indexes the whole tree in one go (up to the configured file limit)
+#[cfg(test)]
+pub fn get_all_files<P: AsRef<Path>>(
+    dir: P,
+    config: &ScanConfig,
+) -> std::io::Result<BTreeMap<PathBuf, Vec<TypeDefinition>>> {
+    let mut indexer = FileIndexer::new(dir, config);
+    let mut result = BTreeMap::new();
+    while !indexer.is_done() {
+        result.extend(indexer.next_batch(INDEX_BATCH_SIZE));
+    }
     Ok(result)
 }
 
+// Scans a file as Rust whatever its extension
+#[cfg(test)]
 pub fn scan_rust_file_fast(
     file_path: &Rc<PathBuf>,
+    config: &ScanConfig,
 ) -> Result<Vec<TypeDefinition>, Box<dyn std::error::Error>> {
-    // Read file content as bytes first to avoid UTF-8 validation overhead
-    let bytes = match fs::read(PathBuf::from("/host").join(file_path.as_ref())) {
-        Ok(bytes) => bytes,
-        Err(_) => return Ok(Vec::new()), // Skip files we can't read
-    };
-
-    // Skip very large files for performance
-    if bytes.len() > 1_000_000 {
-        return Ok(Vec::new());
-    }
-
-    // Use byte-level parsing for better performance
-    scan_with_bytes(&bytes, Rc::clone(file_path))
+    Ok(scan_in_chunks(
+        file_path,
+        config,
+        &crate::extractors::RustExtractor,
+    ))
 }
 
-fn scan_with_bytes(
+pub fn scan_with_bytes(
     bytes: &[u8],
     file_path: Rc<PathBuf>,
+    max_definitions: usize,
 ) -> Result<Vec<TypeDefinition>, Box<dyn std::error::Error>> {
-    let mut definitions = Vec::with_capacity(64);
+    let mut definitions: Vec<TypeDefinition> = Vec::with_capacity(64);
     let mut line_num = 1;
     let mut pos = 0;
+    let mut definition_count = 0;
+    let mut blocks = Blocks::default();
+    let mut lexer = Lexer::default();
+    let mut tokens = Vec::new();
+    let mut attributes = Attributes::default(); // Of the next item, when on lines of their own
+    let mut open_signature: Option<(usize, Signature)> = None; // And the definition it belongs to
+    let mut docs: Vec<String> = Vec::new(); // `///` lines above the next item
 
     while pos < bytes.len() {
         // Use memchr to find next newline - much faster than manual iteration
@@ -136,24 +766,124 @@ fn scan_with_bytes(
 
         let line = &bytes[pos..line_end];
 
-        // Quick rejection: skip empty lines and comments
-        if !line.is_empty() {
-            let first_non_ws = line.iter().position(|&b| b != b' ' && b != b'\t');
-            if let Some(start) = first_non_ws {
-                let trimmed = &line[start..];
-                if !trimmed.starts_with(b"//") && !trimmed.starts_with(b"/*") {
-                    if let Some(def) = extract_definition(trimmed, Rc::clone(&file_path), line_num)
-                    {
-                        definitions.push(def);
+        // Lines starting inside a block comment, a string or a macro body
+        // never declare anything
+        let starts_in_code = lexer.lex_line(line, &mut tokens) && !blocks.in_macro();
 
-                        // Early exit if we have many definitions
-                        if definitions.len() >= 300 {
-                            break;
+        // Quick rejection: skip empty lines and comments
+        let first_non_ws = line.iter().position(|&b| b != b' ' && b != b'\t');
+        if let (true, Some(start)) = (starts_in_code, first_non_ws) {
+            let mut trimmed = &line[start..];
+            if attributes.unclosed {
+                // The rest of an attribute spanning several lines
+                trimmed = match memchr(b']', trimmed) {
+                    Some(end) => {
+                        attributes.unclosed = false;
+                        &trimmed[skip_whitespace(trimmed, end + 1)..]
+                    }
+                    None => &[],
+                };
+            }
+            let (trimmed, line_attributes) = skip_attributes(trimmed);
+            attributes.unclosed |= line_attributes.unclosed;
+            attributes.test |= line_attributes.test;
+            attributes.cfg_test |= line_attributes.cfg_test;
+            if line_attributes.path.is_some() {
+                attributes.path = line_attributes.path;
+            }
+            if let Some((index, signature)) = &mut open_signature {
+                // The rest of a declaration spanning several lines, not an item
+                if signature.push_line(&mut definitions[*index].signature, trimmed) {
+                    open_signature = None;
+                }
+            } else if let Some(doc) = strip_doc_comment(trimmed, b"///") {
+                docs.push(doc);
+            } else if let Some(doc) = strip_doc_comment(trimmed, b"//!") {
+                if let Some(BlockKind::Module { parent, .. }) = blocks.current() {
+                    let module_docs = &mut definitions[*parent].docs;
+                    if !module_docs.is_empty() {
+                        module_docs.push('\n');
+                    }
+                    module_docs.push_str(&doc);
+                }
+            } else if !trimmed.is_empty()
+                && !trimmed.starts_with(b"//")
+                && !trimmed.starts_with(b"/*")
+            {
+                let item_docs = std::mem::take(&mut docs).join("\n");
+                let found = match blocks.current() {
+                    Some(BlockKind::Members { parent, owner }) => {
+                        let parent: &mut TypeDefinition = &mut definitions[*parent];
+                        let member = extract_member(trimmed, parent, owner, line_num);
+                        member
+                            .map(|member| {
+                                let (column, char_column) =
+                                    name_column(line, trimmed, &member.name);
+                                parent.children.push(TypeDefinition {
+                                    docs: item_docs,
+                                    column,
+                                    char_column,
+                                    ..member
+                                })
+                            })
+                            .is_some()
+                    }
+                    _ => match extract_definition(trimmed, Rc::clone(&file_path), line_num) {
+                        Some(mut def) => {
+                            def.docs = item_docs;
+                            def.module_path = blocks.module_path();
+                            let (column, char_column) = name_column(line, trimmed, &def.name);
+                            def.column = column;
+                            def.char_column = char_column;
+                            if has_signature(&def.type_kind) {
+                                if let Some(end) = name_end(trimmed, &def.name) {
+                                    let mut signature = Signature::default();
+                                    if !signature.push_line(&mut def.signature, &trimmed[end..]) {
+                                        open_signature = Some((definitions.len(), signature));
+                                    }
+                                }
+                            }
+                            match def.type_kind {
+                                TypeKind::Impl => blocks
+                                    .expect_body(BlockKind::Impl(impl_self_type(&def.name).into())),
+                                TypeKind::Function => {
+                                    def.owner = blocks.impl_owner();
+                                    def.is_test = attributes.test;
+                                }
+                                TypeKind::Module => {
+                                    def.is_test = attributes.cfg_test;
+                                    def.path_attribute = attributes.path.take();
+                                    blocks.expect_body(BlockKind::Module {
+                                        parent: definitions.len(),
+                                        name: def.name.as_str().into(),
+                                    });
+                                }
+                                TypeKind::Struct | TypeKind::Enum | TypeKind::Union => blocks
+                                    .expect_body(BlockKind::Members {
+                                        parent: definitions.len(),
+                                        owner: def.name.as_str().into(),
+                                    }),
+                                TypeKind::Macro => blocks.expect_body(BlockKind::Macro),
+                                _ => {}
+                            }
+                            definitions.push(def);
+                            true
                         }
+                        None => false,
+                    },
+                };
+                attributes = Attributes::default();
+
+                // Early exit if we have many definitions
+                if found {
+                    definition_count += 1;
+                    if definition_count >= max_definitions {
+                        break;
                     }
                 }
             }
         }
+        blocks.track_tokens(&tokens);
 
         pos = line_end + 1;
         line_num += 1;
@@ -162,59 +892,537 @@ fn scan_with_bytes(
     Ok(definitions)
 }
 
+// What the lines directly inside a block's body are
+enum BlockKind {
+    Impl(Rc<str>),                             // Methods of this type
+    Members { parent: usize, owner: Rc<str> }, // Variants or fields of definitions[parent]
+    Macro,                                     // Rules of a macro_rules!, not items
+    Module { parent: usize, name: Rc<str> },   // Items of an inline `mod name { .. }`
+}
+
+// Follows brace depth to know which block, if any, a line is directly in
+#[derive(Default)]
+struct Blocks {
+    depth: usize,
+    pending: Option<BlockKind>,    // A header whose body hasn't opened yet
+    open: Vec<(BlockKind, usize)>, // And the depth inside their body
+}
+
+impl Blocks {
+    fn expect_body(&mut self, kind: BlockKind) {
+        self.pending = Some(kind);
+    }
+
+    // Only lines directly in the body count, not ones nested deeper
+    fn current(&self) -> Option<&BlockKind> {
+        match self.open.last() {
+            Some((kind, depth)) if *depth == self.depth => Some(kind),
+            _ => None,
+        }
+    }
+
+    fn in_macro(&self) -> bool {
+        self.open
+            .iter()
+            .any(|(kind, _)| matches!(kind, BlockKind::Macro))
+    }
+
+    // The inline modules around the current line, eg. "tests::helpers"
+    fn module_path(&self) -> Option<Rc<str>> {
+        let mut modules = self.open.iter().filter_map(|(kind, _)| match kind {
+            BlockKind::Module { name, .. } => Some(name.as_ref()),
+            _ => None,
+        });
+        let first = modules.next()?;
+        Some(
+            modules
+                .fold(first.to_owned(), |path, name| path + "::" + name)
+                .into(),
+        )
+    }
+
+    fn impl_owner(&self) -> Option<Rc<str>> {
+        match self.current() {
+            Some(BlockKind::Impl(owner)) => Some(Rc::clone(owner)),
+            _ => None,
+        }
+    }
+
+    // Applies the braces and semicolons the lexer found on a line
+    fn track_tokens(&mut self, tokens: &[u8]) {
+        for &token in tokens {
+            match token {
+                b'{' => {
+                    self.depth += 1;
+                    if let Some(kind) = self.pending.take() {
+                        self.open.push((kind, self.depth));
+                    }
+                }
+                b'}' => {
+                    if self
+                        .open
+                        .last()
+                        .is_some_and(|(_, depth)| *depth == self.depth)
+                    {
+                        self.open.pop();
+                    }
+                    self.depth = self.depth.saturating_sub(1);
+                }
+                // Unit and tuple structs have no body, nor do `mod name;` modules
+                b';' if matches!(
+                    self.pending,
+                    Some(BlockKind::Members { .. } | BlockKind::Module { .. })
+                ) =>
+                {
+                    self.pending = None;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+const MAX_SIGNATURE_LINES: usize = 20;
+const MAX_SIGNATURE_LEN: usize = 500;
+
+// Reads a declaration up to the `{` or `;` ending it, one line at a time
+#[derive(Default)]
+struct Signature {
+    depth: usize, // Of parentheses and brackets, so `[u8; 4]` doesn't end it
+    lines: usize,
+}
+
+impl Signature {
+    // Appends a line to `signature` with its whitespace collapsed, returning
+    // whether the declaration is complete
+    fn push_line(&mut self, signature: &mut String, line: &[u8]) -> bool {
+        let mut end = line.len();
+        let mut ended = false;
+        for (i, &b) in line.iter().enumerate() {
+            match b {
+                b'(' | b'[' => self.depth += 1,
+                b')' | b']' => self.depth = self.depth.saturating_sub(1),
+                b'{' | b';' if self.depth == 0 => {
+                    end = i;
+                    ended = true;
+                    break;
+                }
+                b'/' if line.get(i + 1) == Some(&b'/') => {
+                    end = i;
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        let part = String::from_utf8_lossy(&line[..end]);
+        if self.lines == 0 {
+            // Right after the name, where the spacing is meaningful
+            signature.push_str(part.trim_end());
+        } else {
+            let part = part.trim();
+            let closes = part.starts_with([')', ']', '>']);
+            if closes && signature.ends_with(',') {
+                signature.pop(); // The trailing comma of the last parameter
+            }
+            if !(closes || signature.ends_with(['(', '[', '<']) || part.is_empty()) {
+                signature.push(' ');
+            }
+            signature.push_str(part);
+        }
+        if ended && signature.ends_with(',') {
+            signature.pop(); // Of the last `where` bound
+        }
+        self.lines += 1;
+        ended || self.lines >= MAX_SIGNATURE_LINES || signature.len() >= MAX_SIGNATURE_LEN
+    }
+}
+
+// Items whose declaration says more than their name
+fn has_signature(type_kind: &TypeKind) -> bool {
+    matches!(
+        type_kind,
+        TypeKind::Function
+            | TypeKind::Struct
+            | TypeKind::Enum
+            | TypeKind::Union
+            | TypeKind::Trait
+            | TypeKind::TypeAlias
+    )
+}
+
+// Where the declared name ends in its declaration, the name being a whole
+// word after the keyword
+fn name_end(line: &[u8], name: &str) -> Option<usize> {
+    memmem::find_iter(line, name.as_bytes()).find_map(|start| {
+        let end = start + name.len();
+        let is_word = start > 0
+            && matches!(line[start - 1], b' ' | b'\t')
+            && !line
+                .get(end)
+                .is_some_and(|&b| b.is_ascii_alphanumeric() || b == b'_');
+        is_word.then_some(end)
+    })
+}
+
+// The 1-based byte and char columns of `name` in `line`, looked for as a
+// whole word in `trimmed`, the part of the line the item starts at. Names
+// that aren't a word of the line, such as impl headers, get the item's start
+pub fn name_column(line: &[u8], trimmed: &[u8], name: &str) -> (usize, usize) {
+    let is_ident = |b: &u8| b.is_ascii_alphanumeric() || *b == b'_';
+    let offset = memmem::find_iter(trimmed, name.as_bytes())
+        .find(|&start| {
+            let end = start + name.len();
+            let starts_word = start == 0 || !is_ident(&trimmed[start - 1]);
+            starts_word && !trimmed.get(end).is_some_and(is_ident)
+        })
+        .unwrap_or(0);
+    // `trimmed` is the end of `line`
+    let byte = line.len() - trimmed.len() + offset;
+    let chars = line[..byte].iter().filter(|&&b| b & 0xC0 != 0x80).count();
+    (byte + 1, chars + 1)
+}
+
+// The text of a doc comment line starting with `marker`, but not of a
+// `////` separator
+fn strip_doc_comment(line: &[u8], marker: &[u8]) -> Option<String> {
+    let doc = line.strip_prefix(marker)?;
+    if doc.starts_with(b"/") {
+        return None;
+    }
+    let doc = doc.strip_prefix(b" ").unwrap_or(doc);
+    Some(String::from_utf8_lossy(doc).trim_end().to_owned())
+}
+
+// What the attributes in front of an item say about it
+#[derive(Default)]
+struct Attributes {
+    test: bool,           // `#[test]`, or a runtime's such as `#[tokio::test]`
+    cfg_test: bool,       // `#[cfg(test)]`
+    path: Option<String>, // The file of `#[path = "file.rs"]`
+    unclosed: bool,       // The last attribute continues on the next line
+}
+
+// Skips the outer attributes at the start of a line, eg. the `#[test]` of
+// `#[test] fn it_works() {}`, returning the rest of the line
+fn skip_attributes(mut line: &[u8]) -> (&[u8], Attributes) {
+    let mut attributes = Attributes::default();
+    while line.starts_with(b"#[") {
+        // Attributes may contain brackets themselves, eg. `#[doc = ["a"]]`
+        let mut depth = 0;
+        let end = line.iter().skip(1).position(|&b| {
+            match b {
+                b'[' => depth += 1,
+                b']' => depth -= 1,
+                _ => {}
+            }
+            depth == 0
+        });
+        let end = end.map(|end| end + 1);
+        let content = &line[2..end.unwrap_or(line.len())];
+        let path_end = content
+            .iter()
+            .position(|&b| matches!(b, b'(' | b' ' | b'=' | b']'))
+            .unwrap_or(content.len());
+        let path = &content[..path_end];
+        attributes.test |= path == b"test" || path.ends_with(b"::test");
+        attributes.cfg_test |= content
+            .iter()
+            .filter(|b| !b.is_ascii_whitespace())
+            .eq(b"cfg(test)".iter());
+        if path == b"path" {
+            attributes.path = path_attribute_value(content);
+        }
+        line = match end {
+            Some(end) => &line[skip_whitespace(line, end + 1)..],
+            None => {
+                attributes.unclosed = true;
+                &[]
+            }
+        };
+    }
+    (line, attributes)
+}
+
+// The string of `path = "file.rs"`, escapes aside
+fn path_attribute_value(content: &[u8]) -> Option<String> {
+    let rest = content[4..]
+        .iter()
+        .position(|&b| !b.is_ascii_whitespace())?;
+    let rest = content[4 + rest..].strip_prefix(b"=")?;
+    let open = memchr(b'"', rest)?;
+    let close = memchr(b'"', &rest[open + 1..])?;
+    Some(String::from_utf8_lossy(&rest[open + 1..open + 1 + close]).into_owned())
+}
+
+// The type an impl block is for, without generics or path, eg. "Label" for
+// "From<T> for crate::ui::Label<T>"
+fn impl_self_type(impl_name: &str) -> &str {
+    let self_type = match impl_name.find(" for ") {
+        Some(index) => &impl_name[index + 5..],
+        None => impl_name,
+    };
+    let self_type = self_type
+        .trim()
+        .trim_start_matches('&')
+        .trim_start_matches("mut ")
+        .trim_start_matches("dyn ");
+    let self_type = match self_type.find('<') {
+        Some(generics) => &self_type[..generics],
+        None => self_type,
+    };
+    let self_type = match self_type.rfind("::") {
+        Some(path) => &self_type[path + 2..],
+        None => self_type,
+    };
+    self_type.trim()
+}
+
 // Note: line is already trimmed (leading whitespace removed)
 fn extract_definition(
     line: &[u8],
     file_path: Rc<PathBuf>,
     line_num: usize,
 ) -> Option<TypeDefinition> {
-    let mut i = 0;
+    let (visibility, i) = parse_visibility(line);
+    let (qualifiers, after_qualifiers) = parse_fn_qualifiers(line, i);
+    let only_unsafe = FnQualifiers {
+        is_unsafe: qualifiers.is_unsafe,
+        ..Default::default()
+    } == qualifiers;
+
+    let unqualified_item = if qualifiers.is_empty() {
+        extract_unqualified_item(line, i)
+    } else {
+        None
+    };
+
+    let (type_kind, name) = if let Some(item) = unqualified_item {
+        item
+    } else if let Some(j) = skip_keyword(line, after_qualifiers, b"fn") {
+        (TypeKind::Function, extract_identifier(&line[j..])?)
+    } else if only_unsafe && is_impl_keyword(&line[after_qualifiers..]) {
+        (
+            TypeKind::Impl,
+            extract_impl_name(&line[after_qualifiers + 4..])?,
+        )
+    } else if only_unsafe {
+        // `unsafe trait` and `auto trait`
+        let j = skip_keyword(line, after_qualifiers, b"auto").unwrap_or(after_qualifiers);
+        let j = skip_keyword(line, j, b"trait")?;
+        (TypeKind::Trait, extract_identifier(&line[j..])?)
+    } else if qualifiers
+        == (FnQualifiers {
+            is_const: true,
+            ..Default::default()
+        })
+    {
+        (
+            TypeKind::Const,
+            extract_identifier(&line[after_qualifiers..])?,
+        )
+    } else {
+        return None;
+    };
 
-    // Skip "pub" or "pub(...)"
-    if line.len() >= 3 && &line[..3] == b"pub" {
-        i = 3;
+    let qualifiers = if type_kind == TypeKind::Const {
+        FnQualifiers::default()
+    } else {
+        qualifiers
+    };
+    if name == "_" {
+        return None; // eg. `const _: () = assert!(..);`
+    }
+    Some(TypeDefinition {
+        qualifiers,
+        visibility,
+        ..TypeDefinition::new(type_kind, name, file_path, line_num)
+    })
+}
 
-        // Skip pub(crate), pub(super), etc.
-        if i < line.len() && line[i] == b'(' {
-            while i < line.len() && line[i] != b')' {
-                i += 1;
+// The kind and name of items that take no qualifiers, `i` being past the visibility
+fn extract_unqualified_item(line: &[u8], i: usize) -> Option<(TypeKind, String)> {
+    const KEYWORDS: [(&[u8], TypeKind); 6] = [
+        (b"struct", TypeKind::Struct),
+        (b"enum", TypeKind::Enum),
+        (b"union", TypeKind::Union),
+        (b"type", TypeKind::TypeAlias),
+        (b"mod", TypeKind::Module),
+        (b"static", TypeKind::Static),
+    ];
+    if let Some(rest) = line[i..].strip_prefix(b"macro_rules!") {
+        let j = rest
+            .iter()
+            .take_while(|&&b| b == b' ' || b == b'\t')
+            .count();
+        return Some((TypeKind::Macro, extract_identifier(&rest[j..])?));
+    }
+    let (j, type_kind) = KEYWORDS
+        .iter()
+        .find_map(|(keyword, kind)| Some((skip_keyword(line, i, keyword)?, kind.clone())))?;
+    let j = match type_kind {
+        // `static mut` and lazy_static's `static ref`
+        TypeKind::Static => skip_keyword(line, j, b"mut")
+            .or_else(|| skip_keyword(line, j, b"ref"))
+            .unwrap_or(j),
+        _ => j,
+    };
+    Some((type_kind, extract_identifier(&line[j..])?))
+}
+
+fn is_impl_keyword(rest: &[u8]) -> bool {
+    rest.starts_with(b"impl") && matches!(rest.get(4), Some(b' ' | b'\t' | b'<'))
+}
+
+// What an impl block is for, eg. "Display for Wrapper<T>": the header after
+// `impl` and its generic parameters, up to the body or a where clause
+fn extract_impl_name(rest: &[u8]) -> Option<String> {
+    let mut i = rest
+        .iter()
+        .take_while(|&&b| b == b' ' || b == b'\t')
+        .count();
+    if rest.get(i) == Some(&b'<') {
+        let mut depth = 0;
+        while i < rest.len() {
+            match rest[i] {
+                b'<' => depth += 1,
+                b'>' if i > 0 && rest[i - 1] == b'-' => {} // `Fn() -> T` bounds
+                b'>' => depth -= 1,
+                _ => {}
             }
-            if i < line.len() {
-                i += 1; // Skip ')'
+            i += 1;
+            if depth == 0 {
+                break;
             }
         }
+    }
+    let header = &rest[i..];
+    let end = memchr(b'{', header).unwrap_or(header.len());
+    let header = String::from_utf8_lossy(&header[..end]);
+    let header = match header.find(" where") {
+        Some(where_clause) => &header[..where_clause],
+        None => &header,
+    };
+    let header = header.trim();
+    if header.is_empty() {
+        None
+    } else {
+        Some(header.to_owned())
+    }
+}
 
-        // Skip whitespace after pub
-        while i < line.len() && (line[i] == b' ' || line[i] == b'\t') {
-            i += 1;
-        }
+// Parses `pub`, `pub(crate)`, `pub(super)`, `pub(self)` or `pub(in path)`
+// at the start of a line, returning where the rest of the item starts
+pub fn parse_visibility(line: &[u8]) -> (Visibility, usize) {
+    let after_pub = match skip_keyword(line, 0, b"pub") {
+        Some(i) => i,
+        // `pub` right before its parentheses isn't followed by whitespace
+        None if line.starts_with(b"pub(") => 3,
+        None => return (Visibility::Private, 0),
+    };
+    if line.get(after_pub) != Some(&b'(') {
+        return (Visibility::Public, after_pub);
     }
+    let close = match memchr(b')', &line[after_pub..]) {
+        Some(offset) => after_pub + offset,
+        None => return (Visibility::Public, after_pub),
+    };
+    let scope = String::from_utf8_lossy(&line[after_pub + 1..close]);
+    let visibility = match scope.trim() {
+        "crate" => Visibility::Crate,
+        "super" => Visibility::Super,
+        "self" => Visibility::Private,
+        scope => match scope.strip_prefix("in ") {
+            Some(path) => Visibility::In(path.trim().to_owned()),
+            // Not a visibility, eg. a tuple struct field `pub (u8, u8)`
+            None => return (Visibility::Public, after_pub),
+        },
+    };
+    (visibility, skip_whitespace(line, close + 1))
+}
 
-    // Check for keywords
-    if line.len() >= i + 7 && &line[i..i + 6] == b"struct" && line[i + 6] == b' ' {
-        extract_identifier(&line[i + 7..]).map(|name| TypeDefinition {
-            type_kind: TypeKind::Struct,
-            name,
-            file_path,
-            line_number: line_num,
-        })
-    } else if line.len() >= i + 5 && &line[i..i + 4] == b"enum" && line[i + 4] == b' ' {
-        extract_identifier(&line[i + 5..]).map(|name| TypeDefinition {
-            type_kind: TypeKind::Enum,
-            name,
-            file_path,
-            line_number: line_num,
-        })
-    } else if line.len() >= i + 3 && &line[i..i + 2] == b"fn" && line[i + 2] == b' ' {
-        extract_identifier(&line[i + 3..]).map(|name| TypeDefinition {
-            type_kind: TypeKind::Function,
-            name,
-            file_path,
-            line_number: line_num,
-        })
+// A variant of an enum or a named field of a struct or union, from a line
+// directly inside its body
+fn extract_member(
+    line: &[u8],
+    parent: &TypeDefinition,
+    owner: &Rc<str>,
+    line_num: usize,
+) -> Option<TypeDefinition> {
+    // Variants are as visible as their enum
+    let (type_kind, name, visibility) = if parent.type_kind == TypeKind::Enum {
+        let name = extract_identifier(line)?;
+        (TypeKind::Variant, name, parent.visibility.clone())
     } else {
-        None
+        let (visibility, i) = parse_visibility(line);
+        let name = extract_identifier(&line[i..])?;
+        let after_name = skip_whitespace(line, i + name.len());
+        // `name: Type`, but not a path such as `name::Type`
+        if line.get(after_name) != Some(&b':') || line.get(after_name + 1) == Some(&b':') {
+            return None;
+        }
+        (TypeKind::Field, name, visibility)
+    };
+    Some(TypeDefinition {
+        owner: Some(Rc::clone(owner)),
+        visibility,
+        ..TypeDefinition::new(type_kind, name, Rc::clone(&parent.file_path), line_num)
+    })
+}
+
+// Parses `default const async unsafe extern "ABI"` (each optional) starting
+// at `i`, returning the position right after them
+fn parse_fn_qualifiers(line: &[u8], mut i: usize) -> (FnQualifiers, usize) {
+    let mut qualifiers = FnQualifiers::default();
+    loop {
+        if let Some(next) = skip_keyword(line, i, b"default") {
+            qualifiers.is_default = true;
+            i = next;
+        } else if let Some(next) = skip_keyword(line, i, b"const") {
+            qualifiers.is_const = true;
+            i = next;
+        } else if let Some(next) = skip_keyword(line, i, b"async") {
+            qualifiers.is_async = true;
+            i = next;
+        } else if let Some(next) = skip_keyword(line, i, b"unsafe") {
+            qualifiers.is_unsafe = true;
+            i = next;
+        } else if line[i..].starts_with(b"extern")
+            && matches!(line.get(i + 6), Some(b' ' | b'\t' | b'"'))
+        {
+            i = skip_whitespace(line, i + 6);
+            let mut abi = "C".to_owned();
+            if line.get(i) == Some(&b'"') {
+                let end = match memchr(b'"', &line[i + 1..]) {
+                    Some(end) => i + 1 + end,
+                    None => break,
+                };
+                abi = String::from_utf8_lossy(&line[i + 1..end]).into_owned();
+                i = skip_whitespace(line, end + 1);
+            }
+            qualifiers.extern_abi = Some(abi);
+        } else {
+            break;
+        }
+    }
+    (qualifiers, i)
+}
+
+// The position after `keyword` and the whitespace following it, if `keyword`
+// is a whole word at `i`
+fn skip_keyword(line: &[u8], i: usize, keyword: &[u8]) -> Option<usize> {
+    let end = i + keyword.len();
+    if !line[i..].starts_with(keyword) || !matches!(line.get(end), Some(b' ' | b'\t')) {
+        return None;
+    }
+    Some(skip_whitespace(line, end))
+}
+
+fn skip_whitespace(line: &[u8], mut i: usize) -> usize {
+    while i < line.len() && (line[i] == b' ' || line[i] == b'\t') {
+        i += 1;
     }
+    i
 }
 
 // Extract identifier from bytes (no UTF-8 validation until we find one)
@@ -224,7 +1432,7 @@ fn extract_identifier(bytes: &[u8]) -> Option<String> {
         return None;
     }
 
-    // Find end of identifier (until <, {, (, ;, or whitespace)
+    // Find end of identifier (until <, {, (, ;, :, =, ',' or whitespace)
     let end = bytes
         .iter()
         .position(|&b| {
@@ -232,6 +1440,9 @@ fn extract_identifier(bytes: &[u8]) -> Option<String> {
                 || b == b'{'
                 || b == b'('
                 || b == b';'
+                || b == b':'
+                || b == b'='
+                || b == b','
                 || b == b' '
                 || b == b'\t'
                 || b == b'\n'
@@ -0,0 +1,372 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+// Per-directory ignore files, lowest precedence first (same order ripgrep uses)
+const IGNORE_FILE_NAMES: [&str; 3] = [".gitignore", ".ignore", ".rgignore"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IgnoreMatch {
+    None,
+    Ignore,
+    Whitelist,
+}
+
+#[derive(Debug, Clone)]
+struct IgnorePattern {
+    glob: Vec<u8>,
+    negated: bool,
+    dir_only: bool,
+    // Patterns containing a slash are matched against the full relative path,
+    // all others against the file name only
+    anchored: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct IgnoreFile {
+    // Directory the patterns are relative to (relative to the walk root)
+    base: PathBuf,
+    patterns: Vec<IgnorePattern>,
+}
+
+impl IgnoreFile {
+    pub fn parse(base: PathBuf, contents: &str) -> Self {
+        let patterns = contents.lines().filter_map(parse_pattern).collect();
+        IgnoreFile { base, patterns }
+    }
+
+    pub fn from_path(base: PathBuf, path: &Path) -> Option<Self> {
+        let contents = fs::read_to_string(path).ok()?;
+        let ignore_file = Self::parse(base, &contents);
+        if ignore_file.patterns.is_empty() {
+            None
+        } else {
+            Some(ignore_file)
+        }
+    }
+
+    // Last matching pattern wins
+    pub fn matched(&self, relative_path: &Path, is_dir: bool) -> IgnoreMatch {
+        let path = match relative_path.strip_prefix(&self.base) {
+            Ok(path) => path,
+            Err(_) => return IgnoreMatch::None,
+        };
+        let path = path.to_string_lossy();
+        let path = path.as_bytes();
+        let file_name = match path.iter().rposition(|&b| b == b'/') {
+            Some(i) => &path[i + 1..],
+            None => path,
+        };
+
+        for pattern in self.patterns.iter().rev() {
+            if pattern.dir_only && !is_dir {
+                continue;
+            }
+            let candidate = if pattern.anchored { path } else { file_name };
+            if glob_match(&pattern.glob, candidate) {
+                return if pattern.negated {
+                    IgnoreMatch::Whitelist
+                } else {
+                    IgnoreMatch::Ignore
+                };
+            }
+        }
+        IgnoreMatch::None
+    }
+}
+
+fn parse_pattern(line: &str) -> Option<IgnorePattern> {
+    let line = line.trim_end_matches('\r');
+    // Trailing spaces are ignored unless escaped with a backslash
+    let bytes = line.as_bytes();
+    let mut end = bytes.len();
+    while end > 0 && bytes[end - 1] == b' ' && !(end >= 2 && bytes[end - 2] == b'\\') {
+        end -= 1;
+    }
+    let mut line = &line[..end];
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let mut negated = false;
+    if let Some(rest) = line.strip_prefix('!') {
+        negated = true;
+        line = rest;
+    } else if line.starts_with("\\!") || line.starts_with("\\#") {
+        line = &line[1..];
+    }
+
+    let mut dir_only = false;
+    if let Some(rest) = line.strip_suffix('/') {
+        dir_only = true;
+        line = rest;
+    }
+
+    let anchored = line.contains('/');
+    let line = line.strip_prefix('/').unwrap_or(line);
+    if line.is_empty() {
+        return None;
+    }
+
+    Some(IgnorePattern {
+        glob: line.as_bytes().to_vec(),
+        negated,
+        dir_only,
+        anchored,
+    })
+}
+
+// Gitignore-flavoured glob: `*` and `?` never match `/`, `**` matches across
+// directories, `[...]` supports ranges and `!`/`^` negation
+pub fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    let mut p = 0;
+    let mut t = 0;
+
+    while p < pattern.len() {
+        match pattern[p] {
+            b'*' if pattern.get(p + 1) == Some(&b'*') => {
+                let mut rest = p;
+                while rest < pattern.len() && pattern[rest] == b'*' {
+                    rest += 1;
+                }
+                if pattern.get(rest) == Some(&b'/') {
+                    // "**/" matches zero or more leading directories
+                    let after = &pattern[rest + 1..];
+                    if glob_match(after, &text[t..]) {
+                        return true;
+                    }
+                    return (t..text.len())
+                        .any(|i| text[i] == b'/' && glob_match(after, &text[i + 1..]));
+                }
+                return (t..=text.len()).any(|i| glob_match(&pattern[rest..], &text[i..]));
+            }
+            b'*' => {
+                let rest = &pattern[p + 1..];
+                for i in t..=text.len() {
+                    if glob_match(rest, &text[i..]) {
+                        return true;
+                    }
+                    if i < text.len() && text[i] == b'/' {
+                        break;
+                    }
+                }
+                return false;
+            }
+            b'?' => {
+                if t >= text.len() || text[t] == b'/' {
+                    return false;
+                }
+                p += 1;
+                t += 1;
+            }
+            b'[' => {
+                if t >= text.len() {
+                    return false;
+                }
+                match match_class(&pattern[p..], text[t]) {
+                    Some((true, consumed)) => {
+                        p += consumed;
+                        t += 1;
+                    }
+                    Some((false, _)) => return false,
+                    // Unterminated class, treat '[' literally
+                    None => {
+                        if text[t] != b'[' {
+                            return false;
+                        }
+                        p += 1;
+                        t += 1;
+                    }
+                }
+            }
+            b'\\' if p + 1 < pattern.len() => {
+                if t >= text.len() || text[t] != pattern[p + 1] {
+                    return false;
+                }
+                p += 2;
+                t += 1;
+            }
+            literal => {
+                if t >= text.len() || text[t] != literal {
+                    return false;
+                }
+                p += 1;
+                t += 1;
+            }
+        }
+    }
+
+    t == text.len()
+}
+
+// Returns (matched, bytes consumed from the pattern)
+fn match_class(pattern: &[u8], byte: u8) -> Option<(bool, usize)> {
+    let mut i = 1;
+    let negated = matches!(pattern.get(i), Some(b'!') | Some(b'^'));
+    if negated {
+        i += 1;
+    }
+
+    let mut matched = false;
+    let mut first = true;
+    while i < pattern.len() {
+        let c = pattern[i];
+        if c == b']' && !first {
+            return Some((matched != negated && byte != b'/', i + 1));
+        }
+        first = false;
+        if i + 2 < pattern.len() && pattern[i + 1] == b'-' && pattern[i + 2] != b']' {
+            if c <= byte && byte <= pattern[i + 2] {
+                matched = true;
+            }
+            i += 3;
+        } else {
+            if c == byte {
+                matched = true;
+            }
+            i += 1;
+        }
+    }
+    None
+}
+
+// Ignore files that apply to a directory, chained to the ones of its parents
+#[derive(Debug, Default)]
+pub struct IgnoreStack {
+    parent: Option<Rc<IgnoreStack>>,
+    // Ordered from lowest to highest precedence
+    files: Vec<IgnoreFile>,
+}
+
+impl IgnoreStack {
+    // Builds the root of the stack: global excludes, .git/info/exclude and
+    // the ignore files found in the root directory itself
+    pub fn for_root(root: &Path) -> Rc<Self> {
+        let mut files = Vec::new();
+
+        if let Some(global) = global_excludes_file(root) {
+            files.extend(IgnoreFile::from_path(PathBuf::new(), &global));
+        }
+        files.extend(IgnoreFile::from_path(
+            PathBuf::new(),
+            &root.join(".git").join("info").join("exclude"),
+        ));
+        for name in IGNORE_FILE_NAMES {
+            files.extend(IgnoreFile::from_path(PathBuf::new(), &root.join(name)));
+        }
+
+        Rc::new(IgnoreStack {
+            parent: None,
+            files,
+        })
+    }
+
+    // Stack that applies inside `dir` (relative to the walk root), built by
+    // descending from the root through each of its ancestors
+    pub fn for_directory(root: &Path, dir: &Path, extra_patterns: &[String]) -> Rc<Self> {
+        let mut stack = Self::for_root(root).with_patterns(extra_patterns);
+        let mut current = PathBuf::new();
+        for component in dir.components() {
+            current.push(component);
+            stack = stack.descend(root, &current);
+        }
+        stack
+    }
+
+    // Returns a stack for `dir` (relative to the walk root), reusing `self` if
+    // the directory has no ignore files of its own
+    pub fn descend(self: &Rc<Self>, root: &Path, dir: &Path) -> Rc<Self> {
+        let files: Vec<IgnoreFile> = IGNORE_FILE_NAMES
+            .iter()
+            .filter_map(|name| IgnoreFile::from_path(dir.to_path_buf(), &root.join(dir).join(name)))
+            .collect();
+
+        if files.is_empty() {
+            Rc::clone(self)
+        } else {
+            Rc::new(IgnoreStack {
+                parent: Some(Rc::clone(self)),
+                files,
+            })
+        }
+    }
+
+    // Adds patterns that apply as if they were in an ignore file at the root,
+    // with precedence over the root's own ignore files
+    pub fn with_patterns(self: &Rc<Self>, patterns: &[String]) -> Rc<Self> {
+        if patterns.is_empty() {
+            return Rc::clone(self);
+        }
+        Rc::new(IgnoreStack {
+            parent: Some(Rc::clone(self)),
+            files: vec![IgnoreFile::parse(PathBuf::new(), &patterns.join("\n"))],
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty() && self.parent.as_ref().is_none_or(|p| p.is_empty())
+    }
+
+    // Whether `relative_path` is a file ignored by this stack, either directly
+    // or through one of its parent directories
+    pub fn is_ignored(&self, relative_path: &Path) -> bool {
+        let mut current = PathBuf::new();
+        let mut components = relative_path.components().peekable();
+        while let Some(component) = components.next() {
+            current.push(component);
+            let is_dir = components.peek().is_some();
+            if self.matched(&current, is_dir) == IgnoreMatch::Ignore {
+                return true;
+            }
+        }
+        false
+    }
+
+    // Deeper directories take precedence over their parents
+    pub fn matched(&self, relative_path: &Path, is_dir: bool) -> IgnoreMatch {
+        for ignore_file in self.files.iter().rev() {
+            match ignore_file.matched(relative_path, is_dir) {
+                IgnoreMatch::None => continue,
+                decided => return decided,
+            }
+        }
+        match &self.parent {
+            Some(parent) => parent.matched(relative_path, is_dir),
+            None => IgnoreMatch::None,
+        }
+    }
+}
+
+// core.excludesFile from the repository config, falling back to git's default
+// location. Paths outside the plugin's sandbox simply fail to read.
+fn global_excludes_file(root: &Path) -> Option<PathBuf> {
+    if let Ok(config) = fs::read_to_string(root.join(".git").join("config")) {
+        let mut in_core = false;
+        for line in config.lines() {
+            let line = line.trim();
+            if line.starts_with('[') {
+                in_core = line.eq_ignore_ascii_case("[core]");
+            } else if in_core {
+                if let Some((key, value)) = line.split_once('=') {
+                    if key.trim().eq_ignore_ascii_case("excludesfile") {
+                        let value = value.trim().trim_matches('"');
+                        return Some(match (value.strip_prefix("~/"), std::env::var("HOME")) {
+                            (Some(rest), Ok(home)) => PathBuf::from(home).join(rest),
+                            _ => PathBuf::from(value),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    if let Ok(xdg) = std::env::var("XDG_CONFIG_HOME") {
+        return Some(PathBuf::from(xdg).join("git").join("ignore"));
+    }
+    std::env::var("HOME").ok().map(|home| {
+        PathBuf::from(home)
+            .join(".config")
+            .join("git")
+            .join("ignore")
+    })
+}
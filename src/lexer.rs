@@ -0,0 +1,169 @@
+use memchr::{memchr, memchr2};
+
+// Where the lexer is at the end of a line, carried over to the next one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LexState {
+    Code,
+    BlockComment(usize), // Nesting depth, block comments nest in Rust
+    Str,                 // Inside "..." or b"..."
+    RawStr(usize),       // Inside r"..." or r#"..."#, with the number of '#'
+}
+
+// Splits Rust source into code and everything that only looks like code
+// (comments, strings, raw strings), one line at a time. Comments and strings
+// are skipped with memchr rather than byte by byte
+pub struct Lexer {
+    state: LexState,
+}
+
+impl Default for Lexer {
+    fn default() -> Self {
+        Lexer {
+            state: LexState::Code,
+        }
+    }
+}
+
+impl Lexer {
+    // Lexes one line (without its newline), collecting the braces and
+    // semicolons that are code into `tokens`. Returns whether the line started
+    // as code, and not inside a comment or string
+    pub fn lex_line(&mut self, line: &[u8], tokens: &mut Vec<u8>) -> bool {
+        tokens.clear();
+        let starts_in_code = self.state == LexState::Code;
+        let mut i = 0;
+        while i < line.len() {
+            i = match self.state {
+                LexState::Code => self.lex_code(line, i, tokens),
+                LexState::BlockComment(depth) => self.lex_block_comment(line, i, depth),
+                LexState::Str => self.lex_string(line, i),
+                LexState::RawStr(hashes) => self.lex_raw_string(line, i, hashes),
+            };
+        }
+        starts_in_code
+    }
+
+    // Handles one byte of code, or a whole literal or comment starting there,
+    // and returns where to continue
+    fn lex_code(&mut self, line: &[u8], i: usize, tokens: &mut Vec<u8>) -> usize {
+        match line[i] {
+            b'{' | b'}' | b';' => {
+                tokens.push(line[i]);
+                i + 1
+            }
+            b'/' if line.get(i + 1) == Some(&b'/') => line.len(), // Line comment
+            b'/' if line.get(i + 1) == Some(&b'*') => {
+                self.state = LexState::BlockComment(1);
+                i + 2
+            }
+            b'"' => {
+                self.state = LexState::Str;
+                i + 1
+            }
+            b'r' if !is_ident_byte(i.checked_sub(1).map(|p| line[p]))
+                || is_byte_prefix(line, i) =>
+            {
+                match raw_string_hashes(&line[i + 1..]) {
+                    Some(hashes) => {
+                        self.state = LexState::RawStr(hashes);
+                        i + 2 + hashes
+                    }
+                    None => i + 1,
+                }
+            }
+            b'\'' => skip_char_literal(line, i),
+            _ => i + 1,
+        }
+    }
+
+    fn lex_block_comment(&mut self, line: &[u8], i: usize, depth: usize) -> usize {
+        let next = match memchr2(b'*', b'/', &line[i..]) {
+            Some(offset) => i + offset,
+            None => return line.len(),
+        };
+        match (line[next], line.get(next + 1)) {
+            (b'*', Some(b'/')) => {
+                self.state = if depth == 1 {
+                    LexState::Code
+                } else {
+                    LexState::BlockComment(depth - 1)
+                };
+                next + 2
+            }
+            (b'/', Some(b'*')) => {
+                self.state = LexState::BlockComment(depth + 1);
+                next + 2
+            }
+            _ => next + 1,
+        }
+    }
+
+    fn lex_string(&mut self, line: &[u8], i: usize) -> usize {
+        match memchr2(b'"', b'\\', &line[i..]) {
+            Some(offset) if line[i + offset] == b'\\' => i + offset + 2, // Skip the escaped byte
+            Some(offset) => {
+                self.state = LexState::Code;
+                i + offset + 1
+            }
+            None => line.len(),
+        }
+    }
+
+    fn lex_raw_string(&mut self, line: &[u8], i: usize, hashes: usize) -> usize {
+        let quote = match memchr(b'"', &line[i..]) {
+            Some(offset) => i + offset,
+            None => return line.len(),
+        };
+        let closing = line[quote + 1..]
+            .iter()
+            .take(hashes)
+            .take_while(|&&b| b == b'#')
+            .count();
+        if closing == hashes {
+            self.state = LexState::Code;
+            quote + 1 + hashes
+        } else {
+            quote + 1
+        }
+    }
+}
+
+fn is_ident_byte(byte: Option<u8>) -> bool {
+    byte.is_some_and(|b| b.is_ascii_alphanumeric() || b == b'_')
+}
+
+// The `r` of a raw byte string, eg. br"..."
+fn is_byte_prefix(line: &[u8], i: usize) -> bool {
+    i > 0 && line[i - 1] == b'b' && !is_ident_byte(i.checked_sub(2).map(|p| line[p]))
+}
+
+// The number of '#' of a raw string opening right after its `r`, if any
+fn raw_string_hashes(rest: &[u8]) -> Option<usize> {
+    let hashes = rest.iter().take_while(|&&b| b == b'#').count();
+    (rest.get(hashes) == Some(&b'"')).then_some(hashes)
+}
+
+// Skips a char literal such as '{', '\'' or '\u{7f}', or just the quote of a
+// lifetime
+fn skip_char_literal(line: &[u8], i: usize) -> usize {
+    if line.get(i + 1) == Some(&b'\\') {
+        // The escaped char itself might be a quote, eg. '\''
+        return line
+            .get(i + 3..)
+            .and_then(|rest| memchr(b'\'', rest))
+            .map_or(line.len(), |end| i + 3 + end + 1);
+    }
+    // A single, possibly multi-byte, char followed by the closing quote
+    let char_len = match line.get(i + 1) {
+        Some(&b) if b < 0x80 => 1,
+        Some(&b) if b >= 0xF0 => 4,
+        Some(&b) if b >= 0xE0 => 3,
+        Some(_) => 2,
+        None => return i + 1,
+    };
+    if line.get(i + 1 + char_len) == Some(&b'\'') {
+        i + 2 + char_len
+    } else {
+        i + 1 // A lifetime or label
+    }
+}
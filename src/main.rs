@@ -3,30 +3,69 @@ use crate::unit::test_zellij::prelude::*;
 #[cfg(not(test))]
 use zellij_tile::prelude::*;
 
-use std::collections::BTreeMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, VecDeque};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
 #[cfg(test)]
 mod unit;
 
 mod app_state;
+mod cache;
+mod config;
+mod ctags;
+mod editor;
+mod extractors;
 mod files;
+mod ignore;
+mod lexer;
 mod pane;
+mod roots;
 mod search;
 mod search_state;
+mod symbol_index;
 mod ui;
 mod ui_state;
+mod worker;
+mod workspace;
 
 register_plugin!(State);
+register_worker!(IndexWorker, index_worker, INDEX_WORKER);
 
-use crate::app_state::AppState;
-use crate::files::get_all_files;
+use crate::app_state::{AppState, IndexStatus};
+use crate::config::{Config, ConfigError};
+use crate::ctags::TAGS_FILE_NAMES;
+use crate::files::{
+    host_relative_path, is_indexable_path, parse_git_ls_files, parse_visibility,
+    scan_file_definitions, IndexSource, TypeDefinition, TypeKind, Visibility,
+};
 use crate::pane::extract_editor_pane_metadata;
+use crate::roots::{mount_for, project_roots, roots_near_folder, ProjectRoot};
 use crate::search::{SearchEngine, SearchItem};
 use crate::search_state::SearchState;
-use crate::ui::UIRenderer;
+use crate::symbol_index::SymbolIndexFile;
+use crate::ui::{RenderContext, UIRenderer, DOC_DETAIL_ROWS};
 use crate::ui_state::UIState;
+use crate::worker::{
+    ImportRequest, ImportedSymbols, IndexBatch, IndexRequest, IndexWorker, CONTINUE_INDEXING,
+    DIRECTORY_BATCH, INDEX_BATCH, INDEX_DIRECTORY, INDEX_WORKER_NAME, LOAD_SYMBOL_INDEX, LOAD_TAGS,
+    START_INDEXING, SYMBOL_INDEX_LOADED, TAGS_LOADED,
+};
+use crate::workspace::CargoWorkspace;
+
+const GIT_LS_FILES_CONTEXT: &str = "git_ls_files";
+const ROOT_DIR_CONTEXT: &str = "root_dir";
+
+fn post_to_index_worker<T: serde::Serialize>(message: &str, request: &T) {
+    if let Ok(payload) = serde_json::to_string(request) {
+        post_message_to(PluginMessage::new_to_worker(
+            INDEX_WORKER_NAME,
+            message,
+            &payload,
+        ));
+    }
+}
 
 fn is_current_directory_git_repository() -> bool {
     // Check if the current host folder has a .git directory or file
@@ -36,38 +75,148 @@ fn is_current_directory_git_repository() -> bool {
 
 #[derive(Debug, Clone)]
 pub enum RustAssetSearchMode {
-    Struct(String),   // Search term after "struct"
-    Enum(String),     // Search term after "enum"
-    Function(String), // Search term after "fn"
+    Struct(String),    // Search term after "struct"
+    Enum(String),      // Search term after "enum"
+    Function(String),  // Search term after "fn"
+    Trait(String),     // Search term after "trait"
+    Impl(String),      // Search term after "impl"
+    TypeAlias(String), // Search term after "type"
+    Const(String),     // Search term after "const"
+    Static(String),    // Search term after "static"
+    Macro(String),     // Search term after "macro" or "macro_rules!"
+    Module(String),    // Search term after "mod"
+    Union(String),     // Search term after "union"
+    Variant(String),   // Search term after "variant"
+    Field(String),     // Search term after "field"
+    Test(String),      // Search term after "test"
+    Class(String),     // Search term after "class"
+    Interface(String), // Search term after "interface"
 }
 
-fn parse_rust_asset_search(search_term: &str) -> Option<RustAssetSearchMode> {
-    // Don't trim initially - we need to preserve trailing spaces
+impl RustAssetSearchMode {
+    pub fn term(&self) -> &str {
+        match self {
+            RustAssetSearchMode::Struct(term)
+            | RustAssetSearchMode::Enum(term)
+            | RustAssetSearchMode::Function(term)
+            | RustAssetSearchMode::Trait(term)
+            | RustAssetSearchMode::Impl(term)
+            | RustAssetSearchMode::TypeAlias(term)
+            | RustAssetSearchMode::Const(term)
+            | RustAssetSearchMode::Static(term)
+            | RustAssetSearchMode::Macro(term)
+            | RustAssetSearchMode::Module(term)
+            | RustAssetSearchMode::Union(term)
+            | RustAssetSearchMode::Variant(term)
+            | RustAssetSearchMode::Field(term)
+            | RustAssetSearchMode::Test(term)
+            | RustAssetSearchMode::Class(term)
+            | RustAssetSearchMode::Interface(term) => term,
+        }
+    }
 
-    if let Some(rest) = search_term.strip_prefix("struct ") {
-        Some(RustAssetSearchMode::Struct(rest.to_string()))
-    } else if let Some(rest) = search_term.strip_prefix("enum ") {
-        Some(RustAssetSearchMode::Enum(rest.to_string()))
-    } else if let Some(rest) = search_term.strip_prefix("fn ") {
-        Some(RustAssetSearchMode::Function(rest.to_string()))
-    } else {
-        // Case insensitive check
-        let lower = search_term.to_lowercase();
-        if let Some(_rest) = lower.strip_prefix("struct ") {
-            // Find the original casing for the search term after "struct "
-            let original_rest = &search_term[7..]; // Skip "struct " (7 chars)
-            Some(RustAssetSearchMode::Struct(original_rest.to_string()))
-        } else if let Some(_rest) = lower.strip_prefix("enum ") {
-            // Find the original casing for the search term after "enum "
-            let original_rest = &search_term[5..]; // Skip "enum " (5 chars)
-            Some(RustAssetSearchMode::Enum(original_rest.to_string()))
-        } else if let Some(_rest) = lower.strip_prefix("fn ") {
-            // Find the original casing for the search term after "fn "
-            let original_rest = &search_term[3..]; // Skip "fn " (3 chars)
-            Some(RustAssetSearchMode::Function(original_rest.to_string()))
-        } else {
-            None
+    // Whether this definition is searched in this mode
+    pub fn matches(&self, definition: &TypeDefinition) -> bool {
+        if let RustAssetSearchMode::Test(_) = self {
+            return definition.is_test;
         }
+        matches!(
+            (self, &definition.type_kind),
+            (RustAssetSearchMode::Struct(_), TypeKind::Struct)
+                | (RustAssetSearchMode::Enum(_), TypeKind::Enum)
+                | (RustAssetSearchMode::Function(_), TypeKind::Function)
+                | (RustAssetSearchMode::Trait(_), TypeKind::Trait)
+                | (RustAssetSearchMode::Impl(_), TypeKind::Impl)
+                | (RustAssetSearchMode::TypeAlias(_), TypeKind::TypeAlias)
+                | (RustAssetSearchMode::Const(_), TypeKind::Const)
+                | (RustAssetSearchMode::Static(_), TypeKind::Static)
+                | (RustAssetSearchMode::Macro(_), TypeKind::Macro)
+                | (RustAssetSearchMode::Module(_), TypeKind::Module)
+                | (RustAssetSearchMode::Union(_), TypeKind::Union)
+                | (RustAssetSearchMode::Variant(_), TypeKind::Variant)
+                | (RustAssetSearchMode::Field(_), TypeKind::Field)
+                | (RustAssetSearchMode::Class(_), TypeKind::Class)
+                | (RustAssetSearchMode::Interface(_), TypeKind::Interface)
+        )
+    }
+}
+
+// A search for Rust items, eg. "pub(crate) fn parse"
+pub struct RustAssetSearch {
+    pub mode: RustAssetSearchMode,
+    pub visibility: Option<Visibility>, // Only items declared exactly this visible
+}
+
+impl RustAssetSearch {
+    pub fn term(&self) -> &str {
+        self.mode.term()
+    }
+
+    pub fn matches(&self, definition: &TypeDefinition) -> bool {
+        self.mode.matches(definition)
+            && self
+                .visibility
+                .as_ref()
+                .is_none_or(|visibility| *visibility == definition.visibility)
+    }
+}
+
+type SearchModeConstructor = fn(String) -> RustAssetSearchMode;
+
+// The most used first, as the hint lists as many as fit. The keywords of
+// other languages search the closest kind, eg. "def " searches functions
+pub const RUST_ASSET_SEARCH_PREFIXES: [(&str, SearchModeConstructor); 20] = [
+    ("struct ", RustAssetSearchMode::Struct),
+    ("enum ", RustAssetSearchMode::Enum),
+    ("fn ", RustAssetSearchMode::Function),
+    ("trait ", RustAssetSearchMode::Trait),
+    ("impl ", RustAssetSearchMode::Impl),
+    ("type ", RustAssetSearchMode::TypeAlias),
+    ("const ", RustAssetSearchMode::Const),
+    ("static ", RustAssetSearchMode::Static),
+    ("macro ", RustAssetSearchMode::Macro),
+    ("macro_rules! ", RustAssetSearchMode::Macro),
+    ("mod ", RustAssetSearchMode::Module),
+    ("union ", RustAssetSearchMode::Union),
+    ("variant ", RustAssetSearchMode::Variant),
+    ("field ", RustAssetSearchMode::Field),
+    ("test ", RustAssetSearchMode::Test),
+    ("class ", RustAssetSearchMode::Class),
+    ("interface ", RustAssetSearchMode::Interface),
+    ("function ", RustAssetSearchMode::Function),
+    ("func ", RustAssetSearchMode::Function),
+    ("def ", RustAssetSearchMode::Function),
+];
+
+fn parse_rust_asset_search(search_term: &str) -> Option<RustAssetSearch> {
+    // An optional visibility comes first, like in the declaration
+    let (visibility, after_visibility) = parse_visibility(search_term.as_bytes());
+    let visibility = (after_visibility > 0).then_some(visibility);
+    let search_term = &search_term[after_visibility..];
+
+    // Don't trim initially - we need to preserve trailing spaces
+    // The keyword is case insensitive, the rest keeps its original casing
+    RUST_ASSET_SEARCH_PREFIXES
+        .iter()
+        .find(|(prefix, _)| {
+            search_term
+                .get(..prefix.len())
+                .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+        })
+        .map(|(prefix, mode)| RustAssetSearch {
+            mode: mode(search_term[prefix.len()..].to_string()),
+            visibility,
+        })
+}
+
+// Splits a leading "crate:name" filter off the search term
+fn split_crate_filter(search_term: &str) -> (Option<&str>, &str) {
+    match search_term.strip_prefix("crate:") {
+        Some(rest) => match rest.split_once(' ') {
+            Some((crate_name, rest)) => (Some(crate_name), rest),
+            None => (Some(rest), ""),
+        },
+        None => (None, search_term),
     }
 }
 
@@ -82,24 +231,46 @@ pub struct State {
     request_ids: Vec<String>,
     initial_cwd: Option<PathBuf>,
     searching_for_git_repo: bool,
+    index_generation: usize,
+    config: Config,
+    roots: Vec<PathBuf>, // Host paths of the project roots, the first one is the project folder
+    root_request_ids: Vec<String>,
+    pending_roots: VecDeque<ProjectRoot>,
+    finished_roots_status: IndexStatus, // What the roots indexed so far in this scan added up to
+    remounting: bool, // Waiting for the folder holding all roots to become the host folder
 }
 
 impl ZellijPlugin for State {
-    fn load(&mut self, _configuration: BTreeMap<String, String>) {
-        request_permission(&[
+    fn load(&mut self, configuration: BTreeMap<String, String>) {
+        let (config, config_errors) = Config::from_configuration(&configuration);
+        self.config = config;
+        self.ui_renderer.set_config_errors(config_errors);
+
+        let permissions = vec![
             PermissionType::ReadApplicationState,
             PermissionType::ChangeApplicationState,
             PermissionType::OpenFiles,
             PermissionType::FullHdAccess,
             PermissionType::MessageAndLaunchOtherPlugins,
-        ]);
-        subscribe(&[
+            // Tests can be run whatever the configuration
+            PermissionType::RunCommands,
+        ];
+        let mut events = vec![
             EventType::PaneUpdate,
             EventType::Key,
             EventType::PermissionRequestResult,
             EventType::TabUpdate,
             EventType::HostFolderChanged,
-        ]);
+            EventType::CustomMessage,
+            EventType::FileSystemCreate,
+            EventType::FileSystemUpdate,
+            EventType::FileSystemDelete,
+        ];
+        if self.config.index_source == IndexSource::Git {
+            events.push(EventType::RunCommandResult);
+        }
+        request_permission(&permissions);
+        subscribe(&events);
 
         self.initial_cwd = Some(get_plugin_ids().initial_cwd);
         self.update_host_folder(None);
@@ -111,6 +282,7 @@ impl ZellijPlugin for State {
             Event::PermissionRequestResult(_) => {
                 let own_plugin_id = get_plugin_ids().plugin_id;
                 rename_plugin_pane(own_plugin_id, "Grab...");
+                watch_filesystem();
 
                 self.searching_for_git_repo = true;
                 self.start_git_repository_search();
@@ -118,8 +290,57 @@ impl ZellijPlugin for State {
             Event::TabUpdate(tab_info) => {
                 self.tabs = tab_info;
             }
+            Event::CustomMessage(message, payload) if message == INDEX_BATCH => {
+                should_render = self.apply_index_batch(&payload);
+            }
+            Event::CustomMessage(message, payload) if message == DIRECTORY_BATCH => {
+                should_render = self.apply_directory_batch(&payload);
+            }
+            Event::CustomMessage(message, payload) if message == TAGS_LOADED => {
+                should_render = self.apply_imported_tags(&payload);
+            }
+            Event::CustomMessage(message, payload) if message == SYMBOL_INDEX_LOADED => {
+                should_render = self.apply_imported_index(&payload);
+            }
+            Event::RunCommandResult(exit_code, stdout, _stderr, context) => {
+                let is_current_scan = context
+                    .get(GIT_LS_FILES_CONTEXT)
+                    .is_some_and(|generation| generation.parse() == Ok(self.index_generation));
+                let root = context.get(ROOT_DIR_CONTEXT).and_then(|dir| {
+                    self.app_state
+                        .get_file_tags()
+                        .roots
+                        .iter()
+                        .find(|root| root.dir == Path::new(dir))
+                        .cloned()
+                });
+                if let (true, Some(root)) = (is_current_scan, root) {
+                    // Without git (or outside a repository) walk the folder instead
+                    let files = (exit_code == Some(0)).then(|| {
+                        parse_git_ls_files(&stdout)
+                            .into_iter()
+                            .map(|file| root.dir.join(file))
+                            .collect()
+                    });
+                    self.request_index(&root, files);
+                }
+            }
+            Event::FileSystemCreate(paths) | Event::FileSystemUpdate(paths)
+                if self.index_generation > 0 =>
+            {
+                self.index_changed_paths(paths);
+                should_render = true;
+            }
+            Event::FileSystemDelete(paths) if self.index_generation > 0 => {
+                for (path, _) in paths {
+                    let relative_path = host_relative_path(&path, self.app_state.get_cwd());
+                    self.app_state.remove_path(&relative_path);
+                }
+                self.refresh_search_results();
+                should_render = true;
+            }
             Event::PaneUpdate(pane_manifest) => {
-                let panes = extract_editor_pane_metadata(&pane_manifest);
+                let panes = extract_editor_pane_metadata(&pane_manifest, &self.config.editors);
                 self.app_state.update_panes(panes);
                 self.adjust_selection_after_pane_update();
                 self.update_search_results();
@@ -128,11 +349,22 @@ impl ZellijPlugin for State {
             Event::HostFolderChanged(new_host_folder) => {
                 if let Some(initial_cwd) = self.initial_cwd.take() {
                     change_host_folder(initial_cwd);
+                } else if self.remounting {
+                    // Everything indexed so far is relative to the previous folder
+                    self.remounting = false;
+                    self.app_state.set_cwd(new_host_folder);
+                    self.app_state.clear_index();
+                    self.index_roots();
+                    self.update_search_results();
                 } else if self.searching_for_git_repo {
                     self.continue_git_repository_search(new_host_folder);
                 } else {
                     let user_selected = self.app_state.is_user_selected_directory();
                     self.app_state.set_user_selected_directory(false); // Reset flag after use
+                    if user_selected {
+                        // The selected folder replaces the project roots
+                        self.roots.clear();
+                    }
                     self.update_host_folder_with_scan_control(Some(new_host_folder), user_selected);
                 }
                 should_render = true;
@@ -193,6 +425,13 @@ impl ZellijPlugin for State {
                     );
                     should_render = true;
                 }
+                BareKey::Char('a') if key.has_modifiers(&[KeyModifier::Ctrl]) => {
+                    self.request_additional_root();
+                    should_render = true;
+                }
+                BareKey::Char('r') if key.has_modifiers(&[KeyModifier::Ctrl]) => {
+                    self.run_selected_test();
+                }
                 _ => {}
             },
             _ => {}
@@ -203,6 +442,10 @@ impl ZellijPlugin for State {
     fn pipe(&mut self, pipe_message: PipeMessage) -> bool {
         if pipe_message.name == "filepicker_result" {
             match (pipe_message.payload, pipe_message.args.get("request_id")) {
+                (Some(payload), Some(request_id)) if self.root_request_ids.contains(request_id) => {
+                    self.root_request_ids.retain(|p| p != request_id);
+                    self.add_root(PathBuf::from(payload));
+                }
                 (Some(payload), Some(request_id)) => {
                     match self.request_ids.iter().position(|p| p == request_id) {
                         Some(request_id_position) => {
@@ -227,40 +470,43 @@ impl ZellijPlugin for State {
         self.ui_state.update_last_rows(rows);
 
         let table_count = self.search_state.get_current_display_count();
+        let selected_docs = self.selected_docs();
 
-        let available_rows = rows.saturating_sub(8);
+        let detail_rows = if selected_docs.is_empty() {
+            0
+        } else {
+            DOC_DETAIL_ROWS
+        };
+        let available_rows = rows.saturating_sub(8 + detail_rows);
         let visible_items = available_rows.min(table_count);
 
         self.ui_state
             .adjust_scroll_for_selection(visible_items, table_count);
 
-        let (displayed_files, remaining_files) = self
-            .search_engine
-            .get_displayed_files(self.search_state.get_term(), self.app_state.get_files());
-
         self.ui_renderer.render_plugin(
             rows,
             cols,
-            self.search_state.get_term(),
-            self.app_state.get_panes(),
-            self.search_state.get_files_panes_results(),
-            self.ui_state.selected_index,
-            self.ui_state.scroll_offset,
-            &displayed_files,
-            remaining_files,
-            self.app_state.get_cwd(),
+            &RenderContext {
+                search_term: self.search_state.get_term(),
+                results: self.search_state.get_files_panes_results(),
+                selected_index: self.ui_state.selected_index,
+                scroll_offset: self.ui_state.scroll_offset,
+                cwd: self.app_state.get_cwd(),
+                index_status: self.app_state.get_index_status(),
+            },
         );
     }
 }
 
 impl State {
     fn update_search_results(&mut self) {
-        let rust_assets = self.app_state.get_rust_assets();
+        let rust_assets = self.app_state.rust_asset_refs();
         let results = self.search_engine.search(
             self.search_state.get_term(),
             self.app_state.get_panes(),
             self.app_state.get_files(),
             &rust_assets,
+            self.app_state.get_file_tags(),
             self.app_state.get_cwd(),
         );
 
@@ -275,6 +521,16 @@ impl State {
         }
     }
 
+    // Like update_search_results, but keeps the current selection in place
+    fn refresh_search_results(&mut self) {
+        let selected_index = self.ui_state.get_selected_index();
+        self.update_search_results();
+        if selected_index.is_some() {
+            self.ui_state.set_selected_index(selected_index);
+            self.adjust_selection_after_pane_update();
+        }
+    }
+
     fn move_selection_down(&mut self) {
         let table_count = self.search_state.get_current_display_count();
 
@@ -291,6 +547,20 @@ impl State {
         }
     }
 
+    // The docs of the selected item, shown below the results
+    fn selected_docs(&self) -> String {
+        let selected = self.ui_state.get_selected_index().and_then(|index| {
+            self.search_state
+                .get_current_display_results()
+                .get(index)
+                .cloned()
+        });
+        match selected.map(|result| result.item) {
+            Some(SearchItem::RustAsset(rust_asset)) => rust_asset.docs,
+            _ => String::new(),
+        }
+    }
+
     fn focus_selected_item(&mut self) {
         if let Some(selected_index) = self.ui_state.get_selected_index() {
             let display_results = self.search_state.get_current_display_results();
@@ -309,21 +579,96 @@ impl State {
             SearchItem::File(file) => {
                 let should_close_plugin = true;
                 open_file_in_place_of_plugin(
-                    FileToOpen::new(self.app_state.get_cwd().join(file)),
+                    self.file_to_open(file),
                     should_close_plugin,
                     Default::default(),
                 );
             }
             SearchItem::RustAsset(rust_asset) => {
                 let should_close_plugin = true;
-                let mut file_to_open =
-                    FileToOpen::new(self.app_state.get_cwd().join(rust_asset.file_path.as_ref()));
-                file_to_open.line_number = Some(rust_asset.line_number);
-                open_file_in_place_of_plugin(file_to_open, should_close_plugin, Default::default());
+                let mut file_to_open = self.file_to_open(&rust_asset.file_path);
+                match &self.config.editor {
+                    // The column of tags addressed by line number is unknown,
+                    // those open at their line as without an editor
+                    Some(editor) if rust_asset.column > 0 => {
+                        let command = CommandToRun {
+                            path: editor.command.clone().into(),
+                            args: editor.args(
+                                &file_to_open.path,
+                                rust_asset.line_number,
+                                rust_asset.column,
+                                rust_asset.char_column,
+                            ),
+                            cwd: file_to_open.cwd,
+                        };
+                        open_command_pane_in_place_of_plugin(
+                            command,
+                            should_close_plugin,
+                            BTreeMap::new(),
+                        );
+                    }
+                    _ => {
+                        file_to_open.line_number = Some(rust_asset.line_number);
+                        open_file_in_place_of_plugin(
+                            file_to_open,
+                            should_close_plugin,
+                            Default::default(),
+                        );
+                    }
+                }
             }
         }
     }
 
+    // Runs the selected test, or all tests of the selected `#[cfg(test)]`
+    // module, in a command pane replacing the picker
+    fn run_selected_test(&mut self) {
+        let selected = self.ui_state.get_selected_index().and_then(|index| {
+            self.search_state
+                .get_current_display_results()
+                .get(index)
+                .cloned()
+        });
+        let test = match selected.map(|result| result.item) {
+            Some(SearchItem::RustAsset(test)) if test.is_test => test,
+            _ => return,
+        };
+        // Cargo runs from the test's own project root, like the editor does
+        let cwd = match self.app_state.get_file_tags().root_for(&test.file_path) {
+            Some(root) => root.path.clone(),
+            None => self.app_state.get_cwd().clone(),
+        };
+        let mut args = vec!["test".to_owned()];
+        if let Some(crate_name) = &test.crate_name {
+            args.extend(["-p".to_owned(), crate_name.to_string()]);
+        }
+        args.push(test.qualified_name());
+        if test.type_kind == TypeKind::Function {
+            args.extend(["--".to_owned(), "--exact".to_owned()]);
+        }
+        let command = CommandToRun {
+            path: "cargo".into(),
+            args,
+            cwd: Some(cwd),
+        };
+        let close_plugin_after_replace = true;
+        open_command_pane_in_place_of_plugin(command, close_plugin_after_replace, BTreeMap::new());
+    }
+
+    // Resolves an indexed path against its own project root, which is also
+    // where the editor starts
+    fn file_to_open(&self, file: &Path) -> FileToOpen {
+        match self.app_state.get_file_tags().root_for(file) {
+            Some(root) => {
+                let relative_path = file.strip_prefix(&root.dir).unwrap_or(file);
+                let mut file_to_open = FileToOpen::new(root.path.join(relative_path));
+                file_to_open.cwd = Some(root.path.clone());
+                file_to_open
+            }
+            None => FileToOpen::new(self.app_state.get_cwd().join(file)),
+        }
+    }
+
     fn adjust_selection_after_pane_update(&mut self) {
         let table_count = self.search_state.get_current_display_count();
 
@@ -347,15 +692,397 @@ impl State {
             && (is_current_directory_git_repository() || user_selected);
 
         if should_scan {
-            if let Ok(files_and_rust_assets) = get_all_files("/host") {
-                let files: Vec<PathBuf> = files_and_rust_assets.keys().cloned().collect();
-                self.app_state.update_files(files);
-                self.app_state.update_rust_assets(files_and_rust_assets)
-            }
+            self.start_indexing();
         }
         self.update_search_results();
     }
 
+    fn start_indexing(&mut self) {
+        self.roots = self.project_root_paths();
+        match mount_for(&self.roots) {
+            Some(mount) if &mount != self.app_state.get_cwd() => {
+                // Indexing continues once the folder is mounted
+                self.remounting = true;
+                change_host_folder(mount);
+            }
+            _ => self.index_roots(),
+        }
+    }
+
+    // The current folder plus the configured roots, until roots are changed at runtime
+    fn project_root_paths(&mut self) -> Vec<PathBuf> {
+        if !self.roots.is_empty() {
+            return self.roots.clone();
+        }
+        let cwd = self.app_state.get_cwd();
+        let configured = self
+            .config
+            .roots
+            .iter()
+            .map(|root| cwd.join(root))
+            .collect();
+        self.roots_near_project_folder(configured)
+    }
+
+    fn add_root(&mut self, root: PathBuf) {
+        let mut roots = self.project_root_paths();
+        roots.push(root);
+        let roots = self.roots_near_project_folder(roots);
+        if roots != self.roots {
+            self.roots = roots;
+            self.start_indexing();
+        }
+    }
+
+    // Roots further away than the folder holding the project folder are
+    // reported rather than indexed, as only one folder is mounted
+    fn roots_near_project_folder(&mut self, paths: Vec<PathBuf>) -> Vec<PathBuf> {
+        let folder = self
+            .roots
+            .first()
+            .unwrap_or_else(|| self.app_state.get_cwd())
+            .clone();
+        let (roots, outside) = roots_near_folder(&folder, paths);
+        let root_errors = outside
+            .into_iter()
+            .map(|root| ConfigError {
+                key: "roots".to_owned(),
+                value: root.display().to_string(),
+                expected: Some("a folder inside or next to the current one"),
+            })
+            .collect();
+        self.ui_renderer.set_root_errors(root_errors);
+        roots
+    }
+
+    // Indexes every project root below the current host folder, one after the other
+    fn index_roots(&mut self) {
+        self.index_generation += 1;
+        let roots = project_roots(&self.roots, self.app_state.get_cwd());
+        self.app_state.set_roots(roots.clone());
+        self.reload_workspace();
+        if self.config.ctags {
+            for root in &roots {
+                self.import_tags(&root.dir);
+            }
+        }
+        for root in &roots {
+            self.import_index(&root.dir);
+        }
+        self.app_state.set_index_status(IndexStatus {
+            in_progress: true,
+            ..Default::default()
+        });
+        self.finished_roots_status = IndexStatus::default();
+        self.pending_roots = roots.into();
+        self.index_next_root();
+    }
+
+    // Returns false once every root of the current scan was indexed
+    fn index_next_root(&mut self) -> bool {
+        let root = match self.pending_roots.pop_front() {
+            Some(root) => root,
+            None => return false,
+        };
+        match self.config.index_source {
+            IndexSource::Walk => self.request_index(&root, None),
+            IndexSource::Git => {
+                // The file list arrives as a RunCommandResult event
+                let mut context = BTreeMap::new();
+                context.insert(
+                    GIT_LS_FILES_CONTEXT.to_owned(),
+                    self.index_generation.to_string(),
+                );
+                context.insert(
+                    ROOT_DIR_CONTEXT.to_owned(),
+                    root.dir.to_string_lossy().into_owned(),
+                );
+                run_command_with_env_variables_and_cwd(
+                    &[
+                        "git",
+                        "ls-files",
+                        "--cached",
+                        "--others",
+                        "--exclude-standard",
+                        "-z",
+                    ],
+                    BTreeMap::new(),
+                    root.path.clone(),
+                    context,
+                );
+            }
+        }
+        true
+    }
+
+    // Asks the index worker for the definitions of a root's tags file, which
+    // covers languages without a built-in extractor
+    fn import_tags(&mut self, root_dir: &Path) {
+        let request = ImportRequest {
+            generation: self.index_generation,
+            host: PathBuf::from("/host"),
+            root_dir: root_dir.to_path_buf(),
+            index_file: None,
+        };
+        post_to_index_worker(LOAD_TAGS, &request);
+    }
+
+    // Asks the index worker for the definitions of a root's SCIP or LSIF
+    // index, if it has one
+    fn import_index(&mut self, root_dir: &Path) {
+        let index_file = match &self.config.symbol_index {
+            SymbolIndexFile::Disabled => return,
+            SymbolIndexFile::DefaultNames => None,
+            SymbolIndexFile::Path(path) => Some(path.clone()),
+        };
+        let request = ImportRequest {
+            generation: self.index_generation,
+            host: PathBuf::from("/host"),
+            root_dir: root_dir.to_path_buf(),
+            index_file,
+        };
+        post_to_index_worker(LOAD_SYMBOL_INDEX, &request);
+    }
+
+    // Symbols read by the index worker, None if they belong to an outdated scan
+    fn imported_symbols(&self, payload: &str) -> Option<ImportedSymbols> {
+        serde_json::from_str::<ImportedSymbols>(payload)
+            .ok()
+            .filter(|imported| imported.generation == self.index_generation)
+    }
+
+    // Returns true if the tags belong to the current scan
+    fn apply_imported_tags(&mut self, payload: &str) -> bool {
+        let imported = match self.imported_symbols(payload) {
+            Some(imported) => imported,
+            None => return false,
+        };
+        let root_dir = imported.root_dir.clone();
+        self.app_state
+            .import_symbols(&root_dir, imported.into_symbols());
+        self.refresh_search_results();
+        true
+    }
+
+    // Returns true if the index belongs to the current scan
+    fn apply_imported_index(&mut self, payload: &str) -> bool {
+        let imported = match self.imported_symbols(payload) {
+            Some(imported) => imported,
+            None => return false,
+        };
+        let root_dir = imported.root_dir.clone();
+        self.app_state
+            .import_index(&root_dir, imported.into_symbols());
+        self.refresh_search_results();
+        true
+    }
+
+    fn reload_workspace(&mut self) {
+        let dirs: Vec<PathBuf> = self
+            .app_state
+            .get_file_tags()
+            .roots
+            .iter()
+            .map(|root| root.dir.clone())
+            .collect();
+        self.app_state
+            .set_workspace(CargoWorkspace::load_all(Path::new("/host"), &dirs));
+    }
+
+    // Asks the index worker to index `files` (relative to the host folder), or
+    // to walk the root if None
+    fn request_index(&mut self, root: &ProjectRoot, files: Option<Vec<PathBuf>>) {
+        let request = self.index_request(root, files);
+        post_to_index_worker(START_INDEXING, &request);
+    }
+
+    // Asks the index worker to walk a folder that appeared after the scan
+    // (relative to the root), within what is left of the root's file limit
+    fn request_directory_index(&mut self, root: &ProjectRoot, directory: PathBuf) {
+        let mut request = self.index_request(root, None);
+        let file_tags = self.app_state.get_file_tags();
+        let indexed_count = self
+            .app_state
+            .get_files()
+            .iter()
+            .filter(|file| file_tags.root_for(file).map(|other| &other.dir) == Some(&root.dir))
+            .count();
+        request.config.file_limit = request.config.file_limit.saturating_sub(indexed_count);
+        request.cache_dir = None;
+        request.directory = Some(directory);
+        post_to_index_worker(INDEX_DIRECTORY, &request);
+    }
+
+    fn index_request(&self, root: &ProjectRoot, files: Option<Vec<PathBuf>>) -> IndexRequest {
+        let mut config = self.config.scan.clone();
+        // Roots inside this one are indexed on their own
+        config.ignore_patterns.extend(
+            self.app_state
+                .get_file_tags()
+                .roots
+                .iter()
+                .filter_map(|other| other.dir.strip_prefix(&root.dir).ok())
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .map(|dir| format!("/{}/", dir.display())),
+        );
+        IndexRequest {
+            generation: self.index_generation,
+            root: PathBuf::from("/host"),
+            config,
+            repository_root: root.path.clone(),
+            cache_dir: Some(PathBuf::from("/data")),
+            files,
+            subdirectory: root.dir.clone(),
+            directory: None,
+        }
+    }
+
+    // Applies a batch streamed back by the index worker and asks for the next
+    // one, returns true if the batch belongs to the current scan
+    fn apply_index_batch(&mut self, payload: &str) -> bool {
+        let mut batch: IndexBatch = match serde_json::from_str(payload) {
+            Ok(batch) => batch,
+            Err(_) => return false,
+        };
+        if batch.generation != self.index_generation {
+            return false;
+        }
+
+        if !batch.done {
+            post_message_to(PluginMessage::new_to_worker(
+                INDEX_WORKER_NAME,
+                CONTINUE_INDEXING,
+                &self.index_generation.to_string(),
+            ));
+        }
+
+        let mut index_status = IndexStatus {
+            in_progress: !batch.done,
+            indexed_count: self.finished_roots_status.indexed_count + batch.indexed_count,
+            truncated_at: batch
+                .truncated_at
+                .or(self.finished_roots_status.truncated_at),
+        };
+        if batch.done {
+            self.finished_roots_status = index_status.clone();
+            index_status.in_progress = self.index_next_root();
+        }
+        self.app_state.set_index_status(index_status);
+        for removed in &batch.removed {
+            self.app_state.remove_path(removed);
+        }
+        self.app_state
+            .mark_symlinked_files(std::mem::take(&mut batch.symlinked));
+        self.app_state
+            .add_indexed_files(batch.into_files_and_rust_assets());
+        self.refresh_search_results();
+        true
+    }
+
+    // Merges a batch of a folder indexed after the scan, returns true if the
+    // batch belongs to the current scan
+    fn apply_directory_batch(&mut self, payload: &str) -> bool {
+        let mut batch: IndexBatch = match serde_json::from_str(payload) {
+            Ok(batch) => batch,
+            Err(_) => return false,
+        };
+        if batch.generation != self.index_generation {
+            return false;
+        }
+
+        // The worker only knows the budget that was left of the limit
+        let truncated_at = batch.truncated_at.map(|_| self.config.scan.file_limit);
+        let mut index_status = self.app_state.get_index_status().clone();
+        index_status.indexed_count += batch.files.len();
+        index_status.truncated_at = index_status.truncated_at.or(truncated_at);
+        self.finished_roots_status.indexed_count += batch.files.len();
+        self.finished_roots_status.truncated_at =
+            self.finished_roots_status.truncated_at.or(truncated_at);
+        self.app_state.set_index_status(index_status);
+        self.app_state
+            .mark_symlinked_files(std::mem::take(&mut batch.symlinked));
+        self.app_state
+            .add_indexed_files(batch.into_files_and_rust_assets());
+        self.refresh_search_results();
+        true
+    }
+
+    // Patches the index for paths created or modified after the initial scan,
+    // rescanning only the affected files
+    fn index_changed_paths(&mut self, paths: Vec<(PathBuf, Option<FileMetadata>)>) {
+        let root = PathBuf::from("/host");
+        for (path, metadata) in paths {
+            let relative_path = host_relative_path(&path, self.app_state.get_cwd());
+            let host_path = root.join(&relative_path);
+            let is_dir = match metadata {
+                Some(metadata) => metadata.is_dir,
+                None => host_path.is_dir(),
+            };
+            // Paths are matched against the ignore files of their own root
+            let (index_root, project_root, root_relative_path) =
+                match self.app_state.get_file_tags().root_for(&relative_path) {
+                    Some(project_root) => (
+                        project_root.clone(),
+                        root.join(&project_root.dir),
+                        relative_path
+                            .strip_prefix(&project_root.dir)
+                            .unwrap_or(&relative_path)
+                            .to_path_buf(),
+                    ),
+                    None => continue,
+                };
+            // Tags files are usually ignored, but still read
+            let is_tags_file = TAGS_FILE_NAMES
+                .iter()
+                .any(|name| root_relative_path == Path::new(name));
+            if self.config.ctags && is_tags_file {
+                let root_dir = relative_path
+                    .parent()
+                    .unwrap_or(Path::new(""))
+                    .to_path_buf();
+                self.import_tags(&root_dir);
+                continue;
+            }
+            if self.config.symbol_index.is_index_file(&root_relative_path) {
+                let root_dir = relative_path
+                    .ancestors()
+                    .nth(root_relative_path.components().count())
+                    .unwrap_or(Path::new(""))
+                    .to_path_buf();
+                self.import_index(&root_dir);
+                continue;
+            }
+            // The index no longer matches an edited file
+            if !is_dir {
+                self.app_state.discard_index_symbols(&relative_path);
+            }
+            if root_relative_path.as_os_str().is_empty()
+                || !is_indexable_path(
+                    &project_root,
+                    &root_relative_path,
+                    is_dir,
+                    &self.config.scan,
+                )
+            {
+                continue;
+            }
+
+            if relative_path.file_name() == Some(OsStr::new("Cargo.toml")) {
+                // Workspace members might have changed
+                self.reload_workspace();
+            }
+
+            if is_dir {
+                // A folder moved into the project, index everything below it
+                self.request_directory_index(&index_root, root_relative_path);
+            } else if host_path.is_file() {
+                let definitions = scan_file_definitions(&relative_path, &self.config.scan);
+                self.app_state.upsert_file(relative_path, definitions);
+            }
+        }
+        self.refresh_search_results();
+    }
+
     fn start_git_repository_search(&mut self) {
         let initial_cwd = get_plugin_ids().initial_cwd;
         change_host_folder(initial_cwd);
@@ -381,6 +1108,29 @@ impl State {
         }
     }
 
+    fn request_additional_root(&mut self) {
+        let request_id = Uuid::new_v4();
+        let mut config = BTreeMap::new();
+        let mut args = BTreeMap::new();
+        self.root_request_ids.push(request_id.to_string());
+        config.insert("request_id".to_owned(), request_id.to_string());
+        config.insert(
+            "caller_cwd".to_owned(),
+            self.app_state.get_cwd().display().to_string(),
+        );
+        args.insert("request_id".to_owned(), request_id.to_string());
+        pipe_message_to_plugin(
+            MessageToPlugin::new("filepicker")
+                .with_plugin_url("filepicker")
+                .with_plugin_config(config)
+                .new_plugin_instance_should_have_pane_title(
+                    "Select a folder to add to the picker...",
+                )
+                .new_plugin_instance_should_replace_pane(PaneId::Plugin(get_plugin_ids().plugin_id))
+                .with_args(args),
+        );
+    }
+
     fn request_folder_selection(&mut self) {
         let request_id = Uuid::new_v4();
         let mut config = BTreeMap::new();
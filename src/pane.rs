@@ -9,12 +9,21 @@ pub struct PaneMetadata {
     pub title: String,
 }
 
-pub fn extract_editor_pane_metadata(manifest: &PaneManifest) -> Vec<PaneMetadata> {
+// Overridable through the `editors` plugin configuration
+pub const DEFAULT_EDITORS: [&str; 20] = [
+    "vim", "nvim", "neovim", "vi", "emacs", "nano", "micro", "helix", "hx", "code", "subl", "atom",
+    "notepad", "kak", "kakoune", "joe", "mcedit", "ed", "ex", "pico",
+];
+
+pub fn extract_editor_pane_metadata(
+    manifest: &PaneManifest,
+    editors: &[String],
+) -> Vec<PaneMetadata> {
     let mut result = Vec::new();
 
     for (_, panes) in &manifest.panes {
         for pane_info in panes {
-            if is_editor_pane(pane_info) {
+            if is_editor_pane(pane_info, editors) {
                 let pane_id = if pane_info.is_plugin {
                     PaneId::Plugin(pane_info.id)
                 } else {
@@ -33,15 +42,10 @@ pub fn extract_editor_pane_metadata(manifest: &PaneManifest) -> Vec<PaneMetadata
     result
 }
 
-fn is_editor_pane(pane_info: &PaneInfo) -> bool {
-    let common_editors = [
-        "vim", "nvim", "neovim", "vi", "emacs", "nano", "micro", "helix", "hx", "code", "subl",
-        "atom", "notepad", "kak", "kakoune", "joe", "mcedit", "ed", "ex", "pico",
-    ];
-
+fn is_editor_pane(pane_info: &PaneInfo, common_editors: &[String]) -> bool {
     if let Some(ref command) = pane_info.terminal_command {
         let command_lower = command.to_lowercase();
-        if common_editors.iter().any(|&editor| {
+        if common_editors.iter().any(|editor| {
             command_lower.contains(editor)
                 || command_lower.starts_with(&format!("{} ", editor))
                 || command_lower.ends_with(&format!("/{}", editor))
@@ -51,7 +55,7 @@ fn is_editor_pane(pane_info: &PaneInfo) -> bool {
     }
 
     let title_lower = pane_info.title.to_lowercase();
-    common_editors.iter().any(|&editor| {
+    common_editors.iter().any(|editor| {
         title_lower.contains(editor)
             || title_lower.starts_with(&format!("{} ", editor))
             || title_lower.contains(&format!(" {} ", editor))
@@ -0,0 +1,113 @@
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+
+// A folder indexed by the picker, more than one can be searched at once
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectRoot {
+    pub path: PathBuf, // Host path
+    pub dir: PathBuf,  // Relative to the folder mounted at /host, empty if it is that folder
+    pub label: Rc<str>,
+}
+
+// Folders this close to `/`, eg. `/home/user`, are never mounted to reach
+// roots next to the current folder: the whole tree below them would be
+// watched
+const MIN_MOUNT_DEPTH: usize = 3;
+
+// The current folder first, then the other roots inside it or next to it (in
+// the folder that holds it), which are indexed on their own. Roots further
+// away would need a folder too high up to be mounted, so they are returned
+// apart
+pub fn roots_near_folder(folder: &Path, paths: Vec<PathBuf>) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let parent = folder
+        .parent()
+        .filter(|parent| parent.components().count() > MIN_MOUNT_DEPTH);
+    let (near, outside): (Vec<PathBuf>, Vec<PathBuf>) = paths
+        .into_iter()
+        .map(|path| normalize(&path))
+        .partition(|path| {
+            path.starts_with(folder)
+                || parent
+                    .is_some_and(|parent| path.starts_with(parent) && !folder.starts_with(path))
+        });
+    let others = near.into_iter().filter(|path| path != folder).collect();
+    let roots = std::iter::once(folder.to_path_buf())
+        .chain(distinct_roots(others))
+        .collect();
+    (roots, outside)
+}
+
+// Only one folder can be mounted: the current one (the first root), or the
+// folder that holds it when some roots are next to it
+pub fn mount_for(roots: &[PathBuf]) -> Option<PathBuf> {
+    let (folder, others) = roots.split_first()?;
+    if others.iter().all(|root| root.starts_with(folder)) {
+        Some(folder.clone())
+    } else {
+        folder.parent().map(Path::to_path_buf)
+    }
+}
+
+// Drops duplicates and roots that are already inside another root, keeping
+// the order in which they were added
+pub fn distinct_roots(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    let paths: Vec<PathBuf> = paths.into_iter().map(|path| normalize(&path)).collect();
+    let mut roots: Vec<PathBuf> = Vec::new();
+    for (i, path) in paths.iter().enumerate() {
+        let covered = paths
+            .iter()
+            .enumerate()
+            .any(|(j, other)| j != i && path.starts_with(other) && (path != other || j < i));
+        if !covered {
+            roots.push(path.clone());
+        }
+    }
+    roots
+}
+
+// Places the roots below `mount` and labels them with their folder name, or
+// with their path below `mount` where two folder names clash
+pub fn project_roots(paths: &[PathBuf], mount: &Path) -> Vec<ProjectRoot> {
+    let folder_name = |path: &Path| {
+        path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string())
+    };
+    paths
+        .iter()
+        .filter_map(|path| {
+            let dir = path.strip_prefix(mount).ok()?.to_path_buf();
+            let name = folder_name(path);
+            let clashes = paths
+                .iter()
+                .filter(|other| folder_name(other) == name)
+                .count()
+                > 1;
+            let label = if clashes && !dir.as_os_str().is_empty() {
+                dir.display().to_string()
+            } else {
+                name
+            };
+            Some(ProjectRoot {
+                path: path.clone(),
+                dir,
+                label: label.into(),
+            })
+        })
+        .collect()
+}
+
+// Resolves "." and ".." without touching the filesystem
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
@@ -1,21 +1,27 @@
+use crate::app_state::FileTags;
 use crate::files::TypeDefinition;
 use crate::pane::PaneMetadata;
-use crate::{parse_rust_asset_search, RustAssetSearchMode};
+use crate::workspace::crate_name_matches;
+use crate::{parse_rust_asset_search, split_crate_filter, RustAssetSearch};
 use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
 use std::path::PathBuf;
+use std::rc::Rc;
 
 #[derive(Debug, Clone)]
 pub struct SearchResult {
     pub item: SearchItem,
     pub score: i64,
     pub indices: Vec<usize>,
+    pub crate_name: Option<Rc<str>>, // Cargo package owning the file, if any
+    pub is_symlink: bool,            // Reached through a symlink
+    pub root_label: Option<Rc<str>>, // Project root of the file, when several are indexed
 }
 
 #[derive(Debug, Clone)]
 pub enum SearchItem {
     Pane(PaneMetadata),
     File(PathBuf),
-    RustAsset(TypeDefinition),
+    RustAsset(Box<TypeDefinition>), // Boxed, definitions being much larger than the rest
 }
 
 #[derive(Debug, Clone, Default)]
@@ -29,20 +35,34 @@ impl SearchResult {
             item: SearchItem::Pane(pane),
             score,
             indices,
+            crate_name: None,
+            is_symlink: false,
+            root_label: None,
         }
     }
 
-    pub fn new_file(file: PathBuf, score: i64, indices: Vec<usize>) -> Self {
+    pub fn new_file(file: PathBuf, file_tags: &FileTags, score: i64, indices: Vec<usize>) -> Self {
         SearchResult {
+            crate_name: file_tags.crate_for(&file),
+            is_symlink: file_tags.is_symlinked(&file),
+            root_label: file_tags.root_label_for(&file),
             item: SearchItem::File(file),
             score,
             indices,
         }
     }
 
-    pub fn new_rust_asset(rust_asset: TypeDefinition, score: i64, indices: Vec<usize>) -> Self {
+    pub fn new_rust_asset(
+        rust_asset: TypeDefinition,
+        file_tags: &FileTags,
+        score: i64,
+        indices: Vec<usize>,
+    ) -> Self {
         SearchResult {
-            item: SearchItem::RustAsset(rust_asset),
+            crate_name: rust_asset.crate_name.clone(),
+            is_symlink: file_tags.is_symlinked(&rust_asset.file_path),
+            root_label: file_tags.root_label_for(&rust_asset.file_path),
+            item: SearchItem::RustAsset(Box::new(rust_asset)),
             score,
             indices,
         }
@@ -51,12 +71,24 @@ impl SearchResult {
     pub fn display_text(&self) -> String {
         match &self.item {
             SearchItem::Pane(pane) => pane.title.clone(),
-            SearchItem::File(path) => path.to_string_lossy().to_string(),
+            // Symlinked paths get a trailing '@', like `ls -F` marks them
+            SearchItem::File(path) => format!(
+                "{}{}",
+                path.to_string_lossy(),
+                if self.is_symlink { "@" } else { "" }
+            ),
             SearchItem::RustAsset(rust_asset) => {
                 format!(
-                    "{} ({})",
-                    rust_asset.name,
-                    rust_asset.file_path.to_string_lossy()
+                    "{}{} ({}{}{})",
+                    rust_asset.qualified_name(),
+                    rust_asset.signature,
+                    rust_asset.file_path.to_string_lossy(),
+                    if self.is_symlink { "@" } else { "" },
+                    if rust_asset.partial_scan {
+                        ", partially indexed"
+                    } else {
+                        ""
+                    }
                 )
             }
         }
@@ -107,31 +139,57 @@ impl SearchEngine {
         search_term: &str,
         panes: &[PaneMetadata],
         files: &[PathBuf],
-        rust_assets: &[TypeDefinition],
+        rust_assets: &[&TypeDefinition],
+        file_tags: &FileTags,
         _current_cwd: &PathBuf,
+    ) -> SearchResults {
+        let (crate_filter, search_term) = split_crate_filter(search_term);
+        let mut results = self.search_unfiltered(search_term, panes, files, rust_assets, file_tags);
+
+        // "crate:name ..." only keeps results from that Cargo package
+        if let Some(crate_filter) = crate_filter {
+            results.files_panes_results.retain(|result| {
+                result
+                    .crate_name
+                    .as_deref()
+                    .is_some_and(|crate_name| crate_name_matches(crate_name, crate_filter))
+            });
+        }
+
+        results
+    }
+
+    fn search_unfiltered(
+        &self,
+        search_term: &str,
+        panes: &[PaneMetadata],
+        files: &[PathBuf],
+        rust_assets: &[&TypeDefinition],
+        file_tags: &FileTags,
     ) -> SearchResults {
         let mut results = SearchResults::default();
 
         if search_term.is_empty() {
             // Return all items when no search term
-            results.files_panes_results = self.get_all_files_panes_rust(panes, files, rust_assets);
+            results.files_panes_results =
+                self.get_all_files_panes_rust(panes, files, rust_assets, file_tags);
             return results;
         }
 
         // Check if this is a Rust asset search (struct/enum/function)
         if let Some(rust_mode) = parse_rust_asset_search(search_term) {
             // For Rust asset searches, only search rust assets with the term after the keyword
-            let actual_search_term = match &rust_mode {
-                RustAssetSearchMode::Struct(term) => term,
-                RustAssetSearchMode::Enum(term) => term,
-                RustAssetSearchMode::Function(term) => term,
-            };
-            results.files_panes_results =
-                self.search_rust_assets_only(actual_search_term, rust_assets, &rust_mode);
+            let actual_search_term = rust_mode.term();
+            results.files_panes_results = self.search_rust_assets_only(
+                actual_search_term,
+                rust_assets,
+                &rust_mode,
+                file_tags,
+            );
         } else {
             // Normal search: files, panes, and rust assets
             results.files_panes_results =
-                self.search_files_panes_rust(search_term, panes, files, rust_assets);
+                self.search_files_panes_rust(search_term, panes, files, rust_assets, file_tags);
         }
 
         results
@@ -141,7 +199,8 @@ impl SearchEngine {
         &self,
         panes: &[PaneMetadata],
         files: &[PathBuf],
-        rust_assets: &[TypeDefinition],
+        rust_assets: &[&TypeDefinition],
+        file_tags: &FileTags,
     ) -> Vec<SearchResult> {
         let mut results = Vec::new();
 
@@ -151,9 +210,10 @@ impl SearchEngine {
         }
 
         // Add all rust assets
-        for rust_asset in rust_assets {
+        for &rust_asset in rust_assets {
             results.push(SearchResult::new_rust_asset(
                 rust_asset.clone(),
+                file_tags,
                 500,
                 vec![],
             ));
@@ -161,7 +221,7 @@ impl SearchEngine {
 
         // Add all files
         for file in files {
-            results.push(SearchResult::new_file(file.clone(), 100, vec![]));
+            results.push(SearchResult::new_file(file.clone(), file_tags, 100, vec![]));
         }
 
         results
@@ -172,7 +232,8 @@ impl SearchEngine {
         search_term: &str,
         panes: &[PaneMetadata],
         files: &[PathBuf],
-        rust_assets: &[TypeDefinition],
+        rust_assets: &[&TypeDefinition],
+        file_tags: &FileTags,
     ) -> Vec<SearchResult> {
         let mut matches = vec![];
 
@@ -190,12 +251,11 @@ impl SearchEngine {
         }
 
         // Search rust assets
-        for rust_asset in rust_assets {
-            if let Some((score, indices)) =
-                self.matcher.fuzzy_indices(&rust_asset.name, search_term)
-            {
+        for &rust_asset in rust_assets {
+            if let Some((score, indices)) = self.match_rust_asset(rust_asset, search_term) {
                 matches.push(SearchResult::new_rust_asset(
                     rust_asset.clone(),
+                    file_tags,
                     score,
                     indices,
                 ));
@@ -207,7 +267,12 @@ impl SearchEngine {
             let file_string = file.to_string_lossy();
 
             if let Some((score, indices)) = self.matcher.fuzzy_indices(&file_string, search_term) {
-                matches.push(SearchResult::new_file(file.clone(), score, indices));
+                matches.push(SearchResult::new_file(
+                    file.clone(),
+                    file_tags,
+                    score,
+                    indices,
+                ));
             }
         }
 
@@ -219,42 +284,45 @@ impl SearchEngine {
     fn search_rust_assets_only(
         &self,
         search_term: &str,
-        rust_assets: &[TypeDefinition],
-        mode: &RustAssetSearchMode,
+        rust_assets: &[&TypeDefinition],
+        mode: &RustAssetSearch,
+        file_tags: &FileTags,
     ) -> Vec<SearchResult> {
         let mut matches = vec![];
+        let docs_query = DocsQuery::new(search_term);
 
-        for rust_asset in rust_assets {
+        for &rust_asset in rust_assets {
             // Filter by type first
-            let type_matches = match mode {
-                RustAssetSearchMode::Struct(_) => {
-                    matches!(rust_asset.type_kind, crate::files::TypeKind::Struct)
-                }
-                RustAssetSearchMode::Enum(_) => {
-                    matches!(rust_asset.type_kind, crate::files::TypeKind::Enum)
-                }
-                RustAssetSearchMode::Function(_) => {
-                    matches!(rust_asset.type_kind, crate::files::TypeKind::Function)
-                }
-            };
-
-            if type_matches {
+            if mode.matches(rust_asset) {
                 if search_term.is_empty() {
                     // If no search term after the keyword, show all of that type
                     matches.push(SearchResult::new_rust_asset(
                         rust_asset.clone(),
+                        file_tags,
                         1000,
                         vec![],
                     ));
                 } else if let Some((score, indices)) =
-                    self.matcher.fuzzy_indices(&rust_asset.name, search_term)
+                    self.match_rust_asset(rust_asset, search_term)
                 {
                     // Fuzzy match against the rust asset name
                     matches.push(SearchResult::new_rust_asset(
                         rust_asset.clone(),
+                        file_tags,
                         score,
                         indices,
                     ));
+                } else if docs_query
+                    .as_ref()
+                    .is_some_and(|query| query.matches(&rust_asset.docs))
+                {
+                    // Docs mentioning every word match too, below any name
+                    matches.push(SearchResult::new_rust_asset(
+                        rust_asset.clone(),
+                        file_tags,
+                        0,
+                        vec![],
+                    ));
                 }
             }
         }
@@ -263,42 +331,82 @@ impl SearchEngine {
         matches
     }
 
-    pub fn get_displayed_files(
+    // Matches the name, or the full path when the term has a path separator
+    // (eg. "ui::render" or "SearchEngine::search", a leading "crate::" being
+    // optional), or "::search" for the "Type::method" of methods only.
+    // Indices are into the qualified name, which is what gets displayed
+    fn match_rust_asset(
         &self,
+        rust_asset: &TypeDefinition,
         search_term: &str,
-        files: &[PathBuf],
-    ) -> (Vec<PathBuf>, usize) {
-        if search_term.is_empty() {
-            return (vec![], 0);
+    ) -> Option<(i64, Vec<usize>)> {
+        if search_term.starts_with("::") {
+            rust_asset.owner.as_ref()?;
+            let offset = rust_asset.module_offset();
+            let owner_and_name: String = rust_asset.qualified_name().chars().skip(offset).collect();
+            let (score, indices) = self.matcher.fuzzy_indices(&owner_and_name, search_term)?;
+            return Some((score, indices.into_iter().map(|i| i + offset).collect()));
         }
-
-        let mut file_matches = vec![];
-
-        for file in files {
-            let file_string = file.to_string_lossy();
-
-            if let Some((score, _)) = self.matcher.fuzzy_indices(&file_string, search_term) {
-                file_matches.push((file.clone(), score));
+        if search_term.contains("::") {
+            let search_term = search_term.strip_prefix("crate::").unwrap_or(search_term);
+            return self
+                .matcher
+                .fuzzy_indices(&rust_asset.qualified_name(), search_term);
+        }
+        match self.matcher.fuzzy_indices(&rust_asset.name, search_term) {
+            Some((score, indices)) => {
+                let offset = rust_asset.name_offset();
+                Some((score, indices.into_iter().map(|i| i + offset).collect()))
             }
+            None => None,
         }
+    }
+}
 
-        file_matches.sort_by(|a, b| b.1.cmp(&a.1));
+impl Default for SearchEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-        let displayed_count = file_matches.len().min(3);
-        let displayed_files: Vec<PathBuf> = file_matches
-            .iter()
-            .take(displayed_count)
-            .map(|(file, _)| file.clone())
-            .collect();
+// The words of a term looked for in docs, lowercased once per search. Words
+// shorter than 3 chars would match almost any docs, so terms with one don't
+// search docs at all
+struct DocsQuery {
+    words: Vec<String>,
+}
 
-        let remaining_count = file_matches.len().saturating_sub(3);
+impl DocsQuery {
+    fn new(search_term: &str) -> Option<Self> {
+        let words: Vec<String> = search_term
+            .split_whitespace()
+            .map(|word| word.to_lowercase())
+            .collect();
+        if words.is_empty() || words.iter().any(|word| word.chars().count() < 3) {
+            return None;
+        }
+        Some(DocsQuery { words })
+    }
 
-        (displayed_files, remaining_count)
+    // Whether the docs contain every word, ignoring case
+    fn matches(&self, docs: &str) -> bool {
+        !docs.is_empty()
+            && self
+                .words
+                .iter()
+                .all(|word| contains_ignoring_case(docs, word))
     }
 }
 
-impl Default for SearchEngine {
-    fn default() -> Self {
-        Self::new()
+// `lowercase_needle` is already lowercased. ASCII needles, by far the most
+// common, are compared in place instead of lowercasing every docs
+fn contains_ignoring_case(haystack: &str, lowercase_needle: &str) -> bool {
+    if !lowercase_needle.is_ascii() {
+        return haystack.to_lowercase().contains(lowercase_needle);
     }
+    let needle = lowercase_needle.as_bytes();
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .any(|window| window.eq_ignore_ascii_case(needle))
 }
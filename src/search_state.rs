@@ -1,6 +1,5 @@
-use crate::files::TypeKind;
 use crate::search::{SearchItem, SearchResult, SearchResults};
-use crate::{parse_rust_asset_search, RustAssetSearchMode};
+use crate::{parse_rust_asset_search, split_crate_filter, RustAssetSearch};
 
 #[derive(Default)]
 pub struct SearchState {
@@ -56,12 +55,13 @@ impl SearchState {
 
     // Check if current search term is a Rust asset search
     pub fn is_rust_asset_search(&self) -> bool {
-        parse_rust_asset_search(&self.search_term).is_some()
+        self.get_rust_asset_search_mode().is_some()
     }
 
-    // Get Rust asset search mode if applicable
-    pub fn get_rust_asset_search_mode(&self) -> Option<RustAssetSearchMode> {
-        parse_rust_asset_search(&self.search_term)
+    // Get Rust asset search mode if applicable, after any "crate:name" filter
+    pub fn get_rust_asset_search_mode(&self) -> Option<RustAssetSearch> {
+        let (_, search_term) = split_crate_filter(&self.search_term);
+        parse_rust_asset_search(search_term)
     }
 
     // Get filtered results for Rust asset search (only matching Rust assets)
@@ -71,17 +71,7 @@ impl SearchState {
                 .iter()
                 .filter(|result| {
                     if let SearchItem::RustAsset(rust_asset) = &result.item {
-                        match &mode {
-                            RustAssetSearchMode::Struct(_) => {
-                                matches!(rust_asset.type_kind, TypeKind::Struct)
-                            }
-                            RustAssetSearchMode::Enum(_) => {
-                                matches!(rust_asset.type_kind, TypeKind::Enum)
-                            }
-                            RustAssetSearchMode::Function(_) => {
-                                matches!(rust_asset.type_kind, TypeKind::Function)
-                            }
-                        }
+                        mode.matches(rust_asset)
                     } else {
                         false
                     }
@@ -0,0 +1,848 @@
+use crate::files::{SymbolSource, TypeDefinition, TypeKind};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+// Where scip-rust, rust-analyzer and most other indexers write their index,
+// relative to a project root
+pub const INDEX_FILE_NAMES: [&str; 3] = ["index.scip", "dump.lsif", "index.lsif"];
+
+// Which index of each root is imported, none unless configured
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum SymbolIndexFile {
+    #[default]
+    Disabled,
+    DefaultNames,  // The first of `INDEX_FILE_NAMES` found
+    Path(PathBuf), // Relative to the root
+}
+
+impl SymbolIndexFile {
+    pub fn from_config(value: &str) -> Option<Self> {
+        match value.trim() {
+            "" => None,
+            "true" => Some(SymbolIndexFile::DefaultNames),
+            "false" => Some(SymbolIndexFile::Disabled),
+            path => Some(SymbolIndexFile::Path(PathBuf::from(path))),
+        }
+    }
+
+    // True if `file` (relative to a root) is the index of that root
+    pub fn is_index_file(&self, file: &Path) -> bool {
+        match self {
+            SymbolIndexFile::Disabled => false,
+            SymbolIndexFile::DefaultNames => {
+                INDEX_FILE_NAMES.iter().any(|name| file == Path::new(name))
+            }
+            SymbolIndexFile::Path(path) => file == path,
+        }
+    }
+}
+
+// Reads the SCIP or LSIF index of the project root at `root_dir` (relative
+// to the host folder at `host`), either `index_file` or the first of the
+// default names found. Paths are relative to the host folder, and files
+// modified since the index was written are left out
+pub fn load_symbol_index(
+    host: &Path,
+    root_dir: &Path,
+    index_file: Option<&Path>,
+) -> BTreeMap<PathBuf, Vec<TypeDefinition>> {
+    let candidates: Vec<&Path> = match index_file {
+        Some(index_file) => vec![index_file],
+        None => INDEX_FILE_NAMES.iter().map(Path::new).collect(),
+    };
+    let (index_path, contents) = match candidates.into_iter().find_map(|name| {
+        let index_path = host.join(root_dir).join(name);
+        fs::read(&index_path)
+            .ok()
+            .map(|contents| (index_path, contents))
+    }) {
+        Some(index) => index,
+        None => return BTreeMap::new(),
+    };
+    let mut definitions = parse_symbol_index(&contents, root_dir);
+    // Files edited after the index was written are left to the scanner
+    if let Ok(indexed_at) = fs::metadata(&index_path).and_then(|metadata| metadata.modified()) {
+        definitions.retain(|file, _| {
+            fs::metadata(host.join(file))
+                .and_then(|metadata| metadata.modified())
+                .map_or(true, |modified| modified <= indexed_at)
+        });
+    }
+    definitions
+}
+
+// LSIF is JSON, either one vertex or edge per line or a single array, SCIP
+// is protobuf, which can't start with either bracket
+pub fn parse_symbol_index(
+    contents: &[u8],
+    root_dir: &Path,
+) -> BTreeMap<PathBuf, Vec<TypeDefinition>> {
+    let mut definitions = match contents.iter().find(|b| !b.is_ascii_whitespace()) {
+        Some(b'{') | Some(b'[') => parse_lsif(&String::from_utf8_lossy(contents), root_dir),
+        _ => parse_scip(contents, root_dir),
+    };
+    for file_definitions in definitions.values_mut() {
+        file_definitions.sort_by_key(|definition| (definition.line_number, definition.column));
+    }
+    definitions
+}
+
+// Just enough of the protobuf wire format to walk the fields of a message
+enum WireValue<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]), // Strings, nested messages and packed repeated fields
+    Fixed,           // 32 and 64 bit numbers, which SCIP doesn't use
+}
+
+struct WireFields<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Iterator for WireFields<'a> {
+    type Item = (u64, WireValue<'a>);
+
+    // Stops at the first malformed field
+    fn next(&mut self) -> Option<Self::Item> {
+        let key = read_varint(&mut self.bytes)?;
+        let value = match key & 0x7 {
+            0 => WireValue::Varint(read_varint(&mut self.bytes)?),
+            1 | 5 => {
+                let size = if key & 0x7 == 1 { 8 } else { 4 };
+                self.bytes = self.bytes.get(size..)?;
+                WireValue::Fixed
+            }
+            2 => {
+                let len = usize::try_from(read_varint(&mut self.bytes)?).ok()?;
+                let value = self.bytes.get(..len)?;
+                self.bytes = &self.bytes[len..];
+                WireValue::Bytes(value)
+            }
+            _ => return None, // Groups are long deprecated
+        };
+        Some((key >> 3, value))
+    }
+}
+
+fn wire_fields(bytes: &[u8]) -> WireFields<'_> {
+    WireFields { bytes }
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes.split_first()?;
+        *bytes = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn wire_str(bytes: &[u8]) -> Option<&str> {
+    std::str::from_utf8(bytes).ok()
+}
+
+// `SymbolRole.Definition`
+const SCIP_DEFINITION_ROLE: u64 = 0x1;
+
+// What a SCIP `SymbolInformation` says about a symbol
+#[derive(Default)]
+struct ScipSymbol<'a> {
+    documentation: Vec<&'a str>,
+    kind: u64,
+    display_name: Option<&'a str>,
+    signature: Option<&'a str>, // The text of `signature_documentation`
+}
+
+// Reads the `documents` of a SCIP `Index`. Definitions are the occurrences
+// with the definition role of symbols that aren't local to a function
+fn parse_scip(contents: &[u8], root_dir: &Path) -> BTreeMap<PathBuf, Vec<TypeDefinition>> {
+    let mut definitions: BTreeMap<PathBuf, Vec<TypeDefinition>> = BTreeMap::new();
+    for (field, value) in wire_fields(contents) {
+        if let (2, WireValue::Bytes(document)) = (field, value) {
+            if let Some((file_path, file_definitions)) = parse_scip_document(document, root_dir) {
+                definitions
+                    .entry(file_path)
+                    .or_default()
+                    .extend(file_definitions);
+            }
+        }
+    }
+    definitions
+}
+
+fn parse_scip_document(document: &[u8], root_dir: &Path) -> Option<(PathBuf, Vec<TypeDefinition>)> {
+    let mut relative_path = None;
+    let mut occurrences = Vec::new();
+    let mut symbols = BTreeMap::new();
+    for (field, value) in wire_fields(document) {
+        match (field, value) {
+            (1, WireValue::Bytes(path)) => relative_path = wire_str(path),
+            (2, WireValue::Bytes(occurrence)) => occurrences.push(occurrence),
+            (3, WireValue::Bytes(information)) => {
+                if let Some((symbol, information)) = parse_scip_symbol(information) {
+                    symbols.insert(symbol, information);
+                }
+            }
+            _ => {}
+        }
+    }
+    let file_path = Rc::new(root_dir.join(relative_path?));
+    let definitions = occurrences
+        .into_iter()
+        .filter_map(|occurrence| {
+            let mut range = Vec::new();
+            let mut symbol = None;
+            let mut roles = 0;
+            for (field, value) in wire_fields(occurrence) {
+                match (field, value) {
+                    (1, WireValue::Bytes(mut packed)) => {
+                        while let Some(number) = read_varint(&mut packed) {
+                            range.push(number);
+                        }
+                    }
+                    (1, WireValue::Varint(number)) => range.push(number),
+                    (2, WireValue::Bytes(name)) => symbol = wire_str(name),
+                    (3, WireValue::Varint(number)) => roles = number,
+                    _ => {}
+                }
+            }
+            if roles & SCIP_DEFINITION_ROLE == 0 {
+                return None;
+            }
+            let symbol = symbol?;
+            let information = symbols.remove(symbol).unwrap_or_default();
+            // `[startLine, startCharacter, ...]`, both 0-based
+            let (line, character) = (*range.first()?, *range.get(1)?);
+            scip_definition(symbol, information, &file_path, line, character)
+        })
+        .collect();
+    Some((file_path.to_path_buf(), definitions))
+}
+
+fn parse_scip_symbol(information: &[u8]) -> Option<(&str, ScipSymbol<'_>)> {
+    let mut symbol = None;
+    let mut scip_symbol = ScipSymbol::default();
+    for (field, value) in wire_fields(information) {
+        match (field, value) {
+            (1, WireValue::Bytes(name)) => symbol = wire_str(name),
+            (3, WireValue::Bytes(text)) => scip_symbol.documentation.extend(wire_str(text)),
+            (5, WireValue::Varint(kind)) => scip_symbol.kind = kind,
+            (6, WireValue::Bytes(name)) => scip_symbol.display_name = wire_str(name),
+            // A `Document` whose `text` is the declaration
+            (7, WireValue::Bytes(document)) => {
+                scip_symbol.signature = wire_fields(document).find_map(|field| match field {
+                    (5, WireValue::Bytes(text)) => wire_str(text),
+                    _ => None,
+                })
+            }
+            _ => {}
+        }
+    }
+    Some((symbol?, scip_symbol))
+}
+
+fn scip_definition(
+    symbol: &str,
+    information: ScipSymbol<'_>,
+    file_path: &Rc<PathBuf>,
+    line: u64,
+    character: u64,
+) -> Option<TypeDefinition> {
+    let descriptors = parse_descriptors(symbol_descriptors(symbol)?)?;
+    let (descriptor, parents) = descriptors.split_last()?;
+    let owner = descriptor_owner(parents);
+    let type_kind = scip_kind(information.kind)
+        .or_else(|| descriptor_kind(descriptor.suffix, owner.is_some()))?;
+    let name = information
+        .display_name
+        .filter(|name| !name.is_empty())
+        .unwrap_or(&descriptor.name);
+    let module_path: Vec<&str> = parents
+        .iter()
+        .filter(|parent| parent.suffix == DescriptorSuffix::Namespace)
+        .map(|parent| parent.name.as_str())
+        .collect();
+    let signature = information
+        .signature
+        .map(|declaration| declaration_signature(declaration, name, &type_kind))
+        .unwrap_or_default();
+    let docs: Vec<&str> = information
+        .documentation
+        .iter()
+        .map(|text| text.trim())
+        // Some indexers repeat the declaration as the first code block
+        .filter(|text| !text.is_empty() && !text.starts_with("```"))
+        .collect();
+    // Positions are in UTF-8 or UTF-16 code units depending on the indexer,
+    // which only differ on lines with characters outside of ASCII
+    let column = usize::try_from(character).ok()? + 1;
+    Some(TypeDefinition {
+        owner: owner.map(Rc::from),
+        module_path: if module_path.is_empty() {
+            None
+        } else {
+            Some(module_path.join("::").into())
+        },
+        signature,
+        docs: docs.join("\n\n"),
+        column,
+        char_column: column,
+        source: SymbolSource::Index,
+        ..TypeDefinition::new(
+            type_kind,
+            name.to_owned(),
+            Rc::clone(file_path),
+            usize::try_from(line).ok()? + 1,
+        )
+    })
+}
+
+// `SymbolInformation.Kind`, for the kinds with a `TypeKind` counterpart
+fn scip_kind(kind: u64) -> Option<TypeKind> {
+    let type_kind = match kind {
+        // Function, method, constructor and the many flavours of methods
+        17 | 26 | 9 | 66 | 67 | 68 | 69 | 70 | 71 | 74 | 76 | 80 => TypeKind::Function,
+        7 | 75 => TypeKind::Class,
+        8 => TypeKind::Const,
+        11 => TypeKind::Enum,
+        12 => TypeKind::Variant,
+        15 | 41 | 79 | 81 => TypeKind::Field,
+        21 | 42 => TypeKind::Interface,
+        25 => TypeKind::Macro,
+        29 | 30 | 35 => TypeKind::Module,
+        49 => TypeKind::Struct,
+        53 => TypeKind::Trait,
+        3 | 54 | 55 => TypeKind::TypeAlias,
+        59 => TypeKind::Union,
+        77 | 82 => TypeKind::Static,
+        _ => return None,
+    };
+    Some(type_kind)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DescriptorSuffix {
+    Namespace,     // `name/`
+    Type,          // `name#`
+    Term,          // `name.`
+    Method,        // `name(disambiguator).`
+    TypeParameter, // `[name]`
+    Parameter,     // `(name)`
+    Meta,          // `name:`
+    Macro,         // `name!`
+}
+
+struct Descriptor {
+    name: String,
+    suffix: DescriptorSuffix,
+}
+
+// Guesses the kind from the symbol itself, for indexers that leave
+// `SymbolInformation.kind` out
+fn descriptor_kind(suffix: DescriptorSuffix, has_owner: bool) -> Option<TypeKind> {
+    let type_kind = match suffix {
+        DescriptorSuffix::Namespace => TypeKind::Module,
+        DescriptorSuffix::Type => TypeKind::Struct,
+        DescriptorSuffix::Term if has_owner => TypeKind::Field,
+        DescriptorSuffix::Term => TypeKind::Const,
+        DescriptorSuffix::Method => TypeKind::Function,
+        DescriptorSuffix::Macro => TypeKind::Macro,
+        _ => return None,
+    };
+    Some(type_kind)
+}
+
+// The innermost type among the descriptors leading to a symbol.
+// rust-analyzer names the impl blocks of methods `impl#[SelfType]`
+fn descriptor_owner(parents: &[Descriptor]) -> Option<&str> {
+    let mut owner = None;
+    let mut previous: Option<&Descriptor> = None;
+    for parent in parents {
+        match parent.suffix {
+            DescriptorSuffix::Type => owner = Some(parent.name.as_str()),
+            DescriptorSuffix::TypeParameter
+                if previous.is_some_and(|previous| {
+                    previous.suffix == DescriptorSuffix::Type && previous.name == "impl"
+                }) =>
+            {
+                owner = Some(parent.name.as_str())
+            }
+            _ => {}
+        }
+        previous = Some(parent);
+    }
+    owner
+}
+
+// Skips the scheme, manager, package name and version of a symbol such as
+// "rust-analyzer cargo grab 0.1.0 search/SearchEngine#search().", where
+// spaces within a part are doubled. None for local symbols
+fn symbol_descriptors(symbol: &str) -> Option<&str> {
+    if symbol.starts_with("local ") {
+        return None;
+    }
+    let mut rest = symbol;
+    for _ in 0..4 {
+        let bytes = rest.as_bytes();
+        let mut i = 0;
+        loop {
+            match bytes.get(i)? {
+                b' ' if bytes.get(i + 1) == Some(&b' ') => i += 2,
+                b' ' => break,
+                _ => i += 1,
+            }
+        }
+        rest = &rest[i + 1..];
+    }
+    Some(rest)
+}
+
+fn parse_descriptors(mut rest: &str) -> Option<Vec<Descriptor>> {
+    let mut descriptors = Vec::new();
+    while !rest.is_empty() {
+        let (name, suffix, remaining) = if let Some(parameter) = rest.strip_prefix('[') {
+            let (name, remaining) = parameter.split_once(']')?;
+            (name.to_owned(), DescriptorSuffix::TypeParameter, remaining)
+        } else if let Some(parameter) = rest.strip_prefix('(') {
+            let (name, remaining) = parameter.split_once(')')?;
+            (name.to_owned(), DescriptorSuffix::Parameter, remaining)
+        } else {
+            let (name, after_name) = descriptor_name(rest)?;
+            let suffix = match after_name.chars().next()? {
+                '/' => DescriptorSuffix::Namespace,
+                '#' => DescriptorSuffix::Type,
+                '.' => DescriptorSuffix::Term,
+                ':' => DescriptorSuffix::Meta,
+                '!' => DescriptorSuffix::Macro,
+                '(' => {
+                    let (_, remaining) = after_name[1..].split_once(')')?;
+                    let remaining = remaining.strip_prefix('.')?;
+                    descriptors.push(Descriptor {
+                        name,
+                        suffix: DescriptorSuffix::Method,
+                    });
+                    rest = remaining;
+                    continue;
+                }
+                _ => return None,
+            };
+            (name, suffix, &after_name[1..])
+        };
+        descriptors.push(Descriptor { name, suffix });
+        rest = remaining;
+    }
+    Some(descriptors)
+}
+
+// A plain identifier, or one between backticks where backticks are doubled
+fn descriptor_name(rest: &str) -> Option<(String, &str)> {
+    if let Some(escaped) = rest.strip_prefix('`') {
+        let mut name = String::new();
+        let mut chars = escaped.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            if c != '`' {
+                name.push(c);
+            } else if chars.peek().is_some_and(|&(_, next)| next == '`') {
+                name.push('`');
+                chars.next();
+            } else {
+                return Some((name, &escaped[i + 1..]));
+            }
+        }
+        return None;
+    }
+    let end = rest
+        .find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '+' | '-' | '$')))
+        .unwrap_or(rest.len());
+    if end == 0 {
+        return None;
+    }
+    Some((rest[..end].to_owned(), &rest[end..]))
+}
+
+// What follows the name in a declaration such as "pub fn new(name: &str) ->
+// Self", for functions and types like the scanner's signatures
+fn declaration_signature(declaration: &str, name: &str, type_kind: &TypeKind) -> String {
+    match type_kind {
+        TypeKind::Function
+        | TypeKind::Struct
+        | TypeKind::Enum
+        | TypeKind::Union
+        | TypeKind::Trait
+        | TypeKind::TypeAlias
+        | TypeKind::Class
+        | TypeKind::Interface => {}
+        _ => return String::new(),
+    }
+    let declaration = declaration.split_whitespace().collect::<Vec<_>>().join(" ");
+    match find_word(&declaration, name) {
+        Some(start) => declaration[start + name.len()..]
+            .trim_end_matches(['{', ';', ' '])
+            .to_owned(),
+        None => String::new(),
+    }
+}
+
+// The keyword before the name in a declaration, eg. "struct" in "pub
+// struct Config<T>", or a field if a type follows the name
+fn declaration_kind(declaration: &str, name: &str) -> Option<TypeKind> {
+    let start = find_word(declaration, name)?;
+    let keyword = declaration[..start].split_whitespace().next_back();
+    let type_kind = match keyword {
+        Some("fn" | "func" | "function" | "def") => TypeKind::Function,
+        Some("struct") => TypeKind::Struct,
+        Some("enum") => TypeKind::Enum,
+        Some("union") => TypeKind::Union,
+        Some("trait") => TypeKind::Trait,
+        Some("type") => TypeKind::TypeAlias,
+        Some("const") => TypeKind::Const,
+        Some("static") => TypeKind::Static,
+        Some("mod" | "module" | "namespace" | "package") => TypeKind::Module,
+        Some("macro_rules!" | "macro") => TypeKind::Macro,
+        Some("class") => TypeKind::Class,
+        Some("interface") => TypeKind::Interface,
+        _ if declaration[start + name.len()..].starts_with(':') => TypeKind::Field,
+        _ => return None,
+    };
+    Some(type_kind)
+}
+
+// Where `word` first appears in `text` as a whole identifier
+fn find_word(text: &str, word: &str) -> Option<usize> {
+    if word.is_empty() {
+        return None;
+    }
+    let is_identifier = |c: char| c.is_alphanumeric() || c == '_';
+    text.match_indices(word).map(|(i, _)| i).find(|&i| {
+        let before = text[..i].chars().next_back();
+        let after = text[i + word.len()..].chars().next();
+        !before.is_some_and(is_identifier) && !after.is_some_and(is_identifier)
+    })
+}
+
+// The parts of an LSIF dump that make up definitions
+#[derive(Default)]
+struct Lsif {
+    project_root: Option<String>,
+    documents: BTreeMap<String, PathBuf>, // By vertex id
+    ranges: BTreeMap<String, LsifRange>,
+    range_documents: BTreeMap<String, String>, // From `contains` edges
+    result_sets: BTreeMap<String, String>,     // From `next` edges
+    monikers: BTreeMap<String, String>,        // Identifiers by vertex id
+    moniker_edges: BTreeMap<String, String>,
+    hovers: BTreeMap<String, String>, // Markdown by vertex id
+    hover_edges: BTreeMap<String, String>,
+    definition_results: BTreeSet<String>,
+    definition_ranges: BTreeSet<String>, // Ranges tagged or listed as definitions
+    definition_items: Vec<(String, Vec<String>)>, // `item` edges, resolved at the end
+}
+
+struct LsifRange {
+    line: u64,
+    character: u64,
+    tag: Option<(String, Option<u64>)>, // Name and LSP kind of a definition tag
+}
+
+// Reads an LSIF dump. Definitions are the ranges with a definition tag, or
+// those a `definitionResult` points to, named after their moniker and kind
+// after the declaration in their hover
+fn parse_lsif(contents: &str, root_dir: &Path) -> BTreeMap<PathBuf, Vec<TypeDefinition>> {
+    let mut lsif = Lsif::default();
+    if contents.trim_start().starts_with('[') {
+        if let Ok(Value::Array(entries)) = serde_json::from_str::<Value>(contents) {
+            for entry in &entries {
+                lsif.add(entry);
+            }
+        }
+    } else {
+        for line in contents.lines() {
+            if let Ok(entry) = serde_json::from_str::<Value>(line) {
+                lsif.add(&entry);
+            }
+        }
+    }
+    lsif.definitions(root_dir)
+}
+
+// Ids are numbers or strings
+fn lsif_id(value: &Value) -> Option<String> {
+    match value {
+        Value::Number(number) => Some(number.to_string()),
+        Value::String(id) => Some(id.clone()),
+        _ => None,
+    }
+}
+
+fn lsif_ids(entry: &Value) -> Vec<String> {
+    match &entry["inVs"] {
+        Value::Array(ids) => ids.iter().filter_map(lsif_id).collect(),
+        _ => lsif_id(&entry["inV"]).into_iter().collect(),
+    }
+}
+
+impl Lsif {
+    fn add(&mut self, entry: &Value) {
+        let id = match lsif_id(&entry["id"]) {
+            Some(id) => id,
+            None => return,
+        };
+        let label = entry["label"].as_str().unwrap_or_default();
+        if entry["type"] == "vertex" {
+            self.add_vertex(id, label, entry);
+            return;
+        }
+        let out_v = match lsif_id(&entry["outV"]) {
+            Some(out_v) => out_v,
+            None => return,
+        };
+        let in_vs = lsif_ids(entry);
+        match label {
+            "contains" => {
+                for range in in_vs {
+                    self.range_documents.insert(range, out_v.clone());
+                }
+            }
+            "next" => self
+                .result_sets
+                .extend(in_vs.into_iter().next().map(|v| (out_v, v))),
+            "moniker" => self
+                .moniker_edges
+                .extend(in_vs.into_iter().next().map(|v| (out_v, v))),
+            "textDocument/hover" => {
+                self.hover_edges
+                    .extend(in_vs.into_iter().next().map(|v| (out_v, v)));
+            }
+            "item" => self.definition_items.push((out_v, in_vs)),
+            _ => {}
+        }
+    }
+
+    fn add_vertex(&mut self, id: String, label: &str, vertex: &Value) {
+        match label {
+            "metaData" => {
+                self.project_root = vertex["projectRoot"].as_str().map(str::to_owned);
+            }
+            "document" => {
+                if let Some(uri) = vertex["uri"].as_str() {
+                    self.documents.insert(id, PathBuf::from(uri));
+                }
+            }
+            "range" => {
+                let tag = match &vertex["tag"] {
+                    tag if tag["type"] == "definition" => tag["text"]
+                        .as_str()
+                        .map(|name| (name.to_owned(), tag["kind"].as_u64())),
+                    _ => None,
+                };
+                if tag.is_some() {
+                    self.definition_ranges.insert(id.clone());
+                }
+                let range = LsifRange {
+                    line: vertex["start"]["line"].as_u64().unwrap_or_default(),
+                    character: vertex["start"]["character"].as_u64().unwrap_or_default(),
+                    tag,
+                };
+                self.ranges.insert(id, range);
+            }
+            "moniker" if vertex["kind"] != "local" => {
+                if let Some(identifier) = vertex["identifier"].as_str() {
+                    self.monikers.insert(id, identifier.to_owned());
+                }
+            }
+            "hoverResult" => {
+                self.hovers
+                    .insert(id, hover_markdown(&vertex["result"]["contents"]));
+            }
+            "definitionResult" => {
+                self.definition_results.insert(id);
+            }
+            _ => {}
+        }
+    }
+
+    // Monikers and hovers hang off a range or its result set
+    fn linked<'a>(
+        &self,
+        edges: &'a BTreeMap<String, String>,
+        range_id: &str,
+    ) -> Option<&'a String> {
+        edges.get(range_id).or_else(|| {
+            let result_set = self.result_sets.get(range_id)?;
+            edges.get(result_set)
+        })
+    }
+
+    fn definitions(mut self, root_dir: &Path) -> BTreeMap<PathBuf, Vec<TypeDefinition>> {
+        for (out_v, in_vs) in std::mem::take(&mut self.definition_items) {
+            if self.definition_results.contains(&out_v) {
+                self.definition_ranges.extend(in_vs);
+            }
+        }
+        // Document URIs are absolute, which only the root makes sense of
+        let project_root = match &self.project_root {
+            Some(project_root) => project_root.as_str(),
+            None => return BTreeMap::new(),
+        };
+        let mut file_paths: BTreeMap<&str, Rc<PathBuf>> = BTreeMap::new();
+        let mut definitions: BTreeMap<PathBuf, Vec<TypeDefinition>> = BTreeMap::new();
+        for range_id in &self.definition_ranges {
+            let (range, document) = match (
+                self.ranges.get(range_id),
+                self.range_documents.get(range_id),
+            ) {
+                (Some(range), Some(document)) => (range, document),
+                _ => continue,
+            };
+            // Documents outside of the project root are left out
+            let file_path = match self.documents.get(document).and_then(|uri| {
+                let relative = uri.to_str()?.strip_prefix(project_root)?;
+                Some(percent_decode(relative.trim_start_matches('/')))
+            }) {
+                Some(relative_path) => file_paths
+                    .entry(document.as_str())
+                    .or_insert_with(|| Rc::new(root_dir.join(relative_path))),
+                None => continue,
+            };
+            let identifier = self
+                .linked(&self.moniker_edges, range_id)
+                .and_then(|id| self.monikers.get(id));
+            let hover = self
+                .linked(&self.hover_edges, range_id)
+                .and_then(|id| self.hovers.get(id));
+            if let Some(definition) = lsif_definition(range, identifier, hover, file_path) {
+                definitions
+                    .entry(file_path.to_path_buf())
+                    .or_default()
+                    .push(definition);
+            }
+        }
+        definitions
+    }
+}
+
+fn lsif_definition(
+    range: &LsifRange,
+    identifier: Option<&String>,
+    hover: Option<&String>,
+    file_path: &Rc<PathBuf>,
+) -> Option<TypeDefinition> {
+    // "grab::search::SearchEngine::search" for rust-analyzer, whose first
+    // segment is the crate
+    let segments: Vec<&str> = identifier
+        .map(|identifier| identifier.split("::").collect())
+        .unwrap_or_default();
+    let (tag_name, tag_kind) = match &range.tag {
+        Some((name, kind)) => (Some(name.as_str()), kind.and_then(lsp_kind)),
+        None => (None, None),
+    };
+    let name = tag_name.or_else(|| segments.last().copied())?;
+    let (declaration, docs) = hover.map(|hover| hover_parts(hover)).unwrap_or_default();
+    let type_kind = tag_kind.or_else(|| declaration_kind(&declaration, name))?;
+    let module_path = match segments.len() {
+        0..=2 => None,
+        len => Some(segments[1..len - 1].join("::").into()),
+    };
+    let column = usize::try_from(range.character).ok()? + 1;
+    Some(TypeDefinition {
+        signature: declaration_signature(&declaration, name, &type_kind),
+        module_path,
+        docs,
+        column,
+        char_column: column,
+        source: SymbolSource::Index,
+        ..TypeDefinition::new(
+            type_kind,
+            name.to_owned(),
+            Rc::clone(file_path),
+            usize::try_from(range.line).ok()? + 1,
+        )
+    })
+}
+
+// LSP's `SymbolKind`
+fn lsp_kind(kind: u64) -> Option<TypeKind> {
+    let type_kind = match kind {
+        2..=4 => TypeKind::Module,
+        5 => TypeKind::Class,
+        6 | 9 | 12 => TypeKind::Function,
+        7 | 8 => TypeKind::Field,
+        10 => TypeKind::Enum,
+        11 => TypeKind::Interface,
+        14 => TypeKind::Const,
+        22 => TypeKind::Variant,
+        23 => TypeKind::Struct,
+        _ => return None,
+    };
+    Some(type_kind)
+}
+
+// Hover contents are markup, a marked string or a list of marked strings
+fn hover_markdown(contents: &Value) -> String {
+    match contents {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .map(hover_markdown)
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        Value::Object(_) => {
+            let value = contents["value"].as_str().unwrap_or_default();
+            match contents["language"].as_str() {
+                Some(language) => format!("```{}\n{}\n```", language, value),
+                None => value.to_owned(),
+            }
+        }
+        _ => String::new(),
+    }
+}
+
+// The last code block of a hover, which is the declaration, and the prose
+// around it, which is the documentation
+fn hover_parts(markdown: &str) -> (String, String) {
+    let mut declaration = String::new();
+    let mut docs = Vec::new();
+    let mut code_block: Option<Vec<&str>> = None;
+    for line in markdown.lines() {
+        if line.trim_start().starts_with("```") {
+            match code_block.take() {
+                Some(lines) => declaration = lines.join("\n"),
+                None => code_block = Some(Vec::new()),
+            }
+        } else if let Some(lines) = &mut code_block {
+            lines.push(line);
+        } else if line.trim() != "---" {
+            docs.push(line);
+        }
+    }
+    (declaration, docs.join("\n").trim().to_owned())
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
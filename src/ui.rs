@@ -3,38 +3,56 @@ use crate::unit::test_zellij::prelude::*;
 #[cfg(not(test))]
 use zellij_tile::prelude::*;
 
+use crate::app_state::IndexStatus;
+use crate::config::ConfigError;
 use crate::files::TypeKind;
-use crate::pane::PaneMetadata;
 use crate::search::{SearchItem, SearchResult};
-use crate::{parse_rust_asset_search, RustAssetSearchMode};
-use std::path::PathBuf;
+use crate::{
+    parse_rust_asset_search, split_crate_filter, RustAssetSearchMode, RUST_ASSET_SEARCH_PREFIXES,
+};
+use std::path::Path;
+
+// Lines below the results showing the docs of the selected item
+pub const DOC_DETAIL_ROWS: usize = 4;
+
+// What a render shows, taken from the plugin state
+pub struct RenderContext<'a> {
+    pub search_term: &'a str,
+    pub results: &'a [SearchResult],
+    pub selected_index: Option<usize>,
+    pub scroll_offset: usize,
+    pub cwd: &'a Path,
+    pub index_status: &'a IndexStatus,
+}
 
 #[derive(Default)]
-pub struct UIRenderer;
+pub struct UIRenderer {
+    config_errors: Vec<ConfigError>,
+    root_errors: Vec<ConfigError>, // Roots that can't be indexed from the current folder
+}
 
 impl UIRenderer {
-    pub fn render_plugin(
-        &self,
-        rows: usize,
-        cols: usize,
-        search_term: &str,
-        panes: &[PaneMetadata],
-        files_panes_results: &[SearchResult],
-        selected_index: Option<usize>,
-        scroll_offset: usize,
-        _displayed_files: &[PathBuf],
-        _remaining_files: usize,
-        cwd: &PathBuf,
-    ) {
+    pub fn set_config_errors(&mut self, config_errors: Vec<ConfigError>) {
+        self.config_errors = config_errors;
+    }
+
+    pub fn set_root_errors(&mut self, root_errors: Vec<ConfigError>) {
+        self.root_errors = root_errors;
+    }
+
+    pub fn render_plugin(&self, rows: usize, cols: usize, context: &RenderContext) {
         let base_x = 1;
         let base_y = 0;
 
-        let search_display = format!("{}_", search_term);
+        let search_display = format!("{}_", context.search_term);
         let max_search_width = cols.saturating_sub(4);
         let truncated_search = truncate_middle(&search_display, max_search_width);
         let search_text = Text::new(&truncated_search).color_all(3);
 
-        let cwd_display = format!("Current Folder: {} (Ctrl f to change)", cwd.display());
+        let cwd_display = format!(
+            "Current Folder: {} (Ctrl f to change)",
+            context.cwd.display()
+        );
         let max_cwd_width = cols.saturating_sub(4);
         let truncated_cwd = truncate_middle(&cwd_display, max_cwd_width);
 
@@ -52,79 +70,109 @@ impl UIRenderer {
         print_text_with_coordinates(cwd_text, base_x, cwd_y, None, None);
         print_text_with_coordinates(search_text, base_x, search_y, None, None);
 
-        let available_rows = rows.saturating_sub(table_y + 3); // Reserve space for hint line
+        let displayed_results = displayed_results(context.search_term, context.results);
+        let selected_docs = context
+            .selected_index
+            .and_then(|index| displayed_results.get(index))
+            .map(|result| match &result.item {
+                SearchItem::RustAsset(rust_asset) => rust_asset.docs.as_str(),
+                _ => "",
+            })
+            .unwrap_or("");
+        let detail_rows = if selected_docs.is_empty() {
+            0
+        } else {
+            DOC_DETAIL_ROWS
+        };
+        // Reserve space for the docs of the selected item and the hint line
+        let available_rows = rows.saturating_sub(table_y + 3 + detail_rows);
 
         self.render_single_table(
             table_y,
             base_x,
             cols,
             available_rows,
-            search_term,
-            panes,
-            files_panes_results,
-            selected_index,
-            scroll_offset,
-            _remaining_files,
-            cwd,
+            context,
+            &displayed_results,
         );
 
+        let detail_y = rows.saturating_sub(2 + detail_rows);
+        self.render_docs(detail_y, base_x, cols, selected_docs);
+        self.render_index_status(rows.saturating_sub(2), base_x, cols, context.index_status);
+
         let hint_y = rows.saturating_sub(1);
-        let hint_text =
-            "Hint: start your search with 'struct', 'fn' or 'enum' to look for rust assets";
         let max_hint_width = cols.saturating_sub(2);
-        let truncated_hint = truncate_middle(hint_text, max_hint_width);
+        if !self.config_errors.is_empty() || !self.root_errors.is_empty() {
+            // Takes the place of the hint until the configuration is fixed
+            self.render_config_errors(hint_y, base_x, max_hint_width);
+            return;
+        }
+        let truncated_hint = search_hint(max_hint_width);
         let hint_display = Text::new(&truncated_hint).color_substring(3, "Hint:");
         print_text_with_coordinates(hint_display, base_x, hint_y, None, None);
     }
 
+    fn render_config_errors(&self, y: usize, base_x: usize, max_width: usize) {
+        let errors: Vec<String> = self
+            .config_errors
+            .iter()
+            .chain(&self.root_errors)
+            .map(|error| error.to_string())
+            .collect();
+        let error_text = format!("Invalid configuration: {}", errors.join(", "));
+        let truncated_error = truncate_middle(&error_text, max_width);
+        let error_display =
+            Text::new(&truncated_error).color_substring(1, "Invalid configuration:");
+        print_text_with_coordinates(error_display, base_x, y, None, None);
+    }
+
+    fn render_docs(&self, start_y: usize, base_x: usize, cols: usize, docs: &str) {
+        let max_width = cols.saturating_sub(2);
+        let line_count = docs.lines().count();
+        for (i, line) in docs.lines().take(DOC_DETAIL_ROWS).enumerate() {
+            let line = if i + 1 == DOC_DETAIL_ROWS && line_count > DOC_DETAIL_ROWS {
+                "..."
+            } else {
+                line
+            };
+            let doc_text = Text::new(truncate_end(line, max_width)).color_all(2);
+            print_text_with_coordinates(doc_text, base_x, start_y + i, None, None);
+        }
+    }
+
+    fn render_index_status(
+        &self,
+        status_y: usize,
+        base_x: usize,
+        cols: usize,
+        index_status: &IndexStatus,
+    ) {
+        let status_text = if index_status.in_progress {
+            format!("Indexing {} files...", index_status.indexed_count)
+        } else if let Some(file_limit) = index_status.truncated_at {
+            format!(
+                "Index stopped at {} files, raise 'file_limit' to index more",
+                file_limit
+            )
+        } else {
+            return;
+        };
+        let truncated_status = truncate_middle(&status_text, cols.saturating_sub(2));
+        let status_display = Text::new(&truncated_status).color_all(1);
+        print_text_with_coordinates(status_display, base_x, status_y, None, None);
+    }
+
     fn render_single_table(
         &self,
         start_y: usize,
         base_x: usize,
         cols: usize,
         available_rows: usize,
-        search_term: &str,
-        _panes: &[PaneMetadata],
-        files_panes_results: &[SearchResult],
-        selected_index: Option<usize>,
-        scroll_offset: usize,
-        _remaining_files: usize,
-        _current_cwd: &PathBuf,
+        context: &RenderContext,
+        filtered_results: &[SearchResult],
     ) {
-        // Check if we're in Rust asset search mode
-        let filtered_results: Vec<SearchResult> = if let Some(rust_mode) =
-            parse_rust_asset_search(search_term)
-        {
-            // Show only matching Rust assets
-            files_panes_results
-                .iter()
-                .filter(|result| {
-                    if let SearchItem::RustAsset(rust_asset) = &result.item {
-                        match &rust_mode {
-                            RustAssetSearchMode::Struct(_) => {
-                                matches!(rust_asset.type_kind, TypeKind::Struct)
-                            }
-                            RustAssetSearchMode::Enum(_) => {
-                                matches!(rust_asset.type_kind, TypeKind::Enum)
-                            }
-                            RustAssetSearchMode::Function(_) => {
-                                matches!(rust_asset.type_kind, TypeKind::Function)
-                            }
-                        }
-                    } else {
-                        false
-                    }
-                })
-                .cloned()
-                .collect()
-        } else {
-            // Normal mode: filter out Rust assets - only show panes and files
-            files_panes_results
-                .iter()
-                .filter(|result| matches!(result.item, SearchItem::Pane(_) | SearchItem::File(_)))
-                .cloned()
-                .collect()
-        };
+        // A "crate:name" filter was already applied by the search engine
+        let (_, search_term) = split_crate_filter(context.search_term);
 
         let total_items = filtered_results.len();
 
@@ -134,30 +182,60 @@ impl UIRenderer {
         }
 
         let scroll_indication_space = 10;
-        let type_column_width = 7;
-        let available_title_width =
-            cols.saturating_sub(scroll_indication_space + type_column_width);
+        let type_column_width = filtered_results
+            .iter()
+            .map(|result| item_type_label(result).chars().count() + 1)
+            .max()
+            .unwrap_or(0)
+            .max(7);
+        let crate_column_width = filtered_results
+            .iter()
+            .filter_map(|result| result.crate_name.as_ref())
+            .map(|crate_name| crate_name.chars().count() + 2)
+            .max()
+            .unwrap_or(0);
+        let root_column_width = filtered_results
+            .iter()
+            .filter_map(|result| result.root_label.as_ref())
+            .map(|root_label| root_label.chars().count() + 2)
+            .max()
+            .unwrap_or(0);
+        let available_title_width = cols.saturating_sub(
+            scroll_indication_space + type_column_width + crate_column_width + root_column_width,
+        );
 
         self.render_table(
             start_y,
             base_x,
             available_rows,
-            &filtered_results,
+            filtered_results,
             0, // Table starts at index 0
-            selected_index,
-            scroll_offset,
+            context.selected_index,
+            context.scroll_offset,
             available_title_width,
             total_items,
-            _current_cwd,
         );
     }
 
     fn render_no_results(&self, start_y: usize, base_x: usize, search_term: &str) {
-        let message = if let Some(mode) = parse_rust_asset_search(search_term) {
-            match mode {
+        let message = if let Some(search) = parse_rust_asset_search(search_term) {
+            match search.mode {
                 RustAssetSearchMode::Struct(_) => "No matching structs found",
                 RustAssetSearchMode::Enum(_) => "No matching enums found",
                 RustAssetSearchMode::Function(_) => "No matching functions found",
+                RustAssetSearchMode::Trait(_) => "No matching traits found",
+                RustAssetSearchMode::Impl(_) => "No matching impl blocks found",
+                RustAssetSearchMode::TypeAlias(_) => "No matching type aliases found",
+                RustAssetSearchMode::Const(_) => "No matching constants found",
+                RustAssetSearchMode::Static(_) => "No matching statics found",
+                RustAssetSearchMode::Macro(_) => "No matching macros found",
+                RustAssetSearchMode::Module(_) => "No matching modules found",
+                RustAssetSearchMode::Union(_) => "No matching unions found",
+                RustAssetSearchMode::Variant(_) => "No matching enum variants found",
+                RustAssetSearchMode::Field(_) => "No matching fields found",
+                RustAssetSearchMode::Test(_) => "No matching tests found",
+                RustAssetSearchMode::Class(_) => "No matching classes found",
+                RustAssetSearchMode::Interface(_) => "No matching interfaces found",
             }
         } else {
             "No matching panes or files found"
@@ -177,7 +255,6 @@ impl UIRenderer {
         scroll_offset: usize,
         available_title_width: usize,
         total_items: usize,
-        _current_cwd: &PathBuf,
     ) {
         if results.is_empty() {
             let empty_message = "No Panes or Files";
@@ -190,7 +267,12 @@ impl UIRenderer {
             return;
         }
 
-        let mut table = Table::new().add_row(vec![" ".to_owned(), " ".to_owned(), " ".to_owned()]);
+        // Only projects with Cargo packages get a crate column
+        let show_crate_column = results.iter().any(|result| result.crate_name.is_some());
+        // And only searches over several project roots get a root column
+        let show_root_column = results.iter().any(|result| result.root_label.is_some());
+        let header_columns = 3 + show_crate_column as usize + show_root_column as usize;
+        let mut table = Table::new().add_row(vec![" ".to_owned(); header_columns]);
 
         // Calculate visible range considering the global scroll offset
         let global_start = if scroll_offset > table_start_index {
@@ -215,39 +297,37 @@ impl UIRenderer {
                 let global_index = table_start_index + item_index;
                 let is_selected = selected_index == Some(global_index);
 
-                let (display_text, highlight_indices, item_type) = match &search_result.item {
-                    SearchItem::Pane(_) => {
-                        let display_text = search_result.display_text();
-                        (display_text, Some(&search_result.indices), "PANE")
-                    }
-                    SearchItem::File(_) => {
-                        let display_text = search_result.display_text();
-                        (display_text, Some(&search_result.indices), "FILE")
-                    }
-                    SearchItem::RustAsset(rust_asset) => {
-                        let display_text = search_result.display_text();
-                        let item_type = match rust_asset.type_kind {
-                            TypeKind::Struct => "STRUCT",
-                            TypeKind::Enum => "ENUM",
-                            TypeKind::Function => "FN",
-                        };
-                        (display_text, Some(&search_result.indices), item_type)
-                    }
-                };
+                let highlight_indices = Some(&search_result.indices);
+                let item_type = item_type_label(search_result);
 
-                let truncated_title = truncate_middle(&display_text, available_title_width);
+                let mut truncated_title =
+                    truncate_display_text(search_result, available_title_width);
+                // The first line of the docs follows, where there is room for it
+                let title_width = truncated_title.chars().count();
+                let doc_summary = match &search_result.item {
+                    SearchItem::RustAsset(rust_asset) => rust_asset.doc_summary(),
+                    _ => "",
+                };
+                // Two spaces before it, and room for the gaps between the columns
+                let doc_width = available_title_width.saturating_sub(title_width + 2 + 4);
+                let doc_indices: Vec<usize> = if doc_summary.is_empty() || doc_width < 8 {
+                    vec![]
+                } else {
+                    truncated_title.push_str("  ");
+                    truncated_title.push_str(&truncate_end(doc_summary, doc_width));
+                    (title_width + 2..truncated_title.chars().count()).collect()
+                };
 
                 let mut type_cell = if is_selected {
-                    Text::new(item_type).selected()
+                    Text::new(&item_type).selected()
                 } else {
-                    Text::new(item_type)
+                    Text::new(&item_type)
                 };
 
-                let color_index = match item_type {
-                    "PANE" => 0,
-                    "FILE" => 1,
-                    "STRUCT" | "ENUM" | "FN" | "PUB FN" => 2,
-                    _ => 0,
+                let color_index = match search_result.item {
+                    SearchItem::Pane(_) => 0,
+                    SearchItem::File(_) => 1,
+                    SearchItem::RustAsset(_) => 2,
                 };
                 type_cell = type_cell.color_all(color_index);
 
@@ -257,10 +337,13 @@ impl UIRenderer {
                     Text::new(&truncated_title)
                 };
 
+                if !doc_indices.is_empty() {
+                    filename_cell = filename_cell.color_indices(2, doc_indices);
+                }
                 if let Some(indices) = highlight_indices {
                     let valid_indices: Vec<usize> = indices
                         .iter()
-                        .filter(|&&i| i < truncated_title.chars().count())
+                        .filter(|&&i| i < title_width)
                         .copied()
                         .collect();
                     if !valid_indices.is_empty() {
@@ -284,7 +367,27 @@ impl UIRenderer {
                     Text::new(" ")
                 };
 
-                table = table.add_styled_row(vec![type_cell, filename_cell, third_column]);
+                let mut row = vec![type_cell, filename_cell];
+                if show_root_column {
+                    let root_label = search_result.root_label.as_deref().unwrap_or(" ");
+                    let root_cell = if is_selected {
+                        Text::new(root_label).selected()
+                    } else {
+                        Text::new(root_label)
+                    };
+                    row.push(root_cell.color_all(0));
+                }
+                if show_crate_column {
+                    let crate_name = search_result.crate_name.as_deref().unwrap_or(" ");
+                    let crate_cell = if is_selected {
+                        Text::new(crate_name).selected()
+                    } else {
+                        Text::new(crate_name)
+                    };
+                    row.push(crate_cell.color_all(1));
+                }
+                row.push(third_column);
+                table = table.add_styled_row(row);
             }
         }
 
@@ -292,6 +395,120 @@ impl UIRenderer {
     }
 }
 
+// The results a search term shows: only matching Rust assets for a Rust asset
+// search, otherwise only panes and files
+fn displayed_results(search_term: &str, results: &[SearchResult]) -> Vec<SearchResult> {
+    // A "crate:name" filter was already applied by the search engine
+    let (_, search_term) = split_crate_filter(search_term);
+    match parse_rust_asset_search(search_term) {
+        Some(rust_mode) => results
+            .iter()
+            .filter(|result| match &result.item {
+                SearchItem::RustAsset(rust_asset) => rust_mode.matches(rust_asset),
+                _ => false,
+            })
+            .cloned()
+            .collect(),
+        None => results
+            .iter()
+            .filter(|result| matches!(result.item, SearchItem::Pane(_) | SearchItem::File(_)))
+            .cloned()
+            .collect(),
+    }
+}
+
+// Items are labelled like they are declared, eg. "PUB(CRATE) ASYNC FN"
+fn item_type_label(search_result: &SearchResult) -> String {
+    match &search_result.item {
+        SearchItem::Pane(_) => "PANE".to_owned(),
+        SearchItem::File(_) => "FILE".to_owned(),
+        SearchItem::RustAsset(rust_asset) => {
+            let kind = match rust_asset.type_kind {
+                TypeKind::Function | TypeKind::Module if rust_asset.is_test => "TEST",
+                TypeKind::Struct => "STRUCT",
+                TypeKind::Enum => "ENUM",
+                TypeKind::Function => "FN",
+                TypeKind::Trait => "TRAIT",
+                TypeKind::Impl => "IMPL",
+                TypeKind::TypeAlias => "TYPE",
+                TypeKind::Const => "CONST",
+                TypeKind::Static => "STATIC",
+                TypeKind::Macro => "MACRO",
+                TypeKind::Module => "MOD",
+                TypeKind::Union => "UNION",
+                TypeKind::Variant => "VARIANT",
+                TypeKind::Field => "FIELD",
+                TypeKind::Class => "CLASS",
+                TypeKind::Interface => "INTERFACE",
+            };
+            let mut keywords: Vec<String> = rust_asset.visibility.keyword().into_iter().collect();
+            keywords.extend(rust_asset.qualifiers.keywords());
+            keywords.push(kind.to_owned());
+            keywords.join(" ").to_uppercase()
+        }
+    }
+}
+
+// Signatures are cut at their end first, so the name (where the highlights
+// are) and the path stay readable
+fn truncate_display_text(search_result: &SearchResult, max_width: usize) -> String {
+    let display_text = search_result.display_text();
+    if let SearchItem::RustAsset(rust_asset) = &search_result.item {
+        let signature = &rust_asset.signature;
+        let text_width = display_text.chars().count();
+        if !signature.is_empty() && text_width > max_width {
+            let name_end = rust_asset.qualified_name().len();
+            let (name, rest) = display_text.split_at(name_end);
+            let path = &rest[signature.len()..];
+            let ellipsis = "...";
+            let fixed_width = text_width - signature.chars().count() + ellipsis.len();
+            let signature_width = max_width.saturating_sub(fixed_width);
+            if signature_width == 0 {
+                return truncate_middle(&format!("{}{}", name, path), max_width);
+            }
+            let signature: String = signature.chars().take(signature_width).collect();
+            return format!("{}{}{}{}", name, signature, ellipsis, path);
+        }
+    }
+    truncate_middle(&display_text, max_width)
+}
+
+pub fn truncate_end(text: &str, max_width: usize) -> String {
+    if text.chars().count() <= max_width {
+        return text.to_string();
+    }
+    let ellipsis = "...";
+    if max_width < ellipsis.len() {
+        return ellipsis.chars().take(max_width).collect();
+    }
+    let kept: String = text.chars().take(max_width - ellipsis.len()).collect();
+    format!("{}{}", kept, ellipsis)
+}
+
+// Names as many search prefixes as fit, so they can be discovered from the
+// empty state
+fn search_hint(max_width: usize) -> String {
+    let hint = |keywords: &str| format!("Hint: start with {} to search definitions", keywords);
+    let keywords: Vec<String> = RUST_ASSET_SEARCH_PREFIXES
+        .iter()
+        .map(|(prefix, _)| format!("'{}'", prefix.trim_end()))
+        .collect();
+    let (last, others) = keywords.split_last().expect("there are search prefixes");
+    let all = hint(&format!("{} or {}", others.join(", "), last));
+    if all.chars().count() <= max_width {
+        return all;
+    }
+    let mut fitting = hint(&format!("{}...", keywords[0]));
+    for count in 2..keywords.len() {
+        let candidate = hint(&format!("{}, ...", keywords[..count].join(", ")));
+        if candidate.chars().count() > max_width {
+            break;
+        }
+        fitting = candidate;
+    }
+    truncate_middle(&fitting, max_width)
+}
+
 pub fn truncate_middle(text: &str, max_width: usize) -> String {
     if text.chars().count() <= max_width {
         return text.to_string();
@@ -0,0 +1,150 @@
+use crate::cache::IndexCache;
+use crate::config::ScanConfig;
+use crate::unit::fixtures::TempProject;
+use crate::unit::test_zellij::{self, ZellijCall, ZellijWorker};
+use crate::worker::{IndexBatch, IndexRequest, IndexWorker, CONTINUE_INDEXING, START_INDEXING};
+use std::path::{Path, PathBuf};
+
+fn index_with_worker(project: &TempProject, cache_dir: &Path) -> Vec<IndexBatch> {
+    test_zellij::mock_init();
+    let mut worker = IndexWorker::default();
+    let request = IndexRequest {
+        generation: 1,
+        root: project.path().to_path_buf(),
+        config: ScanConfig {
+            file_limit: 1000,
+            ..Default::default()
+        },
+        repository_root: PathBuf::from("/home/user/project"),
+        cache_dir: Some(cache_dir.to_path_buf()),
+        files: None,
+        subdirectory: PathBuf::new(),
+        directory: None,
+    };
+    worker.on_message(
+        START_INDEXING.to_owned(),
+        serde_json::to_string(&request).unwrap(),
+    );
+
+    loop {
+        let batches: Vec<IndexBatch> = test_zellij::mock_get_calls()
+            .into_iter()
+            .filter_map(|call| match call {
+                ZellijCall::PostMessageToPlugin { payload, .. } => {
+                    serde_json::from_str(&payload).ok()
+                }
+                _ => None,
+            })
+            .collect();
+        if batches.last().is_some_and(|batch| batch.done) {
+            return batches;
+        }
+        worker.on_message(CONTINUE_INDEXING.to_owned(), "1".to_owned());
+    }
+}
+
+#[test]
+fn test_warm_cache_is_sent_first_and_stale_files_are_pruned() {
+    let project = TempProject::new();
+    let cache_dir = TempProject::new();
+    project.write("src/lib.rs", "pub struct Cached;\n");
+    project.write("src/gone.rs", "pub struct Gone;\n");
+
+    let cold = index_with_worker(&project, cache_dir.path());
+    assert_eq!(cold.len(), 1, "A cold cache goes straight to the walk");
+
+    std::fs::remove_file(project.path().join("src/gone.rs")).unwrap();
+    let warm = index_with_worker(&project, cache_dir.path());
+
+    let first = &warm[0];
+    assert!(!first.done);
+    assert_eq!(
+        first.files.len(),
+        2,
+        "The cached index is sent before the walk"
+    );
+
+    let last = warm.last().unwrap();
+    assert_eq!(last.removed.len(), 1);
+    assert!(last.removed[0].ends_with("src/gone.rs"));
+}
+
+#[test]
+fn test_cache_rescans_only_changed_files() {
+    let project = TempProject::new();
+    let cache_dir = TempProject::new();
+    let repository_root = Path::new("/home/user/project");
+    project.write("src/lib.rs", "pub struct Before;\n");
+    let lib_rs = project.path().join("src/lib.rs");
+
+    let mut cache = IndexCache::load(
+        cache_dir.path(),
+        project.path(),
+        repository_root,
+        Path::new(""),
+        &ScanConfig::default(),
+    );
+    assert_eq!(
+        cache.definitions_for(&lib_rs, &ScanConfig::default())[0].name,
+        "Before"
+    );
+    cache.save().unwrap();
+
+    let mut cache = IndexCache::load(
+        cache_dir.path(),
+        project.path(),
+        repository_root,
+        Path::new(""),
+        &ScanConfig::default(),
+    );
+    assert_eq!(cache.cached_files(None, usize::MAX).len(), 1);
+    project.write("src/lib.rs", "pub struct AfterTheEdit;\n");
+    assert_eq!(
+        cache.definitions_for(&lib_rs, &ScanConfig::default())[0].name,
+        "AfterTheEdit"
+    );
+
+    // Another repository never sees this cache
+    let other = IndexCache::load(
+        cache_dir.path(),
+        project.path(),
+        Path::new("/other/repo"),
+        Path::new(""),
+        &ScanConfig::default(),
+    );
+    assert!(other.cached_files(None, usize::MAX).is_empty());
+}
+
+#[test]
+fn test_cache_is_dropped_when_the_scan_config_changes() {
+    let project = TempProject::new();
+    let cache_dir = TempProject::new();
+    let repository_root = Path::new("/home/user/project");
+    project.write("src/lib.rs", "pub struct Cached;\n");
+    let lib_rs = project.path().join("src/lib.rs");
+    let load = |config: &ScanConfig| {
+        IndexCache::load(
+            cache_dir.path(),
+            project.path(),
+            repository_root,
+            Path::new(""),
+            config,
+        )
+    };
+
+    let mut cache = load(&ScanConfig::default());
+    cache.definitions_for(&lib_rs, &ScanConfig::default());
+    cache.save().unwrap();
+
+    assert_eq!(
+        load(&ScanConfig::default())
+            .cached_files(None, usize::MAX)
+            .len(),
+        1
+    );
+    let limited = ScanConfig {
+        max_definitions_per_file: 1,
+        ..Default::default()
+    };
+    assert!(load(&limited).cached_files(None, usize::MAX).is_empty());
+}
@@ -0,0 +1,99 @@
+use crate::config::{Config, ScanConfig};
+use crate::editor::EditorKind;
+use crate::files::{IndexSource, SymlinkPolicy};
+use crate::symbol_index::SymbolIndexFile;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+fn configuration(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+    pairs
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_config_parses_all_settings() {
+    let (config, errors) = Config::from_configuration(&configuration(&[
+        ("file_limit", "250000"),
+        ("max_file_size", "2M"),
+        ("scan_chunk_size", "512K"),
+        ("max_definitions_per_file", "1000"),
+        ("ignore", "*.generated.rs fixtures/"),
+        ("index_source", "git"),
+        ("editors", "nvim, hx"),
+        ("symlinks", "skip"),
+        ("editor", "/usr/local/bin/hx"),
+        ("ctags", "true"),
+        ("symbol_index", "target/index.scip"),
+    ]));
+
+    assert!(errors.is_empty());
+    assert_eq!(
+        config.scan,
+        ScanConfig {
+            ignore_patterns: vec!["*.generated.rs".to_owned(), "fixtures/".to_owned()],
+            file_limit: 250_000,
+            max_file_size: 2_000_000,
+            scan_chunk_size: 512_000,
+            max_definitions_per_file: 1000,
+            symlinks: SymlinkPolicy::Skip,
+        }
+    );
+    assert_eq!(config.index_source, IndexSource::Git);
+    assert_eq!(config.editors, vec!["nvim".to_owned(), "hx".to_owned()]);
+    let editor = config.editor.unwrap();
+    assert_eq!(editor.command, "/usr/local/bin/hx");
+    assert_eq!(editor.kind, EditorKind::Helix);
+    assert!(config.ctags);
+    assert_eq!(
+        config.symbol_index,
+        SymbolIndexFile::Path(PathBuf::from("target/index.scip"))
+    );
+}
+
+#[test]
+fn test_invalid_config_values_keep_defaults_and_are_reported() {
+    let (config, errors) = Config::from_configuration(&configuration(&[
+        ("file_limit", "lots"),
+        ("max_file_size", "0"),
+        ("index_source", "svn"),
+        ("editor", "ed"),
+    ]));
+
+    assert_eq!(config.scan, ScanConfig::default());
+    assert_eq!(config.index_source, IndexSource::Walk);
+    let keys: Vec<&str> = errors.iter().map(|error| error.key.as_str()).collect();
+    assert!(config.editor.is_none());
+    assert_eq!(
+        keys,
+        vec!["editor", "file_limit", "index_source", "max_file_size"]
+    );
+    assert_eq!(
+        errors[1].to_string(),
+        "'file_limit' should be a positive number (got 'lots')"
+    );
+}
+
+#[test]
+fn test_unknown_config_options_are_reported() {
+    let (_, errors) = Config::from_configuration(&configuration(&[
+        ("file_limt", "5000"),
+        ("_allow_exec_host_cmd", "true"),
+    ]));
+
+    assert_eq!(errors.len(), 1, "Zellij's own options are left alone");
+    assert_eq!(errors[0].to_string(), "unknown option 'file_limt'");
+}
+
+#[test]
+fn test_symbol_index_is_only_imported_when_enabled() {
+    let (config, _) = Config::from_configuration(&BTreeMap::new());
+    assert_eq!(config.symbol_index, SymbolIndexFile::Disabled);
+
+    let (config, _) = Config::from_configuration(&configuration(&[("symbol_index", "true")]));
+    assert_eq!(config.symbol_index, SymbolIndexFile::DefaultNames);
+    assert!(config
+        .symbol_index
+        .is_index_file(std::path::Path::new("dump.lsif")));
+}
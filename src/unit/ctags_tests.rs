@@ -0,0 +1,105 @@
+use crate::app_state::AppState;
+use crate::ctags::parse_tags;
+use crate::files::{TypeKind, Visibility};
+use std::path::{Path, PathBuf};
+
+const TAGS: &str = "!_TAG_FILE_FORMAT\t2\t/extended format/\n\
+!_TAG_FILE_SORTED\t1\t/0=unsorted, 1=sorted/\n\
+Parser\tsrc/parser.c\t/^struct Parser {$/;\"\tkind:struct\tline:12\tlanguage:C\n\
+parse\tsrc/parser.c\t/^int parse(struct Parser *p) {$/;\"\tkind:function\tline:30\tlanguage:C\tsignature:(struct Parser *p)\n\
+depth\tsrc/parser.c\t/^\tint depth;$/;\"\tkind:member\tline:13\tlanguage:C\tstruct:Parser\n\
+MAX_DEPTH\tsrc/parser.c\t5;\"\td\tlanguage:C\n\
+render\tlib/view.rb\t/^  def render(\\/path)$/;\"\tf\tline:8\tlanguage:Ruby\tclass:App.View\taccess:public\n\
+unknown\tlib/view.rb\t/^x = 1$/;\"\tkind:variable\tline:2\n\
+no_line\tlib/view.rb\t/^def no_line$/;\"\tkind:method\n\
+plain\tlib/view.rb\t/^def plain$/\n";
+
+#[test]
+fn test_extended_tags_are_parsed() {
+    let tags = parse_tags(TAGS, Path::new("backend"));
+
+    let files: Vec<&PathBuf> = tags.keys().collect();
+    assert_eq!(
+        files,
+        vec![
+            Path::new("backend/lib/view.rb"),
+            Path::new("backend/src/parser.c")
+        ]
+    );
+
+    let parser: Vec<(TypeKind, &str, usize)> = tags[Path::new("backend/src/parser.c")]
+        .iter()
+        .map(|definition| {
+            (
+                definition.type_kind.clone(),
+                definition.name.as_str(),
+                definition.line_number,
+            )
+        })
+        .collect();
+    assert_eq!(
+        parser,
+        vec![
+            (TypeKind::Macro, "MAX_DEPTH", 5),
+            (TypeKind::Struct, "Parser", 12),
+            (TypeKind::Field, "depth", 13),
+            (TypeKind::Function, "parse", 30),
+        ],
+        "Sorted by line, which can also be the address"
+    );
+    let parse = &tags[Path::new("backend/src/parser.c")][3];
+    assert_eq!(parse.signature, "(struct Parser *p)");
+    let depth = &tags[Path::new("backend/src/parser.c")][2];
+    assert_eq!(depth.owner.as_deref(), Some("Parser"));
+    let columns: Vec<usize> = tags[Path::new("backend/src/parser.c")]
+        .iter()
+        .map(|definition| definition.column)
+        .collect();
+    assert_eq!(
+        columns,
+        vec![0, 8, 6, 5],
+        "Columns come from the search pattern, line number addresses have none"
+    );
+
+    // Variables, tags without a line and tags without a kind are left out
+    let view = &tags[Path::new("backend/lib/view.rb")];
+    assert_eq!(view.len(), 1);
+    assert_eq!(view[0].name, "render");
+    assert_eq!(view[0].owner.as_deref(), Some("View"));
+    assert_eq!(view[0].visibility, Visibility::Public);
+    assert_eq!((view[0].column, view[0].char_column), (7, 7));
+}
+
+#[test]
+fn test_imported_tags_fill_files_without_an_extractor() {
+    let tags = parse_tags(
+        "main\tsrc/main.rs\t/^fn main() {$/;\"\tkind:function\tline:1\n\
+         render\tlib/view.rb\t/^def render$/;\"\tkind:method\tline:8\n\
+         Parser\tsrc/parser.c\t/^struct Parser {$/;\"\tkind:struct\tline:12\n",
+        Path::new(""),
+    );
+
+    let mut app_state = AppState::default();
+    app_state.upsert_file(PathBuf::from("lib/view.rb"), vec![]);
+    app_state.upsert_file(PathBuf::from("src/main.rs"), vec![]);
+    app_state.import_symbols(Path::new(""), tags);
+    // Indexed after the import
+    app_state.upsert_file(PathBuf::from("src/parser.c"), vec![]);
+
+    let mut names: Vec<String> = app_state
+        .get_rust_assets()
+        .into_iter()
+        .map(|definition| definition.name)
+        .collect();
+    names.sort();
+    assert_eq!(
+        names,
+        vec!["Parser", "render"],
+        "Rust files are left to the built-in extractor"
+    );
+    assert_eq!(app_state.get_files().len(), 3);
+
+    // Importing again replaces what the previous tags file had
+    app_state.import_symbols(Path::new(""), Default::default());
+    assert!(app_state.get_rust_assets().is_empty());
+}
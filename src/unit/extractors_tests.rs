@@ -0,0 +1,202 @@
+use crate::extractors::{
+    extractor_for, GoExtractor, PythonExtractor, SymbolExtractor, TypeScriptExtractor,
+};
+use crate::files::{TypeDefinition, TypeKind, Visibility};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+fn extract(extractor: &dyn SymbolExtractor, source: &str) -> Vec<TypeDefinition> {
+    extractor.extract(source.as_bytes(), Rc::new(PathBuf::from("source")), 300)
+}
+
+// Kind, "Owner::name" and line of every definition, in order
+fn summary(definitions: &[TypeDefinition]) -> Vec<(TypeKind, String, usize)> {
+    definitions
+        .iter()
+        .map(|definition| {
+            let name = match &definition.owner {
+                Some(owner) => format!("{}::{}", owner, definition.name),
+                None => definition.name.clone(),
+            };
+            (definition.type_kind.clone(), name, definition.line_number)
+        })
+        .collect()
+}
+
+// What follows the end of the first chunk
+fn chunk_end<'a>(extractor: &dyn SymbolExtractor, source: &'a str) -> Option<&'a str> {
+    extractor
+        .chunk_end(source.as_bytes())
+        .map(|end| &source[end..])
+}
+
+#[test]
+fn test_extractors_are_chosen_by_extension() {
+    for file in [
+        "main.rs",
+        "app.py",
+        "index.ts",
+        "view.tsx",
+        "lib.mjs",
+        "server.go",
+    ] {
+        assert!(extractor_for(Path::new(file)).is_some(), "{}", file);
+    }
+    for file in ["schema.sql", "README.md", "Makefile"] {
+        assert!(extractor_for(Path::new(file)).is_none(), "{}", file);
+    }
+}
+
+#[test]
+fn test_python_definitions() {
+    let definitions = extract(
+        &PythonExtractor,
+        "import os\n\
+         \n\
+         class Repository(Base):\n\
+         \x20   \"\"\"Stores things.\n\
+         \x20   def not_a_method(self):\n\
+         \x20   \"\"\"\n\
+         \x20   def load(self, id: int) -> Item:\n\
+         \x20       def helper():\n\
+         \x20           pass\n\
+         \x20       return helper()\n\
+         \n\
+         \x20   async def _refresh(self):\n\
+         \x20       pass\n\
+         \n\
+         # def commented_out():\n\
+         def main():\n\
+         \x20   pass\n",
+    );
+
+    assert_eq!(
+        summary(&definitions),
+        vec![
+            (TypeKind::Class, "Repository".to_owned(), 3),
+            (TypeKind::Function, "Repository::load".to_owned(), 7),
+            (TypeKind::Function, "Repository::_refresh".to_owned(), 12),
+            (TypeKind::Function, "main".to_owned(), 16),
+        ]
+    );
+    assert_eq!(definitions[0].signature, "(Base)");
+    assert_eq!(definitions[1].signature, "(self, id: int) -> Item");
+    assert_eq!(definitions[1].visibility, Visibility::Public);
+    assert_eq!(definitions[2].visibility, Visibility::Private);
+    assert_eq!((definitions[1].column, definitions[1].char_column), (9, 9));
+}
+
+#[test]
+fn test_typescript_definitions() {
+    let definitions = extract(
+        &TypeScriptExtractor,
+        "export interface Props {\n\
+         \x20 type: string;\n\
+         }\n\
+         export type Id = string;\n\
+         const enum Mode { Fast }\n\
+         /* function commented() {}\n\
+         */\n\
+         const template = `\n\
+         function inTemplate() {}\n\
+         `;\n\
+         export default class Store<T> extends Base {\n\
+         \x20 private items: T[] = [];\n\
+         \x20 constructor(private api: Api) {\n\
+         \x20   super();\n\
+         \x20 }\n\
+         \x20 async load(id: Id): Promise<T> {\n\
+         \x20   if (id) { return this.fetch(id); }\n\
+         \x20 }\n\
+         \x20 #cache() {}\n\
+         }\n\
+         function* ids() {}\n\
+         async function render(props: Props) {\n\
+         }\n",
+    );
+
+    assert_eq!(
+        summary(&definitions),
+        vec![
+            (TypeKind::Interface, "Props".to_owned(), 1),
+            (TypeKind::TypeAlias, "Id".to_owned(), 4),
+            (TypeKind::Enum, "Mode".to_owned(), 5),
+            (TypeKind::Class, "Store".to_owned(), 11),
+            (TypeKind::Function, "Store::constructor".to_owned(), 13),
+            (TypeKind::Function, "Store::load".to_owned(), 16),
+            (TypeKind::Function, "Store::cache".to_owned(), 19),
+            (TypeKind::Function, "ids".to_owned(), 21),
+            (TypeKind::Function, "render".to_owned(), 22),
+        ]
+    );
+    assert_eq!(definitions[0].visibility, Visibility::Public);
+    assert_eq!(definitions[2].visibility, Visibility::Private);
+    assert_eq!(definitions[5].signature, "(id: Id): Promise<T>");
+    assert_eq!(definitions[6].visibility, Visibility::Private);
+    assert_eq!(definitions[8].signature, "(props: Props)");
+}
+
+#[test]
+fn test_go_definitions() {
+    let definitions = extract(
+        &GoExtractor,
+        "package server\n\
+         \n\
+         type (\n\
+         \t// Handler serves requests\n\
+         \tHandler interface {\n\
+         \t\tServe(req Request) error\n\
+         \t}\n\
+         \tid = string\n\
+         )\n\
+         \n\
+         type Server[T any] struct {\n\
+         \tname string\n\
+         }\n\
+         \n\
+         const usage = `\n\
+         func notAFunction() {}\n\
+         `\n\
+         \n\
+         func (s *Server[T]) Serve(req Request) (Response, error) {\n\
+         \tgo func() {}()\n\
+         \treturn nil, nil\n\
+         }\n\
+         \n\
+         func newServer(name string) *Server {\n\
+         }\n",
+    );
+
+    assert_eq!(
+        summary(&definitions),
+        vec![
+            (TypeKind::Interface, "Handler".to_owned(), 5),
+            (TypeKind::TypeAlias, "id".to_owned(), 8),
+            (TypeKind::Struct, "Server".to_owned(), 11),
+            (TypeKind::Function, "Server::Serve".to_owned(), 19),
+            (TypeKind::Function, "newServer".to_owned(), 24),
+        ]
+    );
+    assert_eq!(definitions[0].visibility, Visibility::Public);
+    assert_eq!(definitions[1].visibility, Visibility::Private);
+    assert_eq!(definitions[3].signature, "(req Request) (Response, error)");
+    assert_eq!(definitions[4].signature, "(name string) *Server");
+}
+
+#[test]
+fn test_chunks_end_outside_of_strings_and_blocks() {
+    let python = "def before():\n    pass\n\nQUERY = \"\"\"\n\nclass Phantom:\n\"\"\"\n";
+    assert_eq!(
+        chunk_end(&PythonExtractor, python),
+        Some("QUERY = \"\"\"\n\nclass Phantom:\n\"\"\"\n")
+    );
+
+    let typescript = "function before() {}\n\nconst page = `\n\nclass Phantom {}\n`;\n";
+    assert_eq!(
+        chunk_end(&TypeScriptExtractor, typescript),
+        Some("const page = `\n\nclass Phantom {}\n`;\n")
+    );
+
+    let go = "type Server struct {\n\nName string\n}\n";
+    assert_eq!(chunk_end(&GoExtractor, go), None, "Still inside the struct");
+}
@@ -0,0 +1,893 @@
+use crate::config::ScanConfig;
+use crate::files::{
+    get_all_files, host_relative_path, is_indexable_path, parse_git_ls_files, scan_rust_file_fast,
+    FileIndexer, SymlinkPolicy, TypeDefinition, TypeKind, Visibility,
+};
+use crate::unit::fixtures::TempProject;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+fn with_file_limit(file_limit: usize) -> ScanConfig {
+    ScanConfig {
+        file_limit,
+        ..Default::default()
+    }
+}
+
+fn scan_source(source: &str) -> Vec<TypeDefinition> {
+    let project = TempProject::new();
+    project.write("src/lib.rs", source);
+    let file_path = Rc::new(project.path().join("src/lib.rs"));
+    scan_rust_file_fast(&file_path, &ScanConfig::default()).unwrap()
+}
+
+fn project_with_files(count: usize) -> TempProject {
+    let project = TempProject::new();
+    project.write(".gitignore", "target/\n");
+    for i in 0..count {
+        project.write(&format!("src/module_{}/file_{}.rs", i % 7, i), "");
+    }
+    project
+}
+
+#[test]
+fn test_indexer_resumes_across_batches() {
+    let project = project_with_files(25);
+
+    let mut indexer = FileIndexer::new(project.path(), &with_file_limit(1000));
+    let mut batched = BTreeSet::new();
+    let mut batches = 0;
+    while !indexer.is_done() {
+        let batch = indexer.next_batch(4);
+        assert!(batch.len() <= 4, "Batches should respect the batch size");
+        batched.extend(batch.into_keys());
+        batches += 1;
+    }
+
+    let all_at_once: BTreeSet<PathBuf> = get_all_files(project.path(), &ScanConfig::default())
+        .unwrap()
+        .into_keys()
+        .collect();
+
+    assert!(batches > 1, "Indexing should take several batches");
+    assert_eq!(batched, all_at_once);
+    assert_eq!(indexer.indexed_count(), 26); // 25 sources and the .gitignore
+    assert!(!indexer.is_truncated());
+}
+
+#[test]
+fn test_indexer_reports_truncation_at_file_limit() {
+    let project = project_with_files(30);
+
+    let mut indexer = FileIndexer::new(project.path(), &with_file_limit(10));
+    let mut indexed = 0;
+    while !indexer.is_done() {
+        indexed += indexer.next_batch(3).len();
+    }
+
+    assert_eq!(indexed, 10);
+    assert!(
+        indexer.is_truncated(),
+        "Hitting the limit should be reported"
+    );
+}
+
+#[test]
+fn test_indexer_not_truncated_when_tree_fits_limit_exactly() {
+    let project = TempProject::new();
+    project.write("a.rs", "");
+    project.write("b.rs", "");
+
+    let mut indexer = FileIndexer::new(project.path(), &with_file_limit(2));
+    while !indexer.is_done() {
+        indexer.next_batch(10);
+    }
+
+    assert!(!indexer.is_truncated());
+}
+
+#[test]
+fn test_is_indexable_path_applies_ignore_files_of_all_ancestors() {
+    let project = TempProject::new();
+    project.write(".gitignore", "*.log\n");
+    project.write("crates/core/.gitignore", "generated/\n");
+
+    let root = project.path();
+    assert!(is_indexable_path(
+        root,
+        Path::new("crates/core/src/lib.rs"),
+        false,
+        &ScanConfig::default()
+    ));
+    assert!(!is_indexable_path(
+        root,
+        Path::new("crates/core/debug.log"),
+        false,
+        &ScanConfig::default()
+    ));
+    assert!(!is_indexable_path(
+        root,
+        Path::new("crates/core/generated/out.rs"),
+        false,
+        &ScanConfig::default()
+    ));
+    assert!(!is_indexable_path(
+        root,
+        Path::new(".git/index"),
+        false,
+        &ScanConfig::default()
+    ));
+}
+
+#[test]
+fn test_indexer_for_directory_only_walks_that_directory() {
+    let project = TempProject::new();
+    project.write(".gitignore", "*.tmp\n");
+    project.write("src/lib.rs", "");
+    project.write("moved/mod.rs", "pub fn moved() {}\n");
+    project.write("moved/nested/scratch.tmp", "");
+
+    let mut indexer =
+        FileIndexer::for_directory(project.path(), Path::new("moved"), &with_file_limit(1000));
+    let mut files = BTreeSet::new();
+    while !indexer.is_done() {
+        files.extend(indexer.next_batch(10).into_keys());
+    }
+
+    let files: BTreeSet<PathBuf> = files.iter().map(|f| project.relative(f)).collect();
+    assert_eq!(files, BTreeSet::from([PathBuf::from("moved/mod.rs")]));
+}
+
+#[test]
+fn test_host_relative_path() {
+    let cwd = Path::new("/home/user/project");
+    assert_eq!(
+        host_relative_path(Path::new("/host/src/main.rs"), cwd),
+        PathBuf::from("src/main.rs")
+    );
+    assert_eq!(
+        host_relative_path(Path::new("/home/user/project/src/main.rs"), cwd),
+        PathBuf::from("src/main.rs")
+    );
+}
+
+#[test]
+fn test_indexer_from_file_list_skips_missing_files_and_respects_limit() {
+    let project = TempProject::new();
+    project.write("src/lib.rs", "");
+    project.write("src/main.rs", "");
+    project.write("README.md", "");
+
+    let listed = parse_git_ls_files(b"src/lib.rs\0src/deleted.rs\0src/main.rs\0README.md\0");
+    assert_eq!(listed.len(), 4);
+
+    let mut indexer = FileIndexer::from_file_list(project.path(), listed, &with_file_limit(2));
+    let mut files = Vec::new();
+    while !indexer.is_done() {
+        files.extend(indexer.next_files(1));
+    }
+
+    assert_eq!(
+        files,
+        vec![PathBuf::from("src/lib.rs"), PathBuf::from("src/main.rs")]
+    );
+    assert!(indexer.is_truncated());
+}
+
+#[test]
+fn test_configured_ignore_patterns_apply_to_walks_and_file_lists() {
+    let project = TempProject::new();
+    project.write(".gitignore", "target/\n");
+    project.write("src/lib.rs", "");
+    project.write("src/schema.generated.rs", "");
+    project.write("benches/data/big.json", "");
+    let config = ScanConfig {
+        ignore_patterns: vec!["*.generated.rs".to_owned(), "data/".to_owned()],
+        ..Default::default()
+    };
+
+    let walked: BTreeSet<PathBuf> = get_all_files(project.path(), &config)
+        .unwrap()
+        .into_keys()
+        .map(|path| project.relative(&path))
+        .collect();
+    assert!(walked.contains(&PathBuf::from("src/lib.rs")));
+    assert!(!walked.contains(&PathBuf::from("src/schema.generated.rs")));
+    assert!(!walked.contains(&PathBuf::from("benches/data/big.json")));
+    assert!(!is_indexable_path(
+        project.path(),
+        Path::new("src/schema.generated.rs"),
+        false,
+        &config
+    ));
+
+    let listed =
+        parse_git_ls_files(b"src/lib.rs\0src/schema.generated.rs\0benches/data/big.json\0");
+    let mut indexer = FileIndexer::from_file_list(project.path(), listed, &config);
+    assert_eq!(indexer.next_files(10), vec![PathBuf::from("src/lib.rs")]);
+}
+
+fn walk_with_symlinks(
+    project: &TempProject,
+    symlinks: SymlinkPolicy,
+) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let config = ScanConfig {
+        symlinks,
+        ..Default::default()
+    };
+    let mut indexer = FileIndexer::new(project.path(), &config);
+    let mut files = Vec::new();
+    while !indexer.is_done() {
+        files.extend(indexer.next_files(10));
+    }
+    let relative = |paths: Vec<PathBuf>| {
+        let mut paths: Vec<PathBuf> = paths.iter().map(|path| project.relative(path)).collect();
+        paths.sort();
+        paths
+    };
+    (relative(files), relative(indexer.take_symlinked_files()))
+}
+
+#[test]
+fn test_symlink_policies_for_shared_directories() {
+    let shared = TempProject::new();
+    shared.write("src/lib.rs", "pub struct Shared;\n");
+    let project = TempProject::new();
+    project.write(".gitignore", "target/\n");
+    project.write("src/main.rs", "");
+    project.symlink("vendor/shared", shared.path());
+    project.symlink("vendor/shared-again", shared.path());
+
+    let (files, symlinked) = walk_with_symlinks(&project, SymlinkPolicy::Skip);
+    assert_eq!(
+        files,
+        vec![PathBuf::from(".gitignore"), PathBuf::from("src/main.rs")]
+    );
+    assert!(symlinked.is_empty());
+
+    let (files, symlinked) = walk_with_symlinks(&project, SymlinkPolicy::Follow);
+    assert!(files.contains(&PathBuf::from("vendor/shared/src/lib.rs")));
+    assert!(files.contains(&PathBuf::from("vendor/shared-again/src/lib.rs")));
+    assert_eq!(symlinked.len(), 2);
+
+    // The shared folder is only indexed under the first name it was found at
+    let (files, symlinked) = walk_with_symlinks(&project, SymlinkPolicy::FollowOnce);
+    assert_eq!(files.len(), 3);
+    assert_eq!(symlinked.len(), 1);
+    assert!(files.contains(&symlinked[0]));
+}
+
+#[test]
+fn test_symlink_cycles_are_not_followed() {
+    let project = TempProject::new();
+    project.write("src/lib.rs", "");
+    project.write("crates/core/lib.rs", "");
+    project.symlink("src/loop", Path::new(".."));
+    project.symlink("core-alias", &project.path().join("crates/core"));
+
+    let (files, _) = walk_with_symlinks(&project, SymlinkPolicy::Follow);
+    assert!(!files.iter().any(|file| file.starts_with("src/loop")));
+    // Links to other folders of the project are followed, and marked
+    assert!(files.contains(&PathBuf::from("core-alias/lib.rs")));
+
+    // ...unless they would be indexed twice
+    let (files, symlinked) = walk_with_symlinks(&project, SymlinkPolicy::FollowOnce);
+    assert_eq!(
+        files,
+        vec![
+            PathBuf::from("crates/core/lib.rs"),
+            PathBuf::from("src/lib.rs")
+        ]
+    );
+    assert!(symlinked.is_empty());
+}
+
+#[test]
+fn test_function_qualifiers_are_parsed_and_recorded() {
+    let definitions = scan_source(
+        "async fn fetch() {}\n\
+         pub(crate) async unsafe fn raw_fetch() {}\n\
+         pub const fn new() -> Self {}\n\
+         extern \"C\" fn callback() {}\n\
+         pub unsafe extern \"system\" fn wnd_proc() {}\n\
+         extern fn bare_extern() {}\n\
+         default fn specialized() {}\n\
+         const LIMIT: usize = 3;\n\
+         unsafe impl Send for Handle {}\n\
+         extern crate alloc;\n",
+    );
+
+    let functions: Vec<(&str, Vec<String>)> = definitions
+        .iter()
+        .filter(|definition| definition.type_kind == TypeKind::Function)
+        .map(|definition| (definition.name.as_str(), definition.qualifiers.keywords()))
+        .collect();
+    assert_eq!(
+        functions,
+        vec![
+            ("fetch", vec!["async".to_owned()]),
+            ("raw_fetch", vec!["async".to_owned(), "unsafe".to_owned()]),
+            ("new", vec!["const".to_owned()]),
+            ("callback", vec!["extern \"C\"".to_owned()]),
+            (
+                "wnd_proc",
+                vec!["unsafe".to_owned(), "extern \"system\"".to_owned()]
+            ),
+            ("bare_extern", vec!["extern \"C\"".to_owned()]),
+            ("specialized", vec!["default".to_owned()]),
+        ]
+    );
+}
+
+#[test]
+fn test_all_item_kinds_are_indexed() {
+    let definitions = scan_source(
+        "pub trait Render {}\n\
+         unsafe trait RawHandle {}\n\
+         impl Render {}\n\
+         impl<T: Into<String>> From<T> for Label<T> where T: Clone {\n\
+         unsafe impl Send for Handle {}\n\
+         pub(crate) type Result<T> = std::result::Result<T, Error>;\n\
+         const LIMIT: usize = 3;\n\
+         const _: () = ();\n\
+         pub static mut COUNTER: u32 = 0;\n\
+         static ref CONFIG: Config = load();\n\
+         macro_rules! render_all { () => {} }\n\
+         pub mod ui;\n\
+         mod tests {\n\
+         union Bits { int: u32, float: f32 }\n\
+         extern crate alloc;\n",
+    );
+
+    let items: Vec<(TypeKind, &str)> = definitions
+        .iter()
+        .map(|definition| (definition.type_kind.clone(), definition.name.as_str()))
+        .collect();
+    assert_eq!(
+        items,
+        vec![
+            (TypeKind::Trait, "Render"),
+            (TypeKind::Trait, "RawHandle"),
+            (TypeKind::Impl, "Render"),
+            (TypeKind::Impl, "From<T> for Label<T>"),
+            (TypeKind::Impl, "Send for Handle"),
+            (TypeKind::TypeAlias, "Result"),
+            (TypeKind::Const, "LIMIT"),
+            (TypeKind::Static, "COUNTER"),
+            (TypeKind::Static, "CONFIG"),
+            (TypeKind::Macro, "render_all"),
+            (TypeKind::Module, "ui"),
+            (TypeKind::Module, "tests"),
+            (TypeKind::Union, "Bits"),
+        ]
+    );
+}
+
+#[test]
+fn test_methods_record_the_type_of_their_impl_block() {
+    let definitions = scan_source(
+        "impl<T: Clone> Display for crate::ui::Label<T>\n\
+         where\n    T: Debug,\n{\n\
+             fn fmt(&self) {\n\
+                 let open = '{';\n\
+                 fn helper() {}\n\
+             }\n\
+         }\n\
+         fn free_function() {}\n\
+         impl SearchEngine {\n\
+             pub fn new() -> Self { Self {} }\n\
+             pub async fn search(&self) {}\n\
+         }\n",
+    );
+
+    let functions: Vec<(String, Option<&str>)> = definitions
+        .iter()
+        .filter(|definition| definition.type_kind == TypeKind::Function)
+        .map(|definition| (definition.qualified_name(), definition.owner.as_deref()))
+        .collect();
+    assert_eq!(
+        functions,
+        vec![
+            ("Label::fmt".to_owned(), Some("Label")),
+            ("helper".to_owned(), None),
+            ("free_function".to_owned(), None),
+            ("SearchEngine::new".to_owned(), Some("SearchEngine")),
+            ("SearchEngine::search".to_owned(), Some("SearchEngine")),
+        ]
+    );
+}
+
+#[test]
+fn test_variants_and_fields_are_children_of_their_type() {
+    let definitions = scan_source(
+        "pub enum SearchItem {\n\
+             #[allow(dead_code)]\n\
+             Pane(PaneMetadata),\n\
+             File(PathBuf), // A file\n\
+             RustAsset {\n\
+                 definition: TypeDefinition,\n\
+             },\n\
+             Empty = 3,\n\
+         }\n\
+         pub struct UIState {\n\
+             pub selected_index: Option<usize>,\n\
+             pub(crate) scroll_offset: usize,\n\
+             last_rows: std::collections::BTreeMap<usize, usize>,\n\
+         }\n\
+         pub struct Tuple(u8);\n\
+         fn not_a_field() {\n\
+             let x: u8 = 3;\n\
+         }\n",
+    );
+
+    let children = |name: &str| -> Vec<(TypeKind, String)> {
+        definitions
+            .iter()
+            .find(|definition| definition.name == name)
+            .unwrap()
+            .children
+            .iter()
+            .map(|child| (child.type_kind.clone(), child.qualified_name()))
+            .collect()
+    };
+    assert_eq!(
+        children("SearchItem"),
+        vec![
+            (TypeKind::Variant, "SearchItem::Pane".to_owned()),
+            (TypeKind::Variant, "SearchItem::File".to_owned()),
+            (TypeKind::Variant, "SearchItem::RustAsset".to_owned()),
+            (TypeKind::Variant, "SearchItem::Empty".to_owned()),
+        ]
+    );
+    assert_eq!(
+        children("UIState"),
+        vec![
+            (TypeKind::Field, "UIState::selected_index".to_owned()),
+            (TypeKind::Field, "UIState::scroll_offset".to_owned()),
+            (TypeKind::Field, "UIState::last_rows".to_owned()),
+        ]
+    );
+    assert!(children("Tuple").is_empty());
+    assert!(children("not_a_field").is_empty());
+
+    let scroll_offset = &definitions[1].children[1];
+    assert_eq!(scroll_offset.line_number, 12);
+}
+
+#[test]
+fn test_items_in_comments_strings_and_macro_bodies_are_skipped() {
+    let definitions = scan_source(
+        "/* A block comment\n\
+         fn in_block_comment() {}\n\
+         /* nested\n\
+         struct InNestedComment;\n\
+         */\n\
+         fn still_in_block_comment() {}\n\
+         */\n\
+         const TEMPLATE: &str = r#\"\n\
+         fn in_raw_string() { \"}\" }\n\
+         \"#;\n\
+         const MESSAGE: &str = \"\n\
+         struct InString {\n\
+         \";\n\
+         macro_rules! make_fn {\n\
+             ($name:ident) => {\n\
+                 fn $name() {}\n\
+                 struct Generated;\n\
+             };\n\
+         }\n\
+         fn after_macro() { let brace = '{'; }\n\
+         struct AfterMacro {\n\
+             field: u8,\n\
+         }\n",
+    );
+
+    let items: Vec<(TypeKind, &str, usize)> = definitions
+        .iter()
+        .map(|definition| {
+            (
+                definition.type_kind.clone(),
+                definition.name.as_str(),
+                definition.line_number,
+            )
+        })
+        .collect();
+    assert_eq!(
+        items,
+        vec![
+            (TypeKind::Const, "TEMPLATE", 8),
+            (TypeKind::Const, "MESSAGE", 11),
+            (TypeKind::Macro, "make_fn", 14),
+            (TypeKind::Function, "after_macro", 20),
+            (TypeKind::Struct, "AfterMacro", 21),
+        ]
+    );
+    assert_eq!(definitions[4].children.len(), 1);
+}
+
+#[test]
+fn test_tests_and_their_modules_are_detected() {
+    let definitions = scan_source(
+        "fn not_a_test() {}\n\
+         #[cfg(test)]\n\
+         mod tests {\n\
+             use super::*;\n\
+             #[test]\n\
+             fn it_works() {}\n\
+             fn helper() {}\n\
+             #[tokio::test(\n\
+                 flavor = \"multi_thread\"\n\
+             )]\n\
+             // Needs a runtime\n\
+             async fn it_works_async() {}\n\
+             mod nested {\n\
+                 #[test] #[should_panic] fn it_panics() {}\n\
+             }\n\
+         }\n\
+         #[test_case(1)]\n\
+         fn parametrized(n: u8) {}\n",
+    );
+
+    let items: Vec<(&str, bool, Option<&str>)> = definitions
+        .iter()
+        .map(|definition| {
+            (
+                definition.name.as_str(),
+                definition.is_test,
+                definition.module_path.as_deref(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        items,
+        vec![
+            ("not_a_test", false, None),
+            ("tests", true, None),
+            ("it_works", true, Some("tests")),
+            ("helper", false, Some("tests")),
+            ("it_works_async", true, Some("tests")),
+            ("nested", false, Some("tests")),
+            ("it_panics", true, Some("tests::nested")),
+            ("parametrized", false, None),
+        ]
+    );
+}
+
+#[test]
+fn test_visibility_is_recorded() {
+    let definitions = scan_source(
+        "pub fn public() {}\n\
+         pub(crate) struct CrateWide {\n\
+             pub(super) parent_field: u8,\n\
+             private_field: u8,\n\
+         }\n\
+         pub(super) const LIMIT: usize = 3;\n\
+         pub(in crate::ui) async fn scoped() {}\n\
+         pub(self) fn own_module() {}\n\
+         pub enum Mode {\n\
+             Fast,\n\
+         }\n\
+         fn private() {}\n",
+    );
+
+    let visibility_of = |name: &str| -> Visibility {
+        definitions
+            .iter()
+            .flat_map(|definition| std::iter::once(definition).chain(&definition.children))
+            .find(|definition| definition.name == name)
+            .unwrap()
+            .visibility
+            .clone()
+    };
+    assert_eq!(visibility_of("public"), Visibility::Public);
+    assert_eq!(visibility_of("CrateWide"), Visibility::Crate);
+    assert_eq!(visibility_of("parent_field"), Visibility::Super);
+    assert_eq!(visibility_of("private_field"), Visibility::Private);
+    assert_eq!(visibility_of("LIMIT"), Visibility::Super);
+    assert_eq!(
+        visibility_of("scoped"),
+        Visibility::In("crate::ui".to_owned())
+    );
+    assert_eq!(visibility_of("own_module"), Visibility::Private);
+    assert_eq!(
+        visibility_of("Fast"),
+        Visibility::Public,
+        "Variants are as visible as their enum"
+    );
+    assert_eq!(visibility_of("private"), Visibility::Private);
+
+    let scoped = definitions.iter().find(|d| d.name == "scoped").unwrap();
+    assert!(scoped.qualifiers.is_async);
+}
+
+#[test]
+fn test_signatures_span_lines_up_to_the_body() {
+    let definitions = scan_source(
+        "pub fn parse(line: &str) -> Result<Item, Error> {\n\
+         fn render(\n\
+             frame: &mut Frame, // Drawn in place\n\
+             cells: [u8; 4],\n\
+         ) -> usize\n\
+         where\n\
+             Frame: Draw,\n\
+         {\n\
+         }\n\
+         pub struct Table<'a, T: Row> {\n\
+             rows: &'a [T],\n\
+         }\n\
+         struct Point(pub f32, pub f32);\n\
+         trait Shape {\n\
+             fn area(&self) -> f64;\n\
+         }\n\
+         type Result<T> = std::result::Result<T, Error>;\n\
+         mod ui;\n",
+    );
+
+    let signatures: Vec<(&str, &str)> = definitions
+        .iter()
+        .map(|definition| (definition.name.as_str(), definition.signature.as_str()))
+        .collect();
+    assert_eq!(
+        signatures,
+        vec![
+            ("parse", "(line: &str) -> Result<Item, Error>"),
+            (
+                "render",
+                "(frame: &mut Frame, cells: [u8; 4]) -> usize where Frame: Draw"
+            ),
+            ("Table", "<'a, T: Row>"),
+            ("Point", "(pub f32, pub f32)"),
+            ("Shape", ""),
+            ("area", "(&self) -> f64"),
+            ("Result", "<T> = std::result::Result<T, Error>"),
+            ("ui", ""),
+        ]
+    );
+    assert_eq!(definitions[2].children.len(), 1, "The body is still read");
+}
+
+#[test]
+fn test_doc_comments_are_collected() {
+    let definitions = scan_source(
+        "//! Docs of the file itself\n\
+         /// Parses a line of input.\n\
+         ///\n\
+         /// Blank lines are skipped.\n\
+         #[inline]\n\
+         // Not part of the docs\n\
+         pub fn parse() {}\n\
+         //// A separator, not docs\n\
+         fn undocumented() {}\n\
+         /// A row of the results\n\
+         struct Row {\n\
+             /// Where the row is drawn\n\
+             y: usize,\n\
+             x: usize,\n\
+         }\n\
+         mod ui {\n\
+             //! Rendering\n\
+             //! of results\n\
+         }\n",
+    );
+
+    let docs_of = |name: &str| -> String {
+        definitions
+            .iter()
+            .flat_map(|definition| std::iter::once(definition).chain(&definition.children))
+            .find(|definition| definition.name == name)
+            .unwrap()
+            .docs
+            .clone()
+    };
+    assert_eq!(
+        docs_of("parse"),
+        "Parses a line of input.\n\nBlank lines are skipped."
+    );
+    assert_eq!(docs_of("undocumented"), "");
+    assert_eq!(docs_of("Row"), "A row of the results");
+    assert_eq!(docs_of("y"), "Where the row is drawn");
+    assert_eq!(docs_of("x"), "");
+    assert_eq!(docs_of("ui"), "Rendering\nof results");
+    assert_eq!(definitions[0].doc_summary(), "Parses a line of input.");
+}
+
+#[test]
+fn test_path_attributes_are_recorded_on_modules() {
+    let definitions = scan_source(
+        "#[path = \"os/unix.rs\"]\n\
+         mod sys;\n\
+         #[cfg(unix)] #[path=\"imp.rs\"] pub mod imp;\n\
+         mod plain;\n",
+    );
+
+    let path_of = |name: &str| -> Option<String> {
+        definitions
+            .iter()
+            .find(|definition| definition.name == name)
+            .unwrap()
+            .path_attribute
+            .clone()
+    };
+    assert_eq!(path_of("sys"), Some("os/unix.rs".to_owned()));
+    assert_eq!(path_of("imp"), Some("imp.rs".to_owned()));
+    assert_eq!(path_of("plain"), None);
+}
+
+#[test]
+fn test_name_columns_are_recorded_in_bytes_and_chars() {
+    let definitions = scan_source(
+        "pub fn run() {}\n\
+         #[doc = \"é\"] struct Naïve {\n\
+             pub(crate) field_é: u8,\n\
+         }\n\
+         #[derive(Debug)] enum Mode { Fast }\n",
+    );
+
+    let columns_of = |name: &str| -> (usize, usize) {
+        let definition = definitions
+            .iter()
+            .flat_map(|definition| std::iter::once(definition).chain(&definition.children))
+            .find(|definition| definition.name == name)
+            .unwrap();
+        (definition.column, definition.char_column)
+    };
+    assert_eq!(columns_of("run"), (8, 8));
+    assert_eq!(
+        columns_of("Naïve"),
+        (22, 21),
+        "The é before it is two bytes but one char"
+    );
+    assert_eq!(columns_of("field_é"), (12, 12));
+    assert_eq!(columns_of("Mode"), (23, 23));
+}
+
+fn scan_source_with(source: &str, config: &ScanConfig) -> Vec<TypeDefinition> {
+    let project = TempProject::new();
+    project.write("src/lib.rs", source);
+    let file_path = Rc::new(project.path().join("src/lib.rs"));
+    scan_rust_file_fast(&file_path, config).unwrap()
+}
+
+fn flattened(definitions: &[TypeDefinition]) -> Vec<(String, usize, Option<String>, bool)> {
+    definitions
+        .iter()
+        .flat_map(|definition| std::iter::once(definition).chain(&definition.children))
+        .map(|definition| {
+            (
+                definition.name.clone(),
+                definition.line_number,
+                definition.owner.as_deref().map(str::to_owned),
+                definition.partial_scan,
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_large_files_are_scanned_in_chunks() {
+    let mut source = String::new();
+    for i in 0..40 {
+        source.push_str(&format!(
+            "/// Item {}\n\
+             pub struct Item{} {{\n    \
+                 pub value: u32,\n\
+             }}\n\
+             \n\
+             impl Item{} {{\n    \
+                 pub fn get(&self) -> u32 {{\n        \
+                     self.value\n    \
+                 }}\n\
+             \n    \
+                 pub fn set(&mut self, value: u32) {{}}\n\
+             }}\n\n",
+            i, i, i
+        ));
+    }
+    let whole = scan_source(&source);
+    let chunked = scan_source_with(
+        &source,
+        &ScanConfig {
+            scan_chunk_size: 150,
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(whole.len(), 160, "No cap on definitions by default");
+    assert_eq!(
+        flattened(&chunked),
+        flattened(&whole),
+        "Chunks end between top-level items, so nothing is lost"
+    );
+    assert_eq!(chunked[156].docs, "Item 39");
+}
+
+#[test]
+fn test_files_scanned_in_part_are_marked() {
+    let long_line = format!(
+        "pub struct Long; // {}\n\npub fn after() {{}}\n",
+        "x".repeat(200)
+    );
+    let definitions = scan_source_with(
+        &long_line,
+        &ScanConfig {
+            scan_chunk_size: 64,
+            ..Default::default()
+        },
+    );
+    assert_eq!(
+        flattened(&definitions),
+        vec![
+            ("Long".to_owned(), 1, None, true),
+            ("after".to_owned(), 3, None, true),
+        ],
+        "The first line was cut between chunks"
+    );
+
+    let definitions = scan_source_with(
+        "fn one() {}\nfn two() {}\nfn three() {}\n",
+        &ScanConfig {
+            max_definitions_per_file: 2,
+            ..Default::default()
+        },
+    );
+    assert_eq!(definitions.len(), 2);
+    assert!(definitions.iter().all(|definition| definition.partial_scan));
+
+    let definitions = scan_source("fn one() {}\n");
+    assert!(!definitions[0].partial_scan);
+}
+
+#[test]
+fn test_files_over_max_file_size_are_skipped() {
+    let source = "pub struct Small;\n";
+    let config = |max_file_size| ScanConfig {
+        max_file_size,
+        ..Default::default()
+    };
+    assert_eq!(scan_source_with(source, &config(18)).len(), 1);
+    assert!(scan_source_with(source, &config(17)).is_empty());
+}
+
+#[test]
+fn test_chunks_are_not_cut_inside_raw_strings() {
+    let source = "pub fn before() {}\n\
+                  \n\
+                  pub const TEMPLATE: &str = r#\"\n\
+                  header\n\
+                  \n\
+                  pub struct Phantom;\n\
+                  \n\
+                  footer\n\
+                  \"#;\n\
+                  \n\
+                  pub fn after() {}\n";
+    // The first chunk ends right after `pub struct Phantom;`
+    let scan_chunk_size = source.find("Phantom;").unwrap() + "Phantom;\n".len();
+    let definitions = scan_source_with(
+        source,
+        &ScanConfig {
+            scan_chunk_size: scan_chunk_size as u64,
+            ..Default::default()
+        },
+    );
+    assert_eq!(
+        flattened(&definitions),
+        vec![
+            ("before".to_owned(), 1, None, false),
+            ("TEMPLATE".to_owned(), 3, None, false),
+            ("after".to_owned(), 11, None, false),
+        ],
+        "The chunk ends before the string instead"
+    );
+
+    // Without a place to end outside of the string, the file is cut anyway
+    let definitions = scan_source_with(
+        &source[source.find("pub const").unwrap()..],
+        &ScanConfig {
+            scan_chunk_size: 40,
+            ..Default::default()
+        },
+    );
+    assert!(definitions.iter().all(|definition| definition.partial_scan));
+}
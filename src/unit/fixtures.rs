@@ -5,7 +5,8 @@ use super::test_zellij::PaneId;
 use crate::files::{TypeDefinition, TypeKind};
 use crate::pane::PaneMetadata;
 use std::collections::BTreeMap;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
 /// Create sample panes for testing
@@ -47,28 +48,28 @@ pub fn sample_rust_assets() -> BTreeMap<PathBuf, Vec<TypeDefinition>> {
     rust_assets.insert(
         (*main_rs).clone(),
         vec![
-            TypeDefinition {
-                type_kind: TypeKind::Struct,
-                name: "State".to_string(),
-                file_path: Rc::clone(&main_rs),
-                line_number: 79,
-            },
-            TypeDefinition {
-                type_kind: TypeKind::Function,
-                name: "render".to_string(),
-                file_path: Rc::clone(&main_rs),
-                line_number: 230,
-            },
+            TypeDefinition::new(
+                TypeKind::Struct,
+                "State".to_string(),
+                Rc::clone(&main_rs),
+                79,
+            ),
+            TypeDefinition::new(
+                TypeKind::Function,
+                "render".to_string(),
+                Rc::clone(&main_rs),
+                230,
+            ),
         ],
     );
     rust_assets.insert(
         (*ui_rs).clone(),
-        vec![TypeDefinition {
-            type_kind: TypeKind::Struct,
-            name: "UIRenderer".to_string(),
-            file_path: Rc::clone(&ui_rs),
-            line_number: 10,
-        }],
+        vec![TypeDefinition::new(
+            TypeKind::Struct,
+            "UIRenderer".to_string(),
+            Rc::clone(&ui_rs),
+            10,
+        )],
     );
 
     rust_assets
@@ -84,45 +85,45 @@ pub fn struct_search_rust_assets() -> BTreeMap<PathBuf, Vec<TypeDefinition>> {
     rust_assets.insert(
         (*main_rs).clone(),
         vec![
-            TypeDefinition {
-                type_kind: TypeKind::Struct,
-                name: "State".to_string(),
-                file_path: Rc::clone(&main_rs),
-                line_number: 79,
-            },
-            TypeDefinition {
-                type_kind: TypeKind::Function,
-                name: "render".to_string(),
-                file_path: Rc::clone(&main_rs),
-                line_number: 230,
-            },
+            TypeDefinition::new(
+                TypeKind::Struct,
+                "State".to_string(),
+                Rc::clone(&main_rs),
+                79,
+            ),
+            TypeDefinition::new(
+                TypeKind::Function,
+                "render".to_string(),
+                Rc::clone(&main_rs),
+                230,
+            ),
         ],
     );
     rust_assets.insert(
         (*types_rs).clone(),
         vec![
-            TypeDefinition {
-                type_kind: TypeKind::Struct,
-                name: "MyStruct".to_string(),
-                file_path: Rc::clone(&types_rs),
-                line_number: 10,
-            },
-            TypeDefinition {
-                type_kind: TypeKind::Struct,
-                name: "MyStructHelper".to_string(),
-                file_path: Rc::clone(&types_rs),
-                line_number: 25,
-            },
+            TypeDefinition::new(
+                TypeKind::Struct,
+                "MyStruct".to_string(),
+                Rc::clone(&types_rs),
+                10,
+            ),
+            TypeDefinition::new(
+                TypeKind::Struct,
+                "MyStructHelper".to_string(),
+                Rc::clone(&types_rs),
+                25,
+            ),
         ],
     );
     rust_assets.insert(
         (*state_rs).clone(),
-        vec![TypeDefinition {
-            type_kind: TypeKind::Struct,
-            name: "AppState".to_string(),
-            file_path: Rc::clone(&state_rs),
-            line_number: 8,
-        }],
+        vec![TypeDefinition::new(
+            TypeKind::Struct,
+            "AppState".to_string(),
+            Rc::clone(&state_rs),
+            8,
+        )],
     );
 
     rust_assets
@@ -138,43 +139,43 @@ pub fn enum_search_rust_assets() -> BTreeMap<PathBuf, Vec<TypeDefinition>> {
     rust_assets.insert(
         (*types_rs).clone(),
         vec![
-            TypeDefinition {
-                type_kind: TypeKind::Enum,
-                name: "SearchMode".to_string(),
-                file_path: Rc::clone(&types_rs),
-                line_number: 42,
-            },
-            TypeDefinition {
-                type_kind: TypeKind::Enum,
-                name: "SearchType".to_string(),
-                file_path: Rc::clone(&types_rs),
-                line_number: 58,
-            },
-            TypeDefinition {
-                type_kind: TypeKind::Struct,
-                name: "SearchHelper".to_string(),
-                file_path: Rc::clone(&types_rs),
-                line_number: 100,
-            },
+            TypeDefinition::new(
+                TypeKind::Enum,
+                "SearchMode".to_string(),
+                Rc::clone(&types_rs),
+                42,
+            ),
+            TypeDefinition::new(
+                TypeKind::Enum,
+                "SearchType".to_string(),
+                Rc::clone(&types_rs),
+                58,
+            ),
+            TypeDefinition::new(
+                TypeKind::Struct,
+                "SearchHelper".to_string(),
+                Rc::clone(&types_rs),
+                100,
+            ),
         ],
     );
     rust_assets.insert(
         (*search_rs).clone(),
-        vec![TypeDefinition {
-            type_kind: TypeKind::Enum,
-            name: "SearchItem".to_string(),
-            file_path: Rc::clone(&search_rs),
-            line_number: 17,
-        }],
+        vec![TypeDefinition::new(
+            TypeKind::Enum,
+            "SearchItem".to_string(),
+            Rc::clone(&search_rs),
+            17,
+        )],
     );
     rust_assets.insert(
         (*events_rs).clone(),
-        vec![TypeDefinition {
-            type_kind: TypeKind::Enum,
-            name: "EventType".to_string(),
-            file_path: Rc::clone(&events_rs),
-            line_number: 5,
-        }],
+        vec![TypeDefinition::new(
+            TypeKind::Enum,
+            "EventType".to_string(),
+            Rc::clone(&events_rs),
+            5,
+        )],
     );
 
     rust_assets
@@ -190,52 +191,98 @@ pub fn function_search_rust_assets() -> BTreeMap<PathBuf, Vec<TypeDefinition>> {
     rust_assets.insert(
         (*main_rs).clone(),
         vec![
-            TypeDefinition {
-                type_kind: TypeKind::Function,
-                name: "render".to_string(),
-                file_path: Rc::clone(&main_rs),
-                line_number: 230,
-            },
-            TypeDefinition {
-                type_kind: TypeKind::Function,
-                name: "render_ui".to_string(),
-                file_path: Rc::clone(&main_rs),
-                line_number: 250,
-            },
-            TypeDefinition {
-                type_kind: TypeKind::Struct,
-                name: "RenderState".to_string(),
-                file_path: Rc::clone(&main_rs),
-                line_number: 50,
-            },
+            TypeDefinition::new(
+                TypeKind::Function,
+                "render".to_string(),
+                Rc::clone(&main_rs),
+                230,
+            ),
+            TypeDefinition::new(
+                TypeKind::Function,
+                "render_ui".to_string(),
+                Rc::clone(&main_rs),
+                250,
+            ),
+            TypeDefinition::new(
+                TypeKind::Struct,
+                "RenderState".to_string(),
+                Rc::clone(&main_rs),
+                50,
+            ),
         ],
     );
     rust_assets.insert(
         (*ui_rs).clone(),
         vec![
-            TypeDefinition {
-                type_kind: TypeKind::Function,
-                name: "render_table".to_string(),
-                file_path: Rc::clone(&ui_rs),
-                line_number: 100,
-            },
-            TypeDefinition {
-                type_kind: TypeKind::Function,
-                name: "render_text".to_string(),
-                file_path: Rc::clone(&ui_rs),
-                line_number: 120,
-            },
+            TypeDefinition::new(
+                TypeKind::Function,
+                "render_table".to_string(),
+                Rc::clone(&ui_rs),
+                100,
+            ),
+            TypeDefinition::new(
+                TypeKind::Function,
+                "render_text".to_string(),
+                Rc::clone(&ui_rs),
+                120,
+            ),
         ],
     );
     rust_assets.insert(
         (*search_rs).clone(),
-        vec![TypeDefinition {
-            type_kind: TypeKind::Function,
-            name: "search".to_string(),
-            file_path: Rc::clone(&search_rs),
-            line_number: 42,
-        }],
+        vec![TypeDefinition::new(
+            TypeKind::Function,
+            "search".to_string(),
+            Rc::clone(&search_rs),
+            42,
+        )],
     );
 
     rust_assets
 }
+
+/// Temporary project folder on disk, removed when dropped
+pub struct TempProject {
+    root: PathBuf,
+}
+
+impl TempProject {
+    pub fn new() -> Self {
+        let root = std::env::temp_dir().join(format!("grab-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&root).expect("Failed to create temp project");
+        TempProject { root }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.root
+    }
+
+    /// Write a file relative to the project root, creating parent folders
+    pub fn write(&self, relative_path: &str, contents: &str) {
+        let path = self.root.join(relative_path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).expect("Failed to create parent folder");
+        }
+        fs::write(path, contents).expect("Failed to write file");
+    }
+
+    /// Create a symlink at `relative_path` pointing to `target`
+    pub fn symlink(&self, relative_path: &str, target: &Path) {
+        let path = self.root.join(relative_path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).expect("Failed to create parent folder");
+        }
+        std::os::unix::fs::symlink(target, path).expect("Failed to create symlink");
+    }
+
+    /// Strip the project root from a path returned by the walker
+    pub fn relative(&self, path: &Path) -> PathBuf {
+        path.strip_prefix(&self.root).unwrap_or(path).to_path_buf()
+    }
+}
+
+impl Drop for TempProject {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
@@ -0,0 +1,128 @@
+use crate::config::ScanConfig;
+use crate::files::get_all_files;
+use crate::ignore::{glob_match, IgnoreFile, IgnoreMatch};
+use crate::unit::fixtures::TempProject;
+use std::path::{Path, PathBuf};
+
+#[test]
+fn test_glob_match_basics() {
+    assert!(glob_match(b"*.log", b"debug.log"));
+    assert!(!glob_match(b"*.log", b"logs/debug.log"));
+    assert!(glob_match(b"**/fixtures", b"fixtures"));
+    assert!(glob_match(b"**/fixtures", b"tests/data/fixtures"));
+    assert!(glob_match(b"assets/**", b"assets/img/logo.png"));
+    assert!(glob_match(b"a/**/b", b"a/b"));
+    assert!(glob_match(b"a/**/b", b"a/x/y/b"));
+    assert!(glob_match(b"file[0-9].txt", b"file7.txt"));
+    assert!(!glob_match(b"file[!0-9].txt", b"file7.txt"));
+    assert!(glob_match(b"?.rs", b"a.rs"));
+}
+
+#[test]
+fn test_ignore_file_negation_and_anchoring() {
+    let ignore_file = IgnoreFile::parse(
+        PathBuf::new(),
+        "# generated\n*.gen.rs\n!keep.gen.rs\n/build/\nvendor\n",
+    );
+
+    let matched = |path: &str, is_dir: bool| ignore_file.matched(Path::new(path), is_dir);
+
+    assert_eq!(matched("src/foo.gen.rs", false), IgnoreMatch::Ignore);
+    assert_eq!(matched("src/keep.gen.rs", false), IgnoreMatch::Whitelist);
+    assert_eq!(matched("build", true), IgnoreMatch::Ignore);
+    // Anchored and directory-only: nested "build" folders and files are kept
+    assert_eq!(matched("crates/build", true), IgnoreMatch::None);
+    assert_eq!(matched("build", false), IgnoreMatch::None);
+    assert_eq!(matched("third_party/vendor", true), IgnoreMatch::Ignore);
+}
+
+// Points core.excludesFile at a folder of the test, so the global ignore file
+// of whoever runs the tests can't change what is indexed
+fn set_global_excludes(project: &TempProject, home: &TempProject, patterns: Option<&str>) {
+    if let Some(patterns) = patterns {
+        home.write("git/ignore", patterns);
+    }
+    project.write(
+        ".git/config",
+        &format!(
+            "[core]\n\texcludesFile = \"{}\"\n",
+            home.path().join("git/ignore").display()
+        ),
+    );
+}
+
+#[test]
+fn test_walker_respects_nested_ignore_files() {
+    let project = TempProject::new();
+    let home = TempProject::new();
+    set_global_excludes(&project, &home, None);
+    project.write(".gitignore", "generated/\n*.snap\n");
+    project.write(".git/info/exclude", "scratch.txt\n");
+    project.write("src/main.rs", "fn main() {}\n");
+    project.write("src/bin/tool.rs", "fn main() {}\n");
+    project.write("src/generated/out.rs", "");
+    project.write("src/fixtures/.ignore", "*.json\n!keep.json\n");
+    project.write("src/fixtures/data.json", "{}");
+    project.write("src/fixtures/keep.json", "{}");
+    project.write("tests/a.snap", "");
+    project.write("scratch.txt", "");
+
+    let files: Vec<PathBuf> = get_all_files(project.path(), &ScanConfig::default())
+        .unwrap()
+        .into_keys()
+        .map(|path| project.relative(&path))
+        .collect();
+
+    assert!(files.contains(&PathBuf::from("src/main.rs")));
+    // "bin" is only in the fallback list, which is unused when ignore files exist
+    assert!(files.contains(&PathBuf::from("src/bin/tool.rs")));
+    assert!(files.contains(&PathBuf::from("src/fixtures/keep.json")));
+    assert!(!files.contains(&PathBuf::from("src/generated/out.rs")));
+    assert!(!files.contains(&PathBuf::from("src/fixtures/data.json")));
+    assert!(!files.contains(&PathBuf::from("tests/a.snap")));
+    assert!(!files.contains(&PathBuf::from("scratch.txt")));
+    assert!(!files.iter().any(|f| f.starts_with(".git")));
+}
+
+#[test]
+fn test_walker_falls_back_to_default_list_without_ignore_files() {
+    let project = TempProject::new();
+    let home = TempProject::new();
+    set_global_excludes(&project, &home, None);
+    project.write("src/lib.rs", "");
+    project.write("node_modules/pkg/index.js", "");
+    project.write("target/debug/build.rs", "");
+
+    let files: Vec<PathBuf> = get_all_files(project.path(), &ScanConfig::default())
+        .unwrap()
+        .into_keys()
+        .map(|path| project.relative(&path))
+        .collect();
+
+    assert_eq!(files, vec![PathBuf::from("src/lib.rs")]);
+}
+
+#[test]
+fn test_walker_respects_the_global_excludes_file() {
+    let project = TempProject::new();
+    let home = TempProject::new();
+    set_global_excludes(&project, &home, Some("*.log\n"));
+    project.write("src/lib.rs", "");
+    project.write("debug.log", "");
+    project.write("node_modules/pkg/index.js", "");
+
+    let files: Vec<PathBuf> = get_all_files(project.path(), &ScanConfig::default())
+        .unwrap()
+        .into_keys()
+        .map(|path| project.relative(&path))
+        .collect();
+
+    // Like any ignore file, it replaces the fallback list
+    assert_eq!(
+        files,
+        vec![
+            PathBuf::from("node_modules/pkg/index.js"),
+            PathBuf::from("src/lib.rs")
+        ]
+    );
+}
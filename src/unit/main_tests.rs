@@ -3,8 +3,8 @@ use crate::State;
 use std::collections::BTreeMap;
 use std::path::PathBuf;
 use test_zellij::{
-    BareKey, Event, Key, KeyModifier, PaneId, PermissionStatus, PipeMessage, PluginIds,
-    ZellijPlugin,
+    BareKey, Event, FileMetadata, Key, KeyModifier, PaneId, PermissionStatus, PermissionType,
+    PipeMessage, PluginIds, ZellijPlugin,
 };
 
 fn setup() -> State {
@@ -23,9 +23,11 @@ fn test_load_requests_permissions() {
     state.load(BTreeMap::new());
 
     let calls = test_zellij::mock_get_calls();
-    assert!(calls
-        .iter()
-        .any(|c| matches!(c, test_zellij::ZellijCall::RequestPermission(_))));
+    assert!(calls.iter().any(|c| matches!(
+        c,
+        test_zellij::ZellijCall::RequestPermission(permissions)
+            if permissions.contains(&PermissionType::RunCommands)
+    )));
 }
 
 #[test]
@@ -958,3 +960,1264 @@ fn test_search_rendering_shows_correct_results() {
 
     test_zellij::assert_frame_snapshot("search_results_ui");
 }
+
+#[test]
+fn test_index_batches_from_worker_are_applied_incrementally() {
+    use crate::worker::{IndexBatch, CONTINUE_INDEXING, INDEX_BATCH, START_INDEXING};
+
+    test_zellij::mock_init();
+    test_zellij::mock_set_plugin_ids(PluginIds {
+        plugin_id: 42,
+        zellij_pid: 1234,
+        initial_cwd: PathBuf::from("/home/user/project"),
+    });
+    test_zellij::mock_init_frame(80, 24);
+
+    let mut plugin = State::default();
+    plugin.load(BTreeMap::new());
+    plugin.start_indexing();
+
+    let calls = test_zellij::mock_get_calls();
+    assert!(calls.iter().any(|c| matches!(
+        c,
+        test_zellij::ZellijCall::PostMessageTo { worker_name: Some(worker), name, .. }
+            if worker == "index" && name == START_INDEXING
+    )));
+
+    let batch = |generation, files: &[&str], done| IndexBatch {
+        generation,
+        files: files
+            .iter()
+            .map(|file| (PathBuf::from(file), vec![]))
+            .collect(),
+        indexed_count: files.len(),
+        done,
+        truncated_at: None,
+        removed: vec![],
+        symlinked: vec![],
+    };
+
+    test_zellij::mock_clear_calls();
+    let first = serde_json::to_string(&batch(1, &["src/main.rs", "README.md"], false)).unwrap();
+    assert!(plugin.update(Event::CustomMessage(INDEX_BATCH.to_owned(), first)));
+    assert_eq!(plugin.app_state.get_files().len(), 2);
+    assert!(plugin.app_state.get_index_status().in_progress);
+
+    // The plugin asks the worker for the next batch
+    let calls = test_zellij::mock_get_calls();
+    assert!(calls.iter().any(|c| matches!(
+        c,
+        test_zellij::ZellijCall::PostMessageTo { name, payload, .. }
+            if name == CONTINUE_INDEXING && payload == "1"
+    )));
+
+    plugin.render(24, 80);
+    let frame = test_zellij::mock_get_frame().expect("Frame should be initialized");
+    assert!(frame.to_string().contains("Indexing 2 files..."));
+
+    // Batches of an outdated scan are dropped
+    let stale = serde_json::to_string(&batch(0, &["old.rs"], true)).unwrap();
+    assert!(!plugin.update(Event::CustomMessage(INDEX_BATCH.to_owned(), stale)));
+    assert_eq!(plugin.app_state.get_files().len(), 2);
+
+    let last = serde_json::to_string(&batch(1, &["Cargo.toml"], true)).unwrap();
+    plugin.update(Event::CustomMessage(INDEX_BATCH.to_owned(), last));
+    assert_eq!(plugin.app_state.get_files().len(), 3);
+    assert!(!plugin.app_state.get_index_status().in_progress);
+}
+
+#[test]
+fn test_file_system_delete_removes_files_from_index() {
+    use crate::worker::INDEX_BATCH;
+
+    let mut plugin = setup();
+    plugin.load(BTreeMap::new());
+    plugin.start_indexing();
+
+    let batch = serde_json::json!({
+        "generation": 1,
+        "files": [
+            ["src/main.rs", [{
+                "type_kind": "Struct",
+                "name": "State",
+                "file_path": "src/main.rs",
+                "line_number": 79
+            }]],
+            ["src/old/mod.rs", []],
+            ["src/old/helpers.rs", []],
+            ["README.md", []]
+        ],
+        "indexed_count": 4,
+        "done": true,
+        "truncated_at": null
+    });
+    plugin.update(Event::CustomMessage(
+        INDEX_BATCH.to_owned(),
+        batch.to_string(),
+    ));
+    assert_eq!(plugin.app_state.get_files().len(), 4);
+
+    plugin.update(Event::FileSystemDelete(vec![
+        (PathBuf::from("/host/src/main.rs"), None),
+        (PathBuf::from("/host/src/old"), None),
+    ]));
+
+    assert_eq!(plugin.app_state.get_files(), &[PathBuf::from("README.md")]);
+    assert!(plugin.app_state.get_rust_assets().is_empty());
+}
+
+#[test]
+fn test_new_directories_are_indexed_by_the_worker_within_the_file_limit() {
+    use crate::worker::{IndexRequest, DIRECTORY_BATCH, INDEX_BATCH, INDEX_DIRECTORY};
+
+    let mut plugin = setup();
+    let mut config = BTreeMap::new();
+    config.insert("file_limit".to_owned(), "5".to_owned());
+    plugin.load(config);
+    plugin.start_indexing();
+
+    let batch = serde_json::json!({
+        "generation": 1,
+        "files": [["src/main.rs", []], ["src/lib.rs", []], ["README.md", []]],
+        "indexed_count": 3,
+        "done": true,
+        "truncated_at": null
+    });
+    plugin.update(Event::CustomMessage(
+        INDEX_BATCH.to_owned(),
+        batch.to_string(),
+    ));
+    test_zellij::mock_clear_calls();
+
+    plugin.update(Event::FileSystemCreate(vec![(
+        PathBuf::from("/host/src/new"),
+        Some(FileMetadata {
+            is_dir: true,
+            ..Default::default()
+        }),
+    )]));
+
+    // Only what is left of the file limit is offered to the new folder
+    let request = test_zellij::mock_get_calls()
+        .into_iter()
+        .find_map(|call| match call {
+            test_zellij::ZellijCall::PostMessageTo { name, payload, .. }
+                if name == INDEX_DIRECTORY =>
+            {
+                serde_json::from_str::<IndexRequest>(&payload).ok()
+            }
+            _ => None,
+        })
+        .expect("the folder should be sent to the worker");
+    assert_eq!(request.directory, Some(PathBuf::from("src/new")));
+    assert_eq!(request.config.file_limit, 2);
+    assert_eq!(plugin.app_state.get_files().len(), 3);
+
+    let batch = serde_json::json!({
+        "generation": 1,
+        "files": [["src/new/a.rs", []], ["src/new/b.rs", []]],
+        "indexed_count": 2,
+        "done": true,
+        "truncated_at": 2
+    });
+    assert!(plugin.update(Event::CustomMessage(
+        DIRECTORY_BATCH.to_owned(),
+        batch.to_string(),
+    )));
+    assert_eq!(plugin.app_state.get_files().len(), 5);
+    let index_status = plugin.app_state.get_index_status();
+    assert_eq!(index_status.indexed_count, 5);
+    assert_eq!(index_status.truncated_at, Some(5));
+}
+
+#[test]
+fn test_symbol_index_is_only_requested_when_enabled() {
+    use crate::worker::{ImportRequest, LOAD_SYMBOL_INDEX};
+
+    let index_requests = || -> Vec<ImportRequest> {
+        test_zellij::mock_get_calls()
+            .into_iter()
+            .filter_map(|call| match call {
+                test_zellij::ZellijCall::PostMessageTo { name, payload, .. }
+                    if name == LOAD_SYMBOL_INDEX =>
+                {
+                    serde_json::from_str(&payload).ok()
+                }
+                _ => None,
+            })
+            .collect()
+    };
+
+    let mut plugin = setup();
+    plugin.load(BTreeMap::new());
+    plugin.start_indexing();
+    assert!(index_requests().is_empty());
+
+    let mut plugin = setup();
+    let mut config = BTreeMap::new();
+    config.insert("symbol_index".to_owned(), "target/index.scip".to_owned());
+    plugin.load(config);
+    plugin.start_indexing();
+    let requests = index_requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(
+        requests[0].index_file,
+        Some(PathBuf::from("target/index.scip"))
+    );
+}
+
+#[test]
+fn test_git_index_source_uses_ls_files_and_falls_back_to_walking() {
+    use crate::worker::{IndexRequest, START_INDEXING};
+
+    let start_requests = || -> Vec<IndexRequest> {
+        test_zellij::mock_get_calls()
+            .into_iter()
+            .filter_map(|call| match call {
+                test_zellij::ZellijCall::PostMessageTo { name, payload, .. }
+                    if name == START_INDEXING =>
+                {
+                    serde_json::from_str(&payload).ok()
+                }
+                _ => None,
+            })
+            .collect()
+    };
+
+    let mut plugin = setup();
+    let mut configuration = BTreeMap::new();
+    configuration.insert("index_source".to_owned(), "git".to_owned());
+    plugin.load(configuration);
+    test_zellij::mock_clear_calls();
+    plugin.start_indexing();
+
+    let context = test_zellij::mock_get_calls()
+        .into_iter()
+        .find_map(|call| match call {
+            test_zellij::ZellijCall::RunCommand {
+                command, context, ..
+            } => {
+                assert_eq!(&command[..2], &["git", "ls-files"]);
+                Some(context)
+            }
+            _ => None,
+        })
+        .expect("git ls-files should be run");
+    assert!(start_requests().is_empty());
+
+    plugin.update(Event::RunCommandResult(
+        Some(0),
+        b"src/main.rs\0Cargo.toml\0".to_vec(),
+        vec![],
+        context,
+    ));
+    let requests = start_requests();
+    assert_eq!(
+        requests[0].files,
+        Some(vec![
+            PathBuf::from("src/main.rs"),
+            PathBuf::from("Cargo.toml")
+        ])
+    );
+
+    // Not a git repository (or no git at all): walk the folder
+    test_zellij::mock_clear_calls();
+    plugin.start_indexing();
+    let context = test_zellij::mock_get_calls()
+        .into_iter()
+        .find_map(|call| match call {
+            test_zellij::ZellijCall::RunCommand { context, .. } => Some(context),
+            _ => None,
+        })
+        .unwrap();
+    plugin.update(Event::RunCommandResult(Some(128), vec![], vec![], context));
+    let requests = start_requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].files, None);
+}
+
+#[test]
+fn test_crate_filter_restricts_results_to_one_package() {
+    use crate::files::{TypeDefinition, TypeKind};
+    use crate::unit::fixtures::TempProject;
+    use crate::workspace::CargoWorkspace;
+    use std::rc::Rc;
+
+    let project = TempProject::new();
+    project.write("Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\n");
+    project.write(
+        "crates/core/Cargo.toml",
+        "[package]\nname = \"grab-core\"\n",
+    );
+    project.write("crates/cli/Cargo.toml", "[package]\nname = \"grab-cli\"\n");
+
+    let mut plugin = setup();
+    plugin.load(BTreeMap::new());
+    plugin
+        .app_state
+        .set_workspace(CargoWorkspace::load(project.path()));
+    for file in ["crates/core/src/parser.rs", "crates/cli/src/args.rs"] {
+        let file_path = Rc::new(PathBuf::from(file));
+        let definition = TypeDefinition::new(
+            TypeKind::Function,
+            "parse".to_owned(),
+            Rc::clone(&file_path),
+            1,
+        );
+        plugin
+            .app_state
+            .upsert_file((*file_path).clone(), vec![definition]);
+    }
+
+    for ch in "crate:grab-core fn parse".chars() {
+        plugin.update(Event::Key(Key {
+            bare_key: BareKey::Char(ch),
+            modifiers: vec![],
+        }));
+    }
+
+    let results = plugin.search_state.get_current_display_results();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].crate_name.as_deref(), Some("grab-core"));
+    assert!(results[0]
+        .display_text()
+        .contains("crates/core/src/parser.rs"));
+
+    test_zellij::mock_init_frame(100, 20);
+    plugin.render(20, 100);
+    let frame = test_zellij::mock_get_frame().unwrap().to_string();
+    assert!(
+        frame.contains("grab-core"),
+        "The crate column should be shown"
+    );
+}
+
+#[test]
+fn test_results_from_partially_scanned_files_are_marked() {
+    use crate::files::{TypeDefinition, TypeKind};
+    use std::rc::Rc;
+
+    let mut plugin = setup();
+    plugin.load(BTreeMap::new());
+    for (file, partial_scan) in [("src/bindings.rs", true), ("src/lib.rs", false)] {
+        let file_path = Rc::new(PathBuf::from(file));
+        let definition = TypeDefinition {
+            partial_scan,
+            ..TypeDefinition::new(
+                TypeKind::Function,
+                "decode".to_owned(),
+                Rc::clone(&file_path),
+                1,
+            )
+        };
+        plugin
+            .app_state
+            .upsert_file((*file_path).clone(), vec![definition]);
+    }
+
+    for ch in "fn decode".chars() {
+        plugin.update(Event::Key(Key {
+            bare_key: BareKey::Char(ch),
+            modifiers: vec![],
+        }));
+    }
+
+    let mut display_texts: Vec<String> = plugin
+        .search_state
+        .get_current_display_results()
+        .iter()
+        .map(|result| result.display_text())
+        .collect();
+    display_texts.sort();
+    assert_eq!(
+        display_texts,
+        vec![
+            "bindings::decode (src/bindings.rs, partially indexed)",
+            "decode (src/lib.rs)"
+        ]
+    );
+}
+
+#[test]
+fn test_invalid_configuration_is_shown_in_the_ui() {
+    let mut plugin = setup();
+    let mut configuration = BTreeMap::new();
+    configuration.insert("file_limit".to_owned(), "-5".to_owned());
+    plugin.load(configuration);
+
+    test_zellij::mock_init_frame(120, 20);
+    plugin.render(20, 120);
+
+    let frame = test_zellij::mock_get_frame().unwrap().to_string();
+    assert!(frame.contains("Invalid configuration: 'file_limit' should be a positive number"));
+    assert!(!frame.contains("Hint:"));
+}
+
+#[test]
+fn test_symlinked_results_are_marked() {
+    let mut plugin = setup();
+    plugin.load(BTreeMap::new());
+    plugin
+        .app_state
+        .upsert_file(PathBuf::from("vendor/shared/lib.rs"), vec![]);
+    plugin
+        .app_state
+        .upsert_file(PathBuf::from("src/lib.rs"), vec![]);
+    plugin
+        .app_state
+        .mark_symlinked_files(vec![PathBuf::from("vendor/shared/lib.rs")]);
+
+    for ch in "lib.rs".chars() {
+        plugin.update(Event::Key(Key {
+            bare_key: BareKey::Char(ch),
+            modifiers: vec![],
+        }));
+    }
+
+    let mut displayed: Vec<String> = plugin
+        .search_state
+        .get_current_display_results()
+        .iter()
+        .map(|result| result.display_text())
+        .collect();
+    displayed.sort();
+    assert_eq!(displayed, vec!["src/lib.rs", "vendor/shared/lib.rs@"]);
+}
+
+#[test]
+fn test_multiple_roots_are_indexed_labelled_and_opened_from_their_root() {
+    use crate::worker::{IndexBatch, IndexRequest, INDEX_BATCH, START_INDEXING};
+
+    let start_requests = || -> Vec<IndexRequest> {
+        test_zellij::mock_get_calls()
+            .into_iter()
+            .filter_map(|call| match call {
+                test_zellij::ZellijCall::PostMessageTo { name, payload, .. }
+                    if name == START_INDEXING =>
+                {
+                    serde_json::from_str(&payload).ok()
+                }
+                _ => None,
+            })
+            .collect()
+    };
+    let finish_root = |plugin: &mut State, generation, file: &str| {
+        let batch = IndexBatch {
+            generation,
+            files: vec![(PathBuf::from(file), vec![])],
+            indexed_count: 1,
+            done: true,
+            truncated_at: None,
+            removed: vec![],
+            symlinked: vec![],
+        };
+        plugin.update(Event::CustomMessage(
+            INDEX_BATCH.to_owned(),
+            serde_json::to_string(&batch).unwrap(),
+        ));
+    };
+
+    let mut plugin = setup();
+    let mut configuration = BTreeMap::new();
+    configuration.insert("roots".to_owned(), "vendor/sdk ../other".to_owned());
+    plugin.load(configuration);
+    plugin.initial_cwd = None;
+    test_zellij::mock_clear_calls();
+    plugin.start_indexing();
+
+    // `/test` is too close to `/` to be mounted, so the root next to the
+    // project is reported
+    assert!(!test_zellij::mock_get_calls()
+        .iter()
+        .any(|c| matches!(c, test_zellij::ZellijCall::ChangeHostFolder { .. })));
+    assert_eq!(
+        plugin.roots,
+        vec![
+            PathBuf::from("/test/project"),
+            PathBuf::from("/test/project/vendor/sdk")
+        ]
+    );
+
+    let requests = start_requests();
+    assert_eq!(requests.len(), 1, "Roots are indexed one after the other");
+    assert_eq!(requests[0].subdirectory, PathBuf::from(""));
+    assert_eq!(requests[0].repository_root, PathBuf::from("/test/project"));
+    assert_eq!(
+        requests[0].config.ignore_patterns,
+        vec!["/vendor/sdk/".to_owned()],
+        "The nested root is left to its own walk"
+    );
+
+    test_zellij::mock_clear_calls();
+    finish_root(&mut plugin, requests[0].generation, "src/main.rs");
+    assert!(plugin.app_state.get_index_status().in_progress);
+    let requests = start_requests();
+    assert_eq!(requests[0].subdirectory, PathBuf::from("vendor/sdk"));
+    assert!(requests[0].config.ignore_patterns.is_empty());
+    finish_root(&mut plugin, requests[0].generation, "vendor/sdk/src/lib.rs");
+    assert!(!plugin.app_state.get_index_status().in_progress);
+    assert_eq!(plugin.app_state.get_index_status().indexed_count, 2);
+
+    for character in "lib".chars() {
+        plugin.update(Event::Key(Key {
+            bare_key: BareKey::Char(character),
+            modifiers: vec![],
+        }));
+    }
+    let results = plugin.search_state.get_current_display_results();
+    assert_eq!(results[0].root_label.as_deref(), Some("sdk"));
+
+    test_zellij::mock_clear_calls();
+    plugin.update(Event::Key(Key {
+        bare_key: BareKey::Enter,
+        modifiers: vec![],
+    }));
+    assert!(test_zellij::mock_get_calls().iter().any(|c| matches!(
+        c,
+        test_zellij::ZellijCall::OpenFileInPlaceOfPlugin { path, .. }
+            if path == &PathBuf::from("/test/project/vendor/sdk/src/lib.rs")
+    )));
+}
+
+#[test]
+fn test_sibling_roots_are_indexed_through_the_folder_holding_them() {
+    use crate::worker::{IndexBatch, IndexRequest, INDEX_BATCH, START_INDEXING};
+
+    let start_requests = || -> Vec<IndexRequest> {
+        test_zellij::mock_get_calls()
+            .into_iter()
+            .filter_map(|call| match call {
+                test_zellij::ZellijCall::PostMessageTo { name, payload, .. }
+                    if name == START_INDEXING =>
+                {
+                    serde_json::from_str(&payload).ok()
+                }
+                _ => None,
+            })
+            .collect()
+    };
+
+    let mut plugin = setup();
+    let mut configuration = BTreeMap::new();
+    configuration.insert("roots".to_owned(), "../sdk ../client /srv/far".to_owned());
+    plugin.load(configuration);
+    plugin.initial_cwd = None;
+    plugin
+        .app_state
+        .set_cwd(PathBuf::from("/home/user/work/service"));
+    test_zellij::mock_clear_calls();
+    plugin.start_indexing();
+
+    assert_eq!(
+        plugin.roots,
+        vec![
+            PathBuf::from("/home/user/work/service"),
+            PathBuf::from("/home/user/work/sdk"),
+            PathBuf::from("/home/user/work/client")
+        ],
+        "Roots too far away to be mounted are left out"
+    );
+    assert!(test_zellij::mock_get_calls().iter().any(|c| matches!(
+        c,
+        test_zellij::ZellijCall::ChangeHostFolder { path }
+            if path == &PathBuf::from("/home/user/work")
+    )));
+    assert!(
+        start_requests().is_empty(),
+        "Indexing waits for the folder to be mounted"
+    );
+
+    plugin.update(Event::HostFolderChanged(PathBuf::from("/home/user/work")));
+    assert_eq!(
+        plugin.app_state.get_cwd(),
+        &PathBuf::from("/home/user/work")
+    );
+    let labels: Vec<String> = plugin
+        .app_state
+        .get_file_tags()
+        .roots
+        .iter()
+        .map(|root| root.label.to_string())
+        .collect();
+    assert_eq!(labels, vec!["service", "sdk", "client"]);
+
+    for (dir, file) in [
+        ("service", "service/src/main.rs"),
+        ("sdk", "sdk/src/lib.rs"),
+        ("client", "client/src/app.rs"),
+    ] {
+        let requests = start_requests();
+        let request = requests.last().unwrap();
+        assert_eq!(request.subdirectory, PathBuf::from(dir));
+        assert!(
+            request.config.ignore_patterns.is_empty(),
+            "Siblings aren't inside one another"
+        );
+        test_zellij::mock_clear_calls();
+        let batch = IndexBatch {
+            generation: request.generation,
+            files: vec![(PathBuf::from(file), vec![])],
+            indexed_count: 1,
+            done: true,
+            truncated_at: None,
+            removed: vec![],
+            symlinked: vec![],
+        };
+        plugin.update(Event::CustomMessage(
+            INDEX_BATCH.to_owned(),
+            serde_json::to_string(&batch).unwrap(),
+        ));
+    }
+    assert!(!plugin.app_state.get_index_status().in_progress);
+    assert_eq!(plugin.app_state.get_index_status().indexed_count, 3);
+
+    for character in "lib.rs".chars() {
+        plugin.update(Event::Key(Key {
+            bare_key: BareKey::Char(character),
+            modifiers: vec![],
+        }));
+    }
+    let results = plugin.search_state.get_current_display_results();
+    assert_eq!(results[0].root_label.as_deref(), Some("sdk"));
+    test_zellij::mock_clear_calls();
+    plugin.update(Event::Key(Key {
+        bare_key: BareKey::Enter,
+        modifiers: vec![],
+    }));
+    assert!(test_zellij::mock_get_calls().iter().any(|c| matches!(
+        c,
+        test_zellij::ZellijCall::OpenFileInPlaceOfPlugin { path, .. }
+            if path == &PathBuf::from("/home/user/work/sdk/src/lib.rs")
+    )));
+
+    // Adding another root keeps the folder mounted
+    test_zellij::mock_clear_calls();
+    plugin.add_root(PathBuf::from("/home/user/work/service/docs"));
+    assert_eq!(plugin.roots.len(), 4);
+    assert!(!test_zellij::mock_get_calls()
+        .iter()
+        .any(|c| matches!(c, test_zellij::ZellijCall::ChangeHostFolder { .. })));
+    assert_eq!(start_requests()[0].subdirectory, PathBuf::from("service"));
+}
+
+#[test]
+fn test_root_added_through_the_filepicker_is_indexed() {
+    let mut plugin = setup();
+    plugin.load(BTreeMap::new());
+    plugin.initial_cwd = None;
+    plugin.app_state.set_cwd(PathBuf::from("/test/project"));
+
+    let add_root = |plugin: &mut State, folder: &str| {
+        plugin.update(Event::Key(Key {
+            bare_key: BareKey::Char('a'),
+            modifiers: vec![KeyModifier::Ctrl],
+        }));
+        let mut args = BTreeMap::new();
+        args.insert("request_id".to_owned(), plugin.root_request_ids[0].clone());
+        plugin.pipe(PipeMessage {
+            source: test_zellij::PipeSource::Plugin(1),
+            name: "filepicker_result".to_owned(),
+            payload: Some(folder.to_owned()),
+            args,
+            is_private: false,
+        });
+    };
+
+    test_zellij::mock_clear_calls();
+    add_root(&mut plugin, "/test/project/docs");
+    assert!(plugin.root_request_ids.is_empty());
+    assert_eq!(
+        plugin.roots,
+        vec![
+            PathBuf::from("/test/project"),
+            PathBuf::from("/test/project/docs")
+        ]
+    );
+    let labels: Vec<String> = plugin
+        .app_state
+        .get_file_tags()
+        .roots
+        .iter()
+        .map(|root| root.label.to_string())
+        .collect();
+    assert_eq!(labels, vec!["project", "docs"]);
+
+    // `/test` would be mounted to reach a folder next to the project, but it
+    // is too close to `/`
+    add_root(&mut plugin, "/test/other");
+    assert_eq!(plugin.roots.len(), 2);
+    assert!(!test_zellij::mock_get_calls()
+        .iter()
+        .any(|c| matches!(c, test_zellij::ZellijCall::ChangeHostFolder { .. })));
+}
+
+#[test]
+fn test_query_prefixes_select_the_item_kind() {
+    use crate::files::TypeKind;
+    use crate::parse_rust_asset_search;
+
+    let cases = [
+        ("trait Rend", TypeKind::Trait, "Rend"),
+        ("IMPL Display for", TypeKind::Impl, "Display for"),
+        ("type Res", TypeKind::TypeAlias, "Res"),
+        ("const LIM", TypeKind::Const, "LIM"),
+        ("static COUNT", TypeKind::Static, "COUNT"),
+        ("macro_rules! vec", TypeKind::Macro, "vec"),
+        ("macro vec", TypeKind::Macro, "vec"),
+        ("mod ui", TypeKind::Module, "ui"),
+        ("union Bits", TypeKind::Union, "Bits"),
+        ("Struct Foo", TypeKind::Struct, "Foo"),
+        ("class Handler", TypeKind::Class, "Handler"),
+        ("interface Props", TypeKind::Interface, "Props"),
+        ("def load", TypeKind::Function, "load"),
+        ("func Serve", TypeKind::Function, "Serve"),
+        ("function render", TypeKind::Function, "render"),
+    ];
+    let sample = fixtures::sample_rust_assets()
+        .into_values()
+        .flatten()
+        .next()
+        .unwrap();
+    for (search_term, type_kind, term) in cases {
+        let mode = parse_rust_asset_search(search_term).unwrap();
+        let definition = crate::files::TypeDefinition {
+            type_kind,
+            ..sample.clone()
+        };
+        assert!(mode.matches(&definition), "{}", search_term);
+        assert_eq!(mode.term(), term);
+    }
+    assert!(parse_rust_asset_search("typed_builder").is_none());
+}
+
+#[test]
+fn test_method_queries_match_the_impl_type() {
+    use crate::app_state::FileTags;
+    use crate::files::{TypeDefinition, TypeKind};
+    use crate::search::SearchEngine;
+    use std::rc::Rc;
+
+    let method = |owner: Option<&str>, name: &str| TypeDefinition {
+        owner: owner.map(Rc::from),
+        ..TypeDefinition::new(
+            TypeKind::Function,
+            name.to_owned(),
+            Rc::new(PathBuf::from("src/search.rs")),
+            1,
+        )
+    };
+    let methods = [
+        method(Some("SearchEngine"), "search"),
+        method(Some("SearchState"), "new"),
+        method(None, "search"),
+    ];
+    let rust_assets: Vec<&TypeDefinition> = methods.iter().collect();
+    let search = |term: &str| -> Vec<String> {
+        SearchEngine::new()
+            .search(
+                term,
+                &[],
+                &[],
+                &rust_assets,
+                &FileTags::default(),
+                &PathBuf::new(),
+            )
+            .files_panes_results
+            .iter()
+            .map(|result| result.display_text())
+            .collect()
+    };
+
+    assert_eq!(
+        search("fn SearchEngine::search"),
+        vec!["SearchEngine::search (src/search.rs)"]
+    );
+    assert_eq!(
+        search("fn ::search"),
+        vec!["SearchEngine::search (src/search.rs)"],
+        "A leading '::' only matches methods"
+    );
+    assert_eq!(search("fn search").len(), 2);
+
+    // Highlights stay on the name when only the name was searched
+    let results = SearchEngine::new().search(
+        "fn new",
+        &[],
+        &[],
+        &rust_assets,
+        &FileTags::default(),
+        &PathBuf::new(),
+    );
+    assert_eq!(
+        results.files_panes_results[0].indices,
+        vec![13, 14, 15],
+        "'SearchState::' comes first"
+    );
+}
+
+#[test]
+fn test_variant_and_field_searches_show_their_parent() {
+    use crate::files::{TypeDefinition, TypeKind};
+    use std::rc::Rc;
+
+    let file_path = Rc::new(PathBuf::from("src/search.rs"));
+    let child = |type_kind, name: &str, owner: &str| TypeDefinition {
+        owner: Some(Rc::from(owner)),
+        ..TypeDefinition::new(type_kind, name.to_owned(), Rc::clone(&file_path), 7)
+    };
+    let search_item = TypeDefinition {
+        children: vec![
+            child(TypeKind::Variant, "RustAsset", "SearchItem"),
+            child(TypeKind::Variant, "Pane", "SearchItem"),
+        ],
+        ..TypeDefinition::new(
+            TypeKind::Enum,
+            "SearchItem".to_owned(),
+            Rc::clone(&file_path),
+            5,
+        )
+    };
+    let ui_state = TypeDefinition {
+        type_kind: TypeKind::Struct,
+        name: "UIState".to_owned(),
+        children: vec![child(TypeKind::Field, "scroll_offset", "UIState")],
+        ..search_item.clone()
+    };
+
+    let mut plugin = setup();
+    plugin.load(BTreeMap::new());
+    plugin
+        .app_state
+        .upsert_file((*file_path).clone(), vec![search_item, ui_state]);
+
+    let search = |plugin: &mut State, term: &str| -> Vec<String> {
+        plugin.search_state.clear();
+        for character in term.chars() {
+            plugin.search_state.add_char(character);
+        }
+        plugin.update_search_results();
+        plugin
+            .search_state
+            .get_rust_asset_display_results()
+            .iter()
+            .map(|result| result.display_text())
+            .collect()
+    };
+    assert_eq!(
+        search(&mut plugin, "variant RustAs"),
+        vec!["search::SearchItem::RustAsset (src/search.rs)"]
+    );
+    assert_eq!(
+        search(&mut plugin, "field scroll"),
+        vec!["search::UIState::scroll_offset (src/search.rs)"]
+    );
+    assert!(search(&mut plugin, "struct scroll").is_empty());
+}
+
+#[test]
+fn test_selected_test_runs_in_a_command_pane() {
+    use crate::files::{TypeDefinition, TypeKind};
+    use crate::unit::fixtures::TempProject;
+    use crate::workspace::CargoWorkspace;
+    use std::rc::Rc;
+
+    let project = TempProject::new();
+    project.write("Cargo.toml", "[package]\nname = \"grab\"\n");
+    let file_path = Rc::new(PathBuf::from("src/ui.rs"));
+    let test_fn = TypeDefinition {
+        is_test: true,
+        module_path: Some(Rc::from("tests")),
+        ..TypeDefinition::new(
+            TypeKind::Function,
+            "renders_empty_state".to_owned(),
+            Rc::clone(&file_path),
+            40,
+        )
+    };
+    let helper = TypeDefinition {
+        name: "render_helper".to_owned(),
+        is_test: false,
+        ..test_fn.clone()
+    };
+    let tests_module = TypeDefinition {
+        type_kind: TypeKind::Module,
+        name: "tests".to_owned(),
+        module_path: None,
+        ..test_fn.clone()
+    };
+
+    let mut plugin = setup();
+    plugin.load(BTreeMap::new());
+    plugin.app_state.set_cwd(PathBuf::from("/test/project"));
+    plugin
+        .app_state
+        .set_workspace(CargoWorkspace::load(project.path()));
+    plugin
+        .app_state
+        .upsert_file((*file_path).clone(), vec![test_fn, helper, tests_module]);
+
+    let run_test = |plugin: &mut State, term: &str| -> Option<test_zellij::CommandToRun> {
+        plugin.search_state.clear();
+        for character in term.chars() {
+            plugin.search_state.add_char(character);
+        }
+        plugin.update_search_results();
+        test_zellij::mock_clear_calls();
+        plugin.update(Event::Key(Key {
+            bare_key: BareKey::Char('r'),
+            modifiers: vec![KeyModifier::Ctrl],
+        }));
+        test_zellij::mock_get_calls()
+            .into_iter()
+            .find_map(|call| match call {
+                test_zellij::ZellijCall::OpenCommandPaneInPlaceOfPlugin { command, .. } => {
+                    Some(command)
+                }
+                _ => None,
+            })
+    };
+
+    let command = run_test(&mut plugin, "test renders").unwrap();
+    assert_eq!(command.path, PathBuf::from("cargo"));
+    assert_eq!(
+        command.args,
+        vec![
+            "test",
+            "-p",
+            "grab",
+            "ui::tests::renders_empty_state",
+            "--",
+            "--exact"
+        ]
+    );
+    assert_eq!(command.cwd, Some(PathBuf::from("/test/project")));
+
+    // A test module runs all of its tests
+    let command = run_test(&mut plugin, "test tests").unwrap();
+    assert_eq!(command.args, vec!["test", "-p", "grab", "ui::tests"]);
+
+    assert!(run_test(&mut plugin, "test render_helper").is_none());
+    assert!(run_test(&mut plugin, "fn render_helper").is_none());
+}
+
+#[test]
+fn test_visibility_prefixes_filter_on_visibility() {
+    use crate::files::{TypeDefinition, TypeKind, Visibility};
+    use crate::parse_rust_asset_search;
+    use std::rc::Rc;
+
+    let definition = |name: &str, visibility: Visibility| TypeDefinition {
+        visibility,
+        ..TypeDefinition::new(
+            TypeKind::Function,
+            name.to_owned(),
+            Rc::new(PathBuf::from("src/lib.rs")),
+            1,
+        )
+    };
+    let public = definition("parse", Visibility::Public);
+    let crate_wide = definition("parse_args", Visibility::Crate);
+    let private = definition("parse_line", Visibility::Private);
+
+    let matching = |search_term: &str| -> Vec<String> {
+        let search = parse_rust_asset_search(search_term).unwrap();
+        [&public, &crate_wide, &private]
+            .iter()
+            .filter(|definition| search.matches(definition))
+            .map(|definition| definition.name.clone())
+            .collect()
+    };
+    assert_eq!(matching("pub fn parse"), vec!["parse"]);
+    assert_eq!(matching("pub(crate) fn "), vec!["parse_args"]);
+    assert_eq!(
+        matching("fn parse"),
+        vec!["parse", "parse_args", "parse_line"]
+    );
+    assert_eq!(
+        parse_rust_asset_search("pub fn parse").unwrap().term(),
+        "parse"
+    );
+    assert!(parse_rust_asset_search("pub(crate) Search").is_none());
+    assert!(parse_rust_asset_search("public fn").is_none());
+}
+
+#[test]
+fn test_render_function_signatures() {
+    use crate::files::{TypeDefinition, TypeKind, Visibility};
+    use std::rc::Rc;
+
+    test_zellij::mock_init();
+    test_zellij::mock_set_plugin_ids(PluginIds {
+        plugin_id: 42,
+        zellij_pid: 1234,
+        initial_cwd: PathBuf::from("/home/user/project"),
+    });
+    test_zellij::mock_init_frame(80, 12);
+
+    let file_path = Rc::new(PathBuf::from("src/parser.rs"));
+    let parse = TypeDefinition {
+        visibility: Visibility::Public,
+        signature: "(line: &str) -> Item".to_owned(),
+        ..TypeDefinition::new(
+            TypeKind::Function,
+            "parse".to_owned(),
+            Rc::clone(&file_path),
+            3,
+        )
+    };
+    let parse_all = TypeDefinition {
+        name: "parse_all".to_owned(),
+        line_number: 12,
+        signature: "<I: IntoIterator<Item = String>>(lines: I, options: &ParseOptions, errors: &mut Vec<ParseError>) -> Vec<Item>".to_owned(),
+        ..parse.clone()
+    };
+
+    let mut plugin = State::default();
+    plugin
+        .app_state
+        .upsert_file((*file_path).clone(), vec![parse, parse_all]);
+    plugin.load(BTreeMap::new());
+    plugin.update(Event::PermissionRequestResult(PermissionStatus::Granted));
+    for character in "fn parse".chars() {
+        plugin.search_state.add_char(character);
+    }
+    plugin.update_search_results();
+
+    plugin.render(12, 80);
+
+    test_zellij::assert_frame_snapshot("render_function_signatures");
+}
+
+#[test]
+fn test_docs_are_previewed_and_searchable() {
+    use crate::files::{TypeDefinition, TypeKind, Visibility};
+    use std::rc::Rc;
+
+    test_zellij::mock_init();
+    test_zellij::mock_set_plugin_ids(PluginIds {
+        plugin_id: 42,
+        zellij_pid: 1234,
+        initial_cwd: PathBuf::from("/home/user/project"),
+    });
+    test_zellij::mock_init_frame(80, 14);
+
+    let file_path = Rc::new(PathBuf::from("src/parser.rs"));
+    let parse = TypeDefinition {
+        visibility: Visibility::Public,
+        signature: "(line: &str) -> Item".to_owned(),
+        docs: "Turns a line of input into an item.\n\nEmpty lines are an error.".to_owned(),
+        ..TypeDefinition::new(
+            TypeKind::Function,
+            "parse".to_owned(),
+            Rc::clone(&file_path),
+            3,
+        )
+    };
+    let tokenize = TypeDefinition {
+        name: "tokenize".to_owned(),
+        line_number: 20,
+        signature: "(line: &str) -> Tokens".to_owned(),
+        docs: String::new(),
+        ..parse.clone()
+    };
+
+    let mut plugin = State::default();
+    plugin
+        .app_state
+        .upsert_file((*file_path).clone(), vec![parse, tokenize]);
+    plugin.load(BTreeMap::new());
+    plugin.update(Event::PermissionRequestResult(PermissionStatus::Granted));
+    let search = |plugin: &mut State, term: &str| -> Vec<String> {
+        plugin.search_state.clear();
+        for character in term.chars() {
+            plugin.search_state.add_char(character);
+        }
+        plugin.update_search_results();
+        plugin
+            .search_state
+            .get_current_display_results()
+            .iter()
+            .map(|result| result.display_text())
+            .collect()
+    };
+
+    assert_eq!(
+        search(&mut plugin, "fn line into"),
+        vec!["parser::parse(line: &str) -> Item (src/parser.rs)"],
+        "Every word has to be in the docs"
+    );
+    assert!(search(&mut plugin, "fn line of").is_empty());
+    assert_eq!(search(&mut plugin, "fn LINE Into").len(), 1);
+    assert!(
+        search(&mut plugin, "line into").is_empty(),
+        "Only searches with a kind prefix look into docs"
+    );
+
+    search(&mut plugin, "fn ");
+    plugin.ui_state.set_selected_index(Some(0));
+    plugin.render(14, 80);
+
+    test_zellij::assert_frame_snapshot("render_docs_of_selected_item");
+}
+
+#[test]
+fn test_module_paths_are_shown_and_searchable() {
+    use crate::files::{TypeDefinition, TypeKind};
+    use std::rc::Rc;
+
+    let definition = |type_kind, name: &str, file: &str| {
+        TypeDefinition::new(type_kind, name.to_owned(), Rc::new(PathBuf::from(file)), 3)
+    };
+    let render = definition(TypeKind::Function, "render", "src/ui/table/mod.rs");
+    let helper = TypeDefinition {
+        module_path: Some("helpers".into()),
+        ..definition(TypeKind::Function, "pad", "src/ui/table/mod.rs")
+    };
+    let method = TypeDefinition {
+        owner: Some("Table".into()),
+        ..definition(TypeKind::Function, "draw", "src/ui/table/mod.rs")
+    };
+    let sys = TypeDefinition {
+        path_attribute: Some("os/unix.rs".to_owned()),
+        ..definition(TypeKind::Module, "sys", "src/lib.rs")
+    };
+    let open = definition(TypeKind::Function, "open", "src/os/unix.rs");
+
+    let mut plugin = setup();
+    plugin.load(BTreeMap::new());
+    // The file a `#[path]` points to may be indexed before its declaration
+    plugin
+        .app_state
+        .upsert_file(PathBuf::from("src/os/unix.rs"), vec![open]);
+    plugin.app_state.upsert_file(
+        PathBuf::from("src/ui/table/mod.rs"),
+        vec![render, helper, method],
+    );
+    plugin
+        .app_state
+        .upsert_file(PathBuf::from("src/lib.rs"), vec![sys]);
+
+    let search = |plugin: &mut State, term: &str| -> Vec<String> {
+        plugin.search_state.clear();
+        for character in term.chars() {
+            plugin.search_state.add_char(character);
+        }
+        plugin.update_search_results();
+        plugin
+            .search_state
+            .get_rust_asset_display_results()
+            .iter()
+            .map(|result| result.display_text())
+            .collect()
+    };
+    assert_eq!(
+        search(&mut plugin, "fn render"),
+        vec!["ui::table::render (src/ui/table/mod.rs)"]
+    );
+    assert_eq!(
+        search(&mut plugin, "fn table::helpers::pad"),
+        vec!["ui::table::helpers::pad (src/ui/table/mod.rs)"]
+    );
+    assert_eq!(
+        search(&mut plugin, "fn crate::ui::table::render"),
+        vec!["ui::table::render (src/ui/table/mod.rs)"]
+    );
+    assert_eq!(
+        search(&mut plugin, "fn ::draw"),
+        vec!["ui::table::Table::draw (src/ui/table/mod.rs)"],
+        "A leading separator only matches methods"
+    );
+    assert_eq!(
+        search(&mut plugin, "fn sys::open"),
+        vec!["sys::open (src/os/unix.rs)"]
+    );
+}
+
+#[test]
+fn test_configured_editor_opens_symbols_at_their_column() {
+    use crate::files::{TypeDefinition, TypeKind};
+    use std::rc::Rc;
+
+    let definition = TypeDefinition {
+        column: 9,
+        char_column: 8,
+        ..TypeDefinition::new(
+            TypeKind::Function,
+            "naïve_split".to_owned(),
+            Rc::new(PathBuf::from("src/text.rs")),
+            12,
+        )
+    };
+
+    let open = |editor: Option<&str>, definition: &TypeDefinition| -> test_zellij::ZellijCall {
+        let mut plugin = setup();
+        let configuration = editor
+            .map(|editor| ("editor".to_owned(), editor.to_owned()))
+            .into_iter()
+            .collect();
+        plugin.load(configuration);
+        plugin.app_state.set_cwd(PathBuf::from("/test/project"));
+        plugin
+            .app_state
+            .upsert_file(PathBuf::from("src/text.rs"), vec![definition.clone()]);
+        for character in "fn naïve".chars() {
+            plugin.search_state.add_char(character);
+        }
+        plugin.update_search_results();
+        test_zellij::mock_clear_calls();
+        plugin.update(Event::Key(Key {
+            bare_key: BareKey::Enter,
+            modifiers: vec![],
+        }));
+        test_zellij::mock_get_calls()
+            .into_iter()
+            .find(|call| {
+                matches!(
+                    call,
+                    test_zellij::ZellijCall::OpenFileInPlaceOfPlugin { .. }
+                        | test_zellij::ZellijCall::OpenCommandPaneInPlaceOfPlugin { .. }
+                )
+            })
+            .unwrap()
+    };
+    let command_args = |call: test_zellij::ZellijCall| match call {
+        test_zellij::ZellijCall::OpenCommandPaneInPlaceOfPlugin { command, .. } => {
+            let mut args = vec![command.path.display().to_string()];
+            args.extend(command.args);
+            args
+        }
+        call => panic!("Expected a command pane, got {:?}", call),
+    };
+
+    // Vim counts columns in bytes, Helix in chars
+    assert_eq!(
+        command_args(open(Some("/usr/bin/nvim"), &definition)),
+        vec![
+            "/usr/bin/nvim",
+            "+call cursor(12, 9)",
+            "/test/project/src/text.rs"
+        ]
+    );
+    assert_eq!(
+        command_args(open(Some("hx"), &definition)),
+        vec!["hx", "/test/project/src/text.rs:12:8"]
+    );
+
+    // Without an editor, or a column, Zellij opens the line in $EDITOR
+    assert!(matches!(
+        open(None, &definition),
+        test_zellij::ZellijCall::OpenFileInPlaceOfPlugin {
+            line_number: Some(12),
+            ..
+        }
+    ));
+    let without_column = TypeDefinition {
+        column: 0,
+        char_column: 0,
+        ..definition.clone()
+    };
+    assert!(matches!(
+        open(Some("hx"), &without_column),
+        test_zellij::ZellijCall::OpenFileInPlaceOfPlugin { .. }
+    ));
+}
@@ -6,3 +6,33 @@ pub mod test_zellij;
 
 #[cfg(test)]
 mod main_tests;
+
+#[cfg(test)]
+mod ignore_tests;
+
+#[cfg(test)]
+mod files_tests;
+
+#[cfg(test)]
+mod worker_tests;
+
+#[cfg(test)]
+mod cache_tests;
+
+#[cfg(test)]
+mod workspace_tests;
+
+#[cfg(test)]
+mod config_tests;
+
+#[cfg(test)]
+mod roots_tests;
+
+#[cfg(test)]
+mod extractors_tests;
+
+#[cfg(test)]
+mod ctags_tests;
+
+#[cfg(test)]
+mod symbol_index_tests;
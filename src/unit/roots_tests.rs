@@ -0,0 +1,70 @@
+use crate::roots::{distinct_roots, mount_for, project_roots, roots_near_folder};
+use std::path::{Path, PathBuf};
+
+fn paths(paths: &[&str]) -> Vec<PathBuf> {
+    paths.iter().map(PathBuf::from).collect()
+}
+
+#[test]
+fn test_roots_inside_or_next_to_the_current_folder_are_indexed() {
+    let (roots, outside) = roots_near_folder(
+        Path::new("/home/user/work/api"),
+        paths(&[
+            "/home/user/work/api/vendor",
+            "/home/user/work/api",
+            "/home/user/work/web",
+            "/home/user/work/api/../sdk",
+            "/home/user/work",
+            "/home/user/other",
+            "/srv/sdk",
+        ]),
+    );
+    assert_eq!(
+        roots,
+        paths(&[
+            "/home/user/work/api",
+            "/home/user/work/api/vendor",
+            "/home/user/work/web",
+            "/home/user/work/sdk"
+        ])
+    );
+    assert_eq!(
+        outside,
+        paths(&["/home/user/work", "/home/user/other", "/srv/sdk"]),
+        "Folders holding the current one or further away are reported"
+    );
+    assert_eq!(mount_for(&roots), Some(PathBuf::from("/home/user/work")));
+    assert_eq!(
+        mount_for(&roots[..2]),
+        Some(PathBuf::from("/home/user/work/api")),
+        "Nested roots don't need another folder"
+    );
+
+    // The home folder or anything above it is never mounted
+    let (roots, outside) =
+        roots_near_folder(Path::new("/home/user/api"), paths(&["/home/user/web"]));
+    assert_eq!(roots, paths(&["/home/user/api"]));
+    assert_eq!(outside, paths(&["/home/user/web"]));
+
+    assert_eq!(
+        distinct_roots(paths(&[
+            "/work/api",
+            "/work/api/../web",
+            "/work/api/vendor",
+            "/work/web/"
+        ])),
+        paths(&["/work/api", "/work/web"]),
+        "Duplicates and nested roots are dropped"
+    );
+}
+
+#[test]
+fn test_root_labels_are_disambiguated() {
+    let roots = project_roots(
+        &paths(&["/work/api", "/work/old/api", "/work/web"]),
+        Path::new("/work"),
+    );
+    let labels: Vec<&str> = roots.iter().map(|root| root.label.as_ref()).collect();
+    assert_eq!(labels, vec!["api", "old/api", "web"]);
+    assert_eq!(roots[1].dir, PathBuf::from("old/api"));
+}
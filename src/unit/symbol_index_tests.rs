@@ -0,0 +1,292 @@
+use crate::app_state::AppState;
+use crate::files::{SymbolSource, TypeDefinition, TypeKind, Visibility};
+use crate::symbol_index::{load_symbol_index, parse_symbol_index};
+use crate::unit::fixtures::TempProject;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, SystemTime};
+
+// Protobuf encoding, enough to write SCIP messages by hand
+fn varint(mut value: u64, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn bytes_field(field: u64, bytes: &[u8], out: &mut Vec<u8>) {
+    varint(field << 3 | 2, out);
+    varint(bytes.len() as u64, out);
+    out.extend_from_slice(bytes);
+}
+
+fn varint_field(field: u64, value: u64, out: &mut Vec<u8>) {
+    varint(field << 3, out);
+    varint(value, out);
+}
+
+fn occurrence(range: &[u64], symbol: &str, roles: u64) -> Vec<u8> {
+    let mut packed = Vec::new();
+    for number in range {
+        varint(*number, &mut packed);
+    }
+    let mut occurrence = Vec::new();
+    bytes_field(1, &packed, &mut occurrence);
+    bytes_field(2, symbol.as_bytes(), &mut occurrence);
+    varint_field(3, roles, &mut occurrence);
+    occurrence
+}
+
+const SEARCH: &str = "rust-analyzer cargo grab 0.1.0 search/impl#[SearchEngine]search().";
+const CONFIG: &str = "rust-analyzer cargo grab 0.1.0 config/Config#";
+const CONFIG_PATH: &str = "rust-analyzer cargo grab 0.1.0 config/Config#path.";
+const RAW: &str = "rust-analyzer cargo grab 0.1.0 config/`r#type`().";
+
+fn scip_index() -> Vec<u8> {
+    let mut search = Vec::new();
+    bytes_field(1, SEARCH.as_bytes(), &mut search);
+    bytes_field(3, b"Finds every match of `query`.", &mut search);
+    varint_field(5, 26, &mut search); // Method
+    let mut signature = Vec::new();
+    bytes_field(
+        5,
+        b"pub fn search(&self,\n    query: &str) -> Vec<Match>",
+        &mut signature,
+    );
+    bytes_field(7, &signature, &mut search);
+
+    let mut document = Vec::new();
+    bytes_field(1, b"src/search.rs", &mut document);
+    bytes_field(2, &occurrence(&[4, 11, 17], SEARCH, 1), &mut document);
+    // A reference, and a local variable
+    bytes_field(2, &occurrence(&[9, 4, 10], SEARCH, 0), &mut document);
+    bytes_field(2, &occurrence(&[5, 8, 13], "local 3", 1), &mut document);
+    bytes_field(3, &search, &mut document);
+
+    // No `SymbolInformation`, so kinds come from the symbols
+    let mut config = Vec::new();
+    bytes_field(1, b"src/config.rs", &mut config);
+    bytes_field(
+        2,
+        &occurrence(&[20, 4, 20, 10], CONFIG_PATH, 1),
+        &mut config,
+    );
+    bytes_field(2, &occurrence(&[2, 11, 17], CONFIG, 1), &mut config);
+    bytes_field(2, &occurrence(&[30, 7, 13], RAW, 1), &mut config);
+
+    let mut index = Vec::new();
+    bytes_field(1, b"", &mut index); // Metadata
+    bytes_field(2, &document, &mut index);
+    bytes_field(2, &config, &mut index);
+    index
+}
+
+#[test]
+fn test_scip_definitions_are_read() {
+    let index = parse_symbol_index(&scip_index(), Path::new("backend"));
+
+    let files: Vec<&PathBuf> = index.keys().collect();
+    assert_eq!(
+        files,
+        vec![
+            Path::new("backend/src/config.rs"),
+            Path::new("backend/src/search.rs")
+        ]
+    );
+
+    let search = &index[Path::new("backend/src/search.rs")];
+    assert_eq!(search.len(), 1, "References and locals are left out");
+    assert_eq!(search[0].type_kind, TypeKind::Function);
+    assert_eq!(search[0].name, "search");
+    assert_eq!(search[0].owner.as_deref(), Some("SearchEngine"));
+    assert_eq!(search[0].module_path.as_deref(), Some("search"));
+    assert_eq!(search[0].qualified_name(), "search::SearchEngine::search");
+    assert_eq!((search[0].line_number, search[0].column), (5, 12));
+    assert_eq!(search[0].signature, "(&self, query: &str) -> Vec<Match>");
+    assert_eq!(search[0].docs, "Finds every match of `query`.");
+    assert_eq!(search[0].source, SymbolSource::Index);
+
+    let config: Vec<(TypeKind, &str, Option<&str>, usize)> = index
+        [Path::new("backend/src/config.rs")]
+    .iter()
+    .map(|definition| {
+        (
+            definition.type_kind.clone(),
+            definition.name.as_str(),
+            definition.owner.as_deref(),
+            definition.line_number,
+        )
+    })
+    .collect();
+    assert_eq!(
+        config,
+        vec![
+            (TypeKind::Struct, "Config", None, 3),
+            (TypeKind::Field, "path", Some("Config"), 21),
+            (TypeKind::Function, "r#type", None, 31),
+        ],
+        "Sorted by line, with escaped names unescaped"
+    );
+}
+
+#[test]
+fn test_truncated_scip_index_keeps_complete_documents() {
+    let mut index = scip_index();
+    let complete = parse_symbol_index(&index, Path::new(""));
+    index.extend_from_slice(&[0x12, 0x40, 0x0a]); // A document cut short
+    assert_eq!(
+        parse_symbol_index(&index, Path::new("")).len(),
+        complete.len()
+    );
+}
+
+const LSIF: &str = r#"{"id":1,"type":"vertex","label":"metaData","version":"0.6.0","projectRoot":"file:///home/me/my%20project","positionEncoding":"utf-16"}
+{"id":2,"type":"vertex","label":"document","uri":"file:///home/me/my%20project/src/ui/table.rs","languageId":"rust"}
+{"id":3,"type":"vertex","label":"range","start":{"line":6,"character":11},"end":{"line":6,"character":16}}
+{"id":4,"type":"vertex","label":"resultSet"}
+{"id":5,"type":"edge","label":"next","outV":3,"inV":4}
+{"id":6,"type":"vertex","label":"moniker","scheme":"rust-analyzer","identifier":"grab::ui::table::Table","kind":"export"}
+{"id":7,"type":"edge","label":"moniker","outV":4,"inV":6}
+{"id":8,"type":"vertex","label":"hoverResult","result":{"contents":{"kind":"markdown","value":"```rust\ngrab::ui::table\n```\n\n```rust\npub struct Table<'a>\n```\n\n---\n\nRows and columns."}}}
+{"id":9,"type":"edge","label":"textDocument/hover","outV":4,"inV":8}
+{"id":10,"type":"vertex","label":"definitionResult"}
+{"id":11,"type":"edge","label":"textDocument/definition","outV":4,"inV":10}
+{"id":12,"type":"edge","label":"item","outV":10,"inVs":[3],"document":2}
+{"id":13,"type":"vertex","label":"range","start":{"line":1,"character":0},"end":{"line":1,"character":4},"tag":{"type":"definition","text":"main","kind":12}}
+{"id":14,"type":"vertex","label":"range","start":{"line":9,"character":4},"end":{"line":9,"character":9}}
+{"id":15,"type":"edge","label":"next","outV":14,"inV":4}
+{"id":16,"type":"edge","label":"contains","outV":2,"inVs":[3,13,14]}
+"#;
+
+#[test]
+fn test_lsif_definitions_are_read() {
+    let index = parse_symbol_index(LSIF.as_bytes(), Path::new(""));
+
+    let table = &index[Path::new("src/ui/table.rs")];
+    assert_eq!(table.len(), 2, "The reference at line 10 is left out");
+    assert_eq!(table[0].type_kind, TypeKind::Function);
+    assert_eq!(table[0].name, "main");
+    assert_eq!(table[1].type_kind, TypeKind::Struct);
+    assert_eq!(table[1].name, "Table");
+    assert_eq!(table[1].qualified_name(), "ui::table::Table");
+    assert_eq!((table[1].line_number, table[1].char_column), (7, 12));
+    assert_eq!(table[1].signature, "<'a>");
+    assert_eq!(table[1].docs, "Rows and columns.");
+
+    // The same dump as a single array
+    let array = format!("[{}]", LSIF.trim().replace('\n', ","));
+    let names = |definitions: &[TypeDefinition]| -> Vec<String> {
+        definitions
+            .iter()
+            .map(|definition| definition.qualified_name())
+            .collect()
+    };
+    let from_array = parse_symbol_index(array.as_bytes(), Path::new(""));
+    assert_eq!(
+        names(&from_array[Path::new("src/ui/table.rs")]),
+        names(table)
+    );
+}
+
+fn scanned(name: &str, file_path: &str) -> TypeDefinition {
+    TypeDefinition {
+        visibility: Visibility::Public,
+        ..TypeDefinition::new(
+            TypeKind::Function,
+            name.to_owned(),
+            Rc::new(PathBuf::from(file_path)),
+            1,
+        )
+    }
+}
+
+#[test]
+fn test_index_symbols_are_preferred_over_scanned_ones() {
+    let index = parse_symbol_index(&scip_index(), Path::new(""));
+
+    let mut app_state = AppState::default();
+    app_state.upsert_file(
+        PathBuf::from("src/search.rs"),
+        vec![scanned("search_heuristically", "src/search.rs")],
+    );
+    app_state.import_index(Path::new(""), index);
+    // Indexed after the import
+    app_state.upsert_file(
+        PathBuf::from("src/config.rs"),
+        vec![scanned("Config", "src/config.rs")],
+    );
+    app_state.upsert_file(
+        PathBuf::from("src/main.rs"),
+        vec![scanned("main", "src/main.rs")],
+    );
+
+    let mut names: Vec<String> = app_state
+        .get_rust_assets()
+        .into_iter()
+        .map(|definition| definition.qualified_name())
+        .collect();
+    names.sort();
+    assert_eq!(
+        names,
+        vec![
+            "config::Config",
+            "config::Config::path",
+            "config::r#type",
+            "main",
+            "search::SearchEngine::search"
+        ],
+        "Index module paths aren't prefixed with the file's module"
+    );
+
+    // Edited files go back to the scanner
+    app_state.discard_index_symbols(Path::new("src/search.rs"));
+    app_state.upsert_file(
+        PathBuf::from("src/search.rs"),
+        vec![scanned("search_heuristically", "src/search.rs")],
+    );
+    assert!(app_state
+        .get_rust_assets()
+        .iter()
+        .any(|definition| definition.name == "search_heuristically"));
+}
+
+#[test]
+fn test_files_modified_after_the_index_are_left_to_the_scanner() {
+    let project = TempProject::new();
+    project.write("dump.lsif", LSIF);
+    project.write("src/ui/table.rs", "pub struct Table;\n");
+    let set_modified = |file: &str, modified: SystemTime| {
+        File::options()
+            .write(true)
+            .open(project.path().join(file))
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    };
+    let indexed_at = SystemTime::now() - Duration::from_secs(60);
+    set_modified("dump.lsif", indexed_at);
+
+    set_modified("src/ui/table.rs", indexed_at - Duration::from_secs(10));
+    let index = load_symbol_index(project.path(), Path::new(""), None);
+    assert!(index.contains_key(Path::new("src/ui/table.rs")));
+
+    set_modified("src/ui/table.rs", indexed_at + Duration::from_secs(10));
+    let index = load_symbol_index(project.path(), Path::new(""), None);
+    assert!(!index.contains_key(Path::new("src/ui/table.rs")));
+
+    // What the scanner found is kept once the index is imported
+    let mut app_state = AppState::default();
+    app_state.upsert_file(
+        PathBuf::from("src/ui/table.rs"),
+        vec![scanned("Table", "src/ui/table.rs")],
+    );
+    app_state.import_index(Path::new(""), index);
+    let sources: Vec<SymbolSource> = app_state
+        .get_rust_assets()
+        .into_iter()
+        .map(|definition| definition.source)
+        .collect();
+    assert_eq!(sources, vec![SymbolSource::Scanner]);
+}
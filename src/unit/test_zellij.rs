@@ -48,6 +48,25 @@ pub enum ZellijCall {
         line_number: Option<usize>,
         close_plugin: bool,
     },
+    WatchFilesystem,
+    PostMessageTo {
+        worker_name: Option<String>,
+        name: String,
+        payload: String,
+    },
+    PostMessageToPlugin {
+        name: String,
+        payload: String,
+    },
+    RunCommand {
+        command: Vec<String>,
+        cwd: PathBuf,
+        context: BTreeMap<String, String>,
+    },
+    OpenCommandPaneInPlaceOfPlugin {
+        command: CommandToRun,
+        close_plugin: bool,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -193,6 +212,7 @@ pub enum EventType {
     FileSystemDelete,
     PermissionRequestResult,
     HostFolderChanged,
+    RunCommandResult,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -211,6 +231,18 @@ pub enum Event {
     PermissionRequestResult(PermissionStatus),
     SessionUpdate(Vec<SessionInfo>, Vec<SessionInfo>),
     HostFolderChanged(PathBuf),
+    FileSystemCreate(Vec<(PathBuf, Option<FileMetadata>)>),
+    FileSystemUpdate(Vec<(PathBuf, Option<FileMetadata>)>),
+    FileSystemDelete(Vec<(PathBuf, Option<FileMetadata>)>),
+    RunCommandResult(Option<i32>, Vec<u8>, Vec<u8>, BTreeMap<String, String>),
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FileMetadata {
+    pub is_dir: bool,
+    pub is_file: bool,
+    pub is_symlink: bool,
+    pub len: u64,
 }
 
 #[derive(Debug, Clone, PartialEq, Default)]
@@ -350,6 +382,13 @@ impl FileToOpen {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandToRun {
+    pub path: PathBuf,
+    pub args: Vec<String>,
+    pub cwd: Option<PathBuf>,
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct FloatingPaneCoordinates {}
 
@@ -453,6 +492,31 @@ pub enum PipeSource {
     Keybind,
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct PluginMessage {
+    pub name: String,
+    pub payload: String,
+    pub worker_name: Option<String>,
+}
+
+impl PluginMessage {
+    pub fn new_to_worker(worker_name: &str, message: &str, payload: &str) -> Self {
+        PluginMessage {
+            name: message.to_owned(),
+            payload: payload.to_owned(),
+            worker_name: Some(worker_name.to_owned()),
+        }
+    }
+
+    pub fn new_to_plugin(message: &str, payload: &str) -> Self {
+        PluginMessage {
+            name: message.to_owned(),
+            payload: payload.to_owned(),
+            worker_name: None,
+        }
+    }
+}
+
 // =============================================================================
 // FRAME STRUCTURE FOR SNAPSHOT TESTING
 // =============================================================================
@@ -764,6 +828,65 @@ pub fn open_file_in_place_of_plugin(
     });
 }
 
+pub fn open_command_pane_in_place_of_plugin(
+    command_to_run: CommandToRun,
+    close_plugin_after_replace: bool,
+    _context: BTreeMap<String, String>,
+) {
+    MOCK_STATE.with(|state| {
+        state
+            .borrow_mut()
+            .calls
+            .push(ZellijCall::OpenCommandPaneInPlaceOfPlugin {
+                command: command_to_run,
+                close_plugin: close_plugin_after_replace,
+            });
+    });
+}
+
+pub fn watch_filesystem() {
+    MOCK_STATE.with(|state| {
+        state.borrow_mut().calls.push(ZellijCall::WatchFilesystem);
+    });
+}
+
+pub fn post_message_to(plugin_message: PluginMessage) {
+    MOCK_STATE.with(|state| {
+        state.borrow_mut().calls.push(ZellijCall::PostMessageTo {
+            worker_name: plugin_message.worker_name,
+            name: plugin_message.name,
+            payload: plugin_message.payload,
+        });
+    });
+}
+
+pub fn post_message_to_plugin(plugin_message: PluginMessage) {
+    MOCK_STATE.with(|state| {
+        state
+            .borrow_mut()
+            .calls
+            .push(ZellijCall::PostMessageToPlugin {
+                name: plugin_message.name,
+                payload: plugin_message.payload,
+            });
+    });
+}
+
+pub fn run_command_with_env_variables_and_cwd(
+    cmd: &[&str],
+    _env_variables: BTreeMap<String, String>,
+    cwd: PathBuf,
+    context: BTreeMap<String, String>,
+) {
+    MOCK_STATE.with(|state| {
+        state.borrow_mut().calls.push(ZellijCall::RunCommand {
+            command: cmd.iter().map(|arg| arg.to_string()).collect(),
+            cwd,
+            context,
+        });
+    });
+}
+
 pub fn print_text_with_coordinates(
     text: Text,
     x: usize,
@@ -824,6 +947,12 @@ pub trait ZellijPlugin: Default {
     }
 }
 
+pub trait ZellijWorker<'de>: Default {
+    fn on_message(&mut self, message: String, payload: String) {
+        let _ = (message, payload);
+    }
+}
+
 // Plugin registration macro
 #[macro_export]
 macro_rules! register_plugin {
@@ -831,3 +960,16 @@ macro_rules! register_plugin {
         // In tests, this is a no-op since we'll instantiate directly
     };
 }
+
+// Worker registration macro
+#[macro_export]
+macro_rules! register_worker {
+    ($worker:ty, $worker_name:ident, $worker_static_name:ident) => {
+        // In tests, workers are driven directly through `on_message`
+        thread_local! {
+            #[allow(dead_code)]
+            static $worker_static_name: std::cell::RefCell<$worker> =
+                std::cell::RefCell::new(Default::default());
+        }
+    };
+}
@@ -0,0 +1,237 @@
+use crate::config::ScanConfig;
+use crate::unit::fixtures::TempProject;
+use crate::unit::test_zellij::{self, ZellijCall, ZellijWorker};
+use crate::worker::{
+    ImportRequest, ImportedSymbols, IndexBatch, IndexRequest, IndexWorker, CONTINUE_INDEXING,
+    DIRECTORY_BATCH, INDEX_BATCH, INDEX_DIRECTORY, LOAD_TAGS, START_INDEXING, TAGS_LOADED,
+};
+use std::path::PathBuf;
+
+fn sent_batches() -> Vec<IndexBatch> {
+    sent_messages(INDEX_BATCH)
+}
+
+fn sent_messages(message: &str) -> Vec<IndexBatch> {
+    test_zellij::mock_get_calls()
+        .into_iter()
+        .filter_map(|call| match call {
+            ZellijCall::PostMessageToPlugin { name, payload } if name == message => {
+                serde_json::from_str(&payload).ok()
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_worker_streams_batches_until_done() {
+    test_zellij::mock_init();
+    let project = TempProject::new();
+    for i in 0..1200 {
+        project.write(&format!("src/file_{}.txt", i), "");
+    }
+    project.write("src/lib.rs", "pub struct Indexed;\n");
+
+    let mut worker = IndexWorker::default();
+    let request = IndexRequest {
+        generation: 3,
+        root: project.path().to_path_buf(),
+        config: ScanConfig {
+            file_limit: 10_000,
+            ..Default::default()
+        },
+        repository_root: project.path().to_path_buf(),
+        cache_dir: None,
+        files: None,
+        subdirectory: PathBuf::new(),
+        directory: None,
+    };
+    worker.on_message(
+        START_INDEXING.to_owned(),
+        serde_json::to_string(&request).unwrap(),
+    );
+
+    // The worker only sends the next batch once the plugin asks for it
+    assert_eq!(sent_batches().len(), 1);
+    while !sent_batches().last().unwrap().done {
+        worker.on_message(CONTINUE_INDEXING.to_owned(), "3".to_owned());
+    }
+
+    let batches = sent_batches();
+    assert!(batches.len() > 1, "Large trees should take several batches");
+    assert!(batches.iter().all(|batch| batch.generation == 3));
+    assert_eq!(batches.last().unwrap().indexed_count, 1201);
+
+    let files_and_rust_assets = batches
+        .into_iter()
+        .flat_map(|batch| batch.into_files_and_rust_assets())
+        .collect::<Vec<_>>();
+    let (_, definitions) = files_and_rust_assets
+        .iter()
+        .find(|(path, _)| path.ends_with("src/lib.rs"))
+        .expect("lib.rs should be indexed");
+    assert_eq!(definitions[0].name, "Indexed");
+    assert!(definitions[0].file_path.ends_with("src/lib.rs"));
+}
+
+#[test]
+fn test_worker_ignores_continue_for_outdated_scan() {
+    test_zellij::mock_init();
+    let project = TempProject::new();
+    project.write("a.txt", "");
+
+    let mut worker = IndexWorker::default();
+    let request = IndexRequest {
+        generation: 2,
+        root: PathBuf::from(project.path()),
+        config: ScanConfig {
+            file_limit: 10_000,
+            ..Default::default()
+        },
+        repository_root: project.path().to_path_buf(),
+        cache_dir: None,
+        files: None,
+        subdirectory: PathBuf::new(),
+        directory: None,
+    };
+    worker.on_message(
+        START_INDEXING.to_owned(),
+        serde_json::to_string(&request).unwrap(),
+    );
+    test_zellij::mock_clear_calls();
+
+    worker.on_message(CONTINUE_INDEXING.to_owned(), "1".to_owned());
+
+    assert!(sent_batches().is_empty());
+}
+
+#[test]
+fn test_worker_indexes_a_new_directory_within_the_file_limit() {
+    test_zellij::mock_init();
+    let project = TempProject::new();
+    project.write("src/lib.rs", "pub struct Indexed;\n");
+    project.write("new/a.rs", "pub struct A;\n");
+    project.write("new/b.rs", "pub struct B;\n");
+    project.write("new/c.rs", "pub struct C;\n");
+
+    let mut worker = IndexWorker::default();
+    let request = IndexRequest {
+        generation: 4,
+        root: project.path().to_path_buf(),
+        config: ScanConfig {
+            file_limit: 2,
+            ..Default::default()
+        },
+        repository_root: project.path().to_path_buf(),
+        cache_dir: None,
+        files: None,
+        subdirectory: PathBuf::new(),
+        directory: Some(PathBuf::from("new")),
+    };
+    worker.on_message(
+        INDEX_DIRECTORY.to_owned(),
+        serde_json::to_string(&request).unwrap(),
+    );
+
+    // The whole folder is sent without waiting for the plugin
+    let batches = sent_messages(DIRECTORY_BATCH);
+    assert!(sent_batches().is_empty());
+    let last = batches.last().expect("the folder should be sent");
+    assert!(last.done);
+    assert_eq!(last.truncated_at, Some(2));
+    let files = batches
+        .into_iter()
+        .flat_map(|batch| batch.into_files_and_rust_assets())
+        .map(|(path, _)| path)
+        .collect::<Vec<_>>();
+    assert_eq!(files.len(), 2);
+    assert!(files
+        .iter()
+        .all(|file| file.starts_with(project.path().join("new"))));
+}
+
+#[test]
+fn test_worker_reads_the_tags_file_of_a_root() {
+    test_zellij::mock_init();
+    let project = TempProject::new();
+    project.write(
+        "backend/tags",
+        "Parser\tsrc/parser.c\t/^struct Parser {$/;\"\tkind:struct\tline:12\n",
+    );
+
+    let mut worker = IndexWorker::default();
+    let request = ImportRequest {
+        generation: 5,
+        host: project.path().to_path_buf(),
+        root_dir: PathBuf::from("backend"),
+        index_file: None,
+    };
+    worker.on_message(
+        LOAD_TAGS.to_owned(),
+        serde_json::to_string(&request).unwrap(),
+    );
+
+    let imported = test_zellij::mock_get_calls()
+        .into_iter()
+        .find_map(|call| match call {
+            ZellijCall::PostMessageToPlugin { name, payload } if name == TAGS_LOADED => {
+                serde_json::from_str::<ImportedSymbols>(&payload).ok()
+            }
+            _ => None,
+        })
+        .expect("the tags should be sent back");
+    assert_eq!(imported.generation, 5);
+    assert_eq!(imported.root_dir, PathBuf::from("backend"));
+    let symbols = imported.into_symbols();
+    let definitions = &symbols[&PathBuf::from("backend/src/parser.c")];
+    assert_eq!(definitions[0].name, "Parser");
+    assert_eq!(
+        *definitions[0].file_path,
+        PathBuf::from("backend/src/parser.c")
+    );
+}
+
+#[test]
+fn test_cached_files_are_sent_in_batches() {
+    test_zellij::mock_init();
+    let project = TempProject::new();
+    let cache_dir = TempProject::new();
+    for i in 0..1200 {
+        project.write(&format!("src/file_{}.txt", i), "");
+    }
+    let request = IndexRequest {
+        generation: 1,
+        root: project.path().to_path_buf(),
+        config: ScanConfig::default(),
+        repository_root: project.path().to_path_buf(),
+        cache_dir: Some(cache_dir.path().to_path_buf()),
+        files: None,
+        subdirectory: PathBuf::new(),
+        directory: None,
+    };
+    let index = |worker: &mut IndexWorker| {
+        test_zellij::mock_clear_calls();
+        worker.on_message(
+            START_INDEXING.to_owned(),
+            serde_json::to_string(&request).unwrap(),
+        );
+        while !sent_batches().last().unwrap().done {
+            worker.on_message(CONTINUE_INDEXING.to_owned(), "1".to_owned());
+        }
+        sent_batches()
+    };
+    index(&mut IndexWorker::default());
+
+    let batches = index(&mut IndexWorker::default());
+    let cached: Vec<&IndexBatch> = batches
+        .iter()
+        .take_while(|batch| batch.indexed_count == 0)
+        .collect();
+    assert_eq!(cached.len(), 3, "The cache is shown a batch at a time");
+    assert!(cached
+        .iter()
+        .all(|batch| batch.files.len() <= crate::files::INDEX_BATCH_SIZE));
+    let cached_count: usize = cached.iter().map(|batch| batch.files.len()).sum();
+    assert_eq!(cached_count, 1200);
+    assert_eq!(batches.last().unwrap().indexed_count, 1200);
+}
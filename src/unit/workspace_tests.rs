@@ -0,0 +1,86 @@
+use crate::unit::fixtures::TempProject;
+use crate::workspace::{crate_name_matches, file_module_path, CargoWorkspace};
+use std::path::Path;
+
+fn workspace_project() -> TempProject {
+    let project = TempProject::new();
+    project.write(
+        "Cargo.toml",
+        "[package]\nname = \"app\" # the binary\n\n[workspace]\nmembers = [\n    \"crates/*\",\n    \"tools/xtask\",\n]\nexclude = [\"crates/scratch\"]\n",
+    );
+    project.write("crates/core/Cargo.toml", "[package]\nname = \"app-core\"\n");
+    project.write("crates/ui/Cargo.toml", "[package]\nname = 'app_ui'\n");
+    project.write(
+        "crates/scratch/Cargo.toml",
+        "[package]\nname = \"scratch\"\n",
+    );
+    project.write("crates/notes/README.md", "");
+    project.write("tools/xtask/Cargo.toml", "[package]\nname = \"xtask\"\n");
+    project
+}
+
+#[test]
+fn test_workspace_members_are_expanded_and_own_their_files() {
+    let project = workspace_project();
+    let workspace = CargoWorkspace::load(project.path());
+
+    let crate_for = |file: &str| workspace.crate_for(Path::new(file)).map(|c| c.to_string());
+
+    assert_eq!(
+        crate_for("crates/core/src/lib.rs").as_deref(),
+        Some("app-core")
+    );
+    assert_eq!(
+        crate_for("crates/ui/src/view.rs").as_deref(),
+        Some("app_ui")
+    );
+    assert_eq!(
+        crate_for("tools/xtask/src/main.rs").as_deref(),
+        Some("xtask")
+    );
+    // Excluded members and folders without a manifest belong to the root package
+    assert_eq!(
+        crate_for("crates/scratch/src/lib.rs").as_deref(),
+        Some("app")
+    );
+    assert_eq!(crate_for("crates/notes/README.md").as_deref(), Some("app"));
+    assert_eq!(crate_for("src/main.rs").as_deref(), Some("app"));
+}
+
+#[test]
+fn test_virtual_manifest_leaves_files_outside_members_untagged() {
+    let project = TempProject::new();
+    project.write("Cargo.toml", "[workspace]\nmembers = [\"grab-core\"]\n");
+    project.write("grab-core/Cargo.toml", "[package]\nname = \"grab-core\"\n");
+
+    let workspace = CargoWorkspace::load(project.path());
+
+    assert!(workspace
+        .crate_for(Path::new("grab-core/src/lib.rs"))
+        .is_some());
+    assert!(workspace.crate_for(Path::new("README.md")).is_none());
+}
+
+#[test]
+fn test_crate_name_matches_ignores_dash_underscore_differences() {
+    assert!(crate_name_matches("grab-core", "grab_core"));
+    assert!(crate_name_matches("grab_core", "grab_core"));
+    assert!(!crate_name_matches("grab-core", "grab"));
+}
+
+#[test]
+fn test_file_module_path_follows_the_default_layout() {
+    let module_path = |file: &str| file_module_path(Path::new(file)).join("::");
+
+    assert_eq!(module_path("src/lib.rs"), "");
+    assert_eq!(module_path("src/main.rs"), "");
+    assert_eq!(module_path("src/ui.rs"), "ui");
+    assert_eq!(module_path("src/unit/mod.rs"), "unit");
+    assert_eq!(module_path("src/unit/files_tests.rs"), "unit::files_tests");
+    assert_eq!(module_path("src/bin/xtask.rs"), "");
+    assert_eq!(module_path("src/bin/xtask/main.rs"), "");
+    assert_eq!(module_path("src/bin/xtask/cli.rs"), "cli");
+    assert_eq!(module_path("tests/search.rs"), "");
+    assert_eq!(module_path("tests/cli/main.rs"), "");
+    assert_eq!(module_path("tests/cli/helpers.rs"), "helpers");
+}
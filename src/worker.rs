@@ -0,0 +1,314 @@
+#[cfg(test)]
+use crate::unit::test_zellij::prelude::*;
+#[cfg(not(test))]
+use zellij_tile::prelude::*;
+
+use crate::cache::IndexCache;
+use crate::config::ScanConfig;
+use crate::ctags::load_tags;
+use crate::files::{FileIndexer, TypeDefinition, INDEX_BATCH_SIZE};
+use crate::symbol_index::load_symbol_index;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+pub const INDEX_WORKER_NAME: &str = "index";
+
+// Plugin -> worker
+pub const START_INDEXING: &str = "start_indexing";
+pub const CONTINUE_INDEXING: &str = "continue_indexing";
+pub const INDEX_DIRECTORY: &str = "index_directory";
+pub const LOAD_TAGS: &str = "load_tags";
+pub const LOAD_SYMBOL_INDEX: &str = "load_symbol_index";
+
+// Worker -> plugin
+pub const INDEX_BATCH: &str = "index_batch";
+pub const DIRECTORY_BATCH: &str = "directory_batch";
+pub const TAGS_LOADED: &str = "tags_loaded";
+pub const SYMBOL_INDEX_LOADED: &str = "symbol_index_loaded";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexRequest {
+    pub generation: usize, // Lets the plugin drop batches of an outdated scan
+    pub root: PathBuf,
+    pub config: ScanConfig,
+    pub repository_root: PathBuf, // Host path of `root`, keys the index cache
+    pub cache_dir: Option<PathBuf>,
+    // Index exactly these files (relative to `root`) instead of walking it
+    #[serde(default)]
+    pub files: Option<Vec<PathBuf>>,
+    // The project root to walk, relative to `root`, when several are indexed
+    #[serde(default)]
+    pub subdirectory: PathBuf,
+    // A folder that appeared after the scan, relative to the project root
+    #[serde(default)]
+    pub directory: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexBatch {
+    pub generation: usize,
+    pub files: Vec<(PathBuf, Vec<TypeDefinition>)>,
+    pub indexed_count: usize,
+    pub done: bool,
+    pub truncated_at: Option<usize>,
+    // Cached files that no longer exist, sent with the last batch
+    #[serde(default)]
+    pub removed: Vec<PathBuf>,
+    // Files of this batch that were reached through a symlink
+    #[serde(default)]
+    pub symlinked: Vec<PathBuf>,
+}
+
+impl IndexBatch {
+    pub fn into_files_and_rust_assets(self) -> BTreeMap<PathBuf, Vec<TypeDefinition>> {
+        share_file_paths(self.files)
+    }
+}
+
+// Asks for the symbols of a file kept at a project root, its tags file or
+// its SCIP or LSIF index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportRequest {
+    pub generation: usize,
+    pub host: PathBuf,
+    pub root_dir: PathBuf, // Relative to `host`
+    // The index to read rather than the first of the usual names found
+    #[serde(default)]
+    pub index_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportedSymbols {
+    pub generation: usize,
+    pub root_dir: PathBuf,
+    pub files: Vec<(PathBuf, Vec<TypeDefinition>)>,
+}
+
+impl ImportedSymbols {
+    pub fn into_symbols(self) -> BTreeMap<PathBuf, Vec<TypeDefinition>> {
+        share_file_paths(self.files)
+    }
+}
+
+// Definitions of the same file share one path again after deserialization
+fn share_file_paths(
+    files: Vec<(PathBuf, Vec<TypeDefinition>)>,
+) -> BTreeMap<PathBuf, Vec<TypeDefinition>> {
+    files
+        .into_iter()
+        .map(|(file_path, mut definitions)| {
+            let rc_path = Rc::new(file_path.clone());
+            for definition in &mut definitions {
+                definition.set_file_path(&rc_path);
+            }
+            (file_path, definitions)
+        })
+        .collect()
+}
+
+// Walks the project and scans Rust files off the plugin's main thread, one
+// batch per message so the plugin can apply each batch as it lands
+#[derive(Default, Serialize, Deserialize)]
+pub struct IndexWorker {
+    #[serde(skip)]
+    indexer: Option<FileIndexer>,
+    #[serde(skip)]
+    cache: Option<IndexCache>,
+    // Cached files are sent first, `cache_cursor` is the last one sent
+    #[serde(skip)]
+    showing_cache: bool,
+    #[serde(skip)]
+    cache_cursor: Option<PathBuf>,
+    generation: usize,
+}
+
+impl ZellijWorker<'_> for IndexWorker {
+    fn on_message(&mut self, message: String, payload: String) {
+        match message.as_str() {
+            START_INDEXING => {
+                if let Ok(request) = serde_json::from_str::<IndexRequest>(&payload) {
+                    self.start_indexing(request);
+                }
+            }
+            CONTINUE_INDEXING if payload.parse() == Ok(self.generation) => {
+                self.send_next_batch();
+            }
+            INDEX_DIRECTORY => {
+                if let Ok(request) = serde_json::from_str::<IndexRequest>(&payload) {
+                    self.index_directory(request);
+                }
+            }
+            LOAD_TAGS => {
+                if let Ok(request) = serde_json::from_str::<ImportRequest>(&payload) {
+                    let symbols = load_tags(&request.host, &request.root_dir);
+                    post_imported_symbols(TAGS_LOADED, request, symbols);
+                }
+            }
+            LOAD_SYMBOL_INDEX => {
+                if let Ok(request) = serde_json::from_str::<ImportRequest>(&payload) {
+                    let symbols = load_symbol_index(
+                        &request.host,
+                        &request.root_dir,
+                        request.index_file.as_deref(),
+                    );
+                    post_imported_symbols(SYMBOL_INDEX_LOADED, request, symbols);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl IndexWorker {
+    fn start_indexing(&mut self, request: IndexRequest) {
+        self.generation = request.generation;
+        self.cache = request.cache_dir.as_ref().map(|cache_dir| {
+            IndexCache::load(
+                cache_dir,
+                &request.root,
+                &request.repository_root,
+                &request.subdirectory,
+                &request.config,
+            )
+        });
+        self.indexer = Some(match request.files {
+            Some(files) => FileIndexer::from_file_list(request.root, files, &request.config),
+            None => FileIndexer::new(request.root.join(&request.subdirectory), &request.config),
+        });
+
+        // Show what was indexed last time right away, the walk then validates it
+        self.showing_cache = true;
+        self.cache_cursor = None;
+        self.send_next_batch();
+    }
+
+    // Sends the next batch of what was cached, returns false once all of it
+    // was sent
+    fn send_cached_batch(&mut self) -> bool {
+        let cached_files = match self.cache.as_ref() {
+            Some(cache) => cache.cached_files(self.cache_cursor.as_deref(), INDEX_BATCH_SIZE),
+            None => Vec::new(),
+        };
+        self.cache_cursor = match cached_files.last() {
+            Some((file, _)) => Some(file.clone()),
+            None => return false,
+        };
+        self.post_batch(
+            INDEX_BATCH,
+            IndexBatch {
+                generation: self.generation,
+                files: cached_files,
+                indexed_count: 0,
+                done: false,
+                truncated_at: None,
+                removed: Vec::new(),
+                symlinked: Vec::new(),
+            },
+        );
+        true
+    }
+
+    fn send_next_batch(&mut self) {
+        if self.showing_cache {
+            if self.send_cached_batch() {
+                return;
+            }
+            self.showing_cache = false;
+        }
+        let indexer = match self.indexer.as_mut() {
+            Some(indexer) => indexer,
+            None => return,
+        };
+
+        let files = match self.cache.as_mut() {
+            Some(cache) => {
+                let files = indexer.next_files(INDEX_BATCH_SIZE);
+                files
+                    .into_iter()
+                    .map(|file| {
+                        let definitions = cache.definitions_for(&file, indexer.scan_config());
+                        (file, definitions)
+                    })
+                    .collect()
+            }
+            None => indexer.next_batch(INDEX_BATCH_SIZE).into_iter().collect(),
+        };
+        let mut batch = IndexBatch {
+            generation: self.generation,
+            files,
+            indexed_count: indexer.indexed_count(),
+            done: indexer.is_done(),
+            truncated_at: if indexer.is_truncated() {
+                Some(indexer.file_limit())
+            } else {
+                None
+            },
+            removed: Vec::new(),
+            symlinked: indexer.take_symlinked_files(),
+        };
+
+        if batch.done {
+            self.indexer = None;
+            if let Some(cache) = self.cache.take() {
+                batch.removed = cache.removed_files();
+                let _ = cache.save();
+            }
+        }
+
+        self.post_batch(INDEX_BATCH, batch);
+    }
+
+    // Walks the folder in one go, leaving a scan in progress alone
+    fn index_directory(&self, request: IndexRequest) {
+        let directory = request.directory.unwrap_or_default();
+        let mut indexer = FileIndexer::for_directory(
+            request.root.join(&request.subdirectory),
+            &directory,
+            &request.config,
+        );
+        loop {
+            let files = indexer.next_batch(INDEX_BATCH_SIZE).into_iter().collect();
+            let batch = IndexBatch {
+                generation: request.generation,
+                files,
+                indexed_count: indexer.indexed_count(),
+                done: indexer.is_done(),
+                truncated_at: if indexer.is_truncated() {
+                    Some(indexer.file_limit())
+                } else {
+                    None
+                },
+                removed: Vec::new(),
+                symlinked: indexer.take_symlinked_files(),
+            };
+            let done = batch.done;
+            self.post_batch(DIRECTORY_BATCH, batch);
+            if done {
+                break;
+            }
+        }
+    }
+
+    fn post_batch(&self, message: &str, batch: IndexBatch) {
+        if let Ok(payload) = serde_json::to_string(&batch) {
+            post_message_to_plugin(PluginMessage::new_to_plugin(message, &payload));
+        }
+    }
+}
+
+fn post_imported_symbols(
+    message: &str,
+    request: ImportRequest,
+    symbols: BTreeMap<PathBuf, Vec<TypeDefinition>>,
+) {
+    let imported = ImportedSymbols {
+        generation: request.generation,
+        root_dir: request.root_dir,
+        files: symbols.into_iter().collect(),
+    };
+    if let Ok(payload) = serde_json::to_string(&imported) {
+        post_message_to_plugin(PluginMessage::new_to_plugin(message, &payload));
+    }
+}
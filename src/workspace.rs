@@ -0,0 +1,237 @@
+use crate::ignore::glob_match;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CargoPackage {
+    pub name: Rc<str>,
+    pub dir: PathBuf, // Relative to the project root, empty for the root package
+}
+
+// Packages of the project's Cargo workspace, read from the root Cargo.toml
+#[derive(Debug, Clone, Default)]
+pub struct CargoWorkspace {
+    packages: Vec<CargoPackage>,
+}
+
+impl CargoWorkspace {
+    pub fn load(root: &Path) -> Self {
+        let manifest = match fs::read_to_string(root.join("Cargo.toml")) {
+            Ok(manifest) => manifest,
+            Err(_) => return Self::default(),
+        };
+
+        let mut packages = Vec::new();
+        if let Some(name) = toml_string(&manifest, "package", "name") {
+            packages.push(CargoPackage {
+                name: name.into(),
+                dir: PathBuf::new(),
+            });
+        }
+
+        let excluded = toml_string_array(&manifest, "workspace", "exclude");
+        for member in toml_string_array(&manifest, "workspace", "members") {
+            for dir in expand_member_glob(root, &member) {
+                if excluded.iter().any(|e| Path::new(e) == dir) {
+                    continue;
+                }
+                let name = fs::read_to_string(root.join(&dir).join("Cargo.toml"))
+                    .ok()
+                    .and_then(|manifest| toml_string(&manifest, "package", "name"));
+                if let Some(name) = name {
+                    if !packages.iter().any(|package| package.dir == dir) {
+                        packages.push(CargoPackage {
+                            name: name.into(),
+                            dir,
+                        });
+                    }
+                }
+            }
+        }
+
+        // Deepest first, so nested packages win over the ones containing them
+        packages.sort_by_key(|package| std::cmp::Reverse(package.dir.components().count()));
+        CargoWorkspace { packages }
+    }
+
+    // The workspaces of several project roots, `dirs` being relative to `root`
+    pub fn load_all(root: &Path, dirs: &[PathBuf]) -> Self {
+        let mut packages: Vec<CargoPackage> = dirs
+            .iter()
+            .flat_map(|dir| {
+                CargoWorkspace::load(&root.join(dir))
+                    .packages
+                    .into_iter()
+                    .map(move |package| CargoPackage {
+                        name: package.name,
+                        dir: dir.join(package.dir),
+                    })
+            })
+            .collect();
+        packages.sort_by_key(|package| std::cmp::Reverse(package.dir.components().count()));
+        CargoWorkspace { packages }
+    }
+
+    // The package owning `file` (relative to the project root)
+    pub fn crate_for(&self, file: &Path) -> Option<Rc<str>> {
+        self.packages
+            .iter()
+            .find(|package| file.starts_with(&package.dir))
+            .map(|package| Rc::clone(&package.name))
+    }
+
+    pub fn package_for(&self, file: &Path) -> Option<&CargoPackage> {
+        self.packages
+            .iter()
+            .find(|package| file.starts_with(&package.dir))
+    }
+}
+
+// The module path of a file (relative to its package) in Cargo's default
+// layout, eg. ["ui", "table"] for src/ui/table.rs or src/ui/table/mod.rs.
+// Files directly in src/bin, tests, benches or examples are crate roots
+pub fn file_module_path(file: &Path) -> Vec<String> {
+    let mut components: Vec<String> = file
+        .with_extension("")
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    let target_root_len = match components.first().map(String::as_str) {
+        Some("src") if components.get(1).map(String::as_str) == Some("bin") => 3,
+        Some("src") => 1,
+        Some("tests" | "benches" | "examples") => 2,
+        _ => 0,
+    };
+    // src/main.rs, src/lib.rs and eg. tests/cli/main.rs are crate roots
+    // themselves
+    let is_crate_root = components.len() == target_root_len + 1
+        && match target_root_len {
+            0 => false,
+            1 => matches!(components[1].as_str(), "main" | "lib"),
+            _ => components[target_root_len] == "main",
+        };
+    if is_crate_root || components.len() <= target_root_len {
+        return Vec::new();
+    }
+    if components.last().map(String::as_str) == Some("mod") {
+        components.pop();
+    }
+    components.split_off(target_root_len)
+}
+
+// Cargo treats dashes and underscores in package names the same
+pub fn crate_name_matches(crate_name: &str, query: &str) -> bool {
+    crate_name.len() == query.len()
+        && crate_name
+            .bytes()
+            .zip(query.bytes())
+            .all(|(a, b)| a == b || (matches!(a, b'-' | b'_') && matches!(b, b'-' | b'_')))
+}
+
+// Expands a `members` entry such as "crates/*" into the directories it matches
+fn expand_member_glob(root: &Path, member: &str) -> Vec<PathBuf> {
+    let mut dirs = vec![PathBuf::new()];
+    for component in member.trim_end_matches('/').split('/') {
+        if component.is_empty() || component == "." {
+            continue;
+        }
+        let is_glob = component.contains(['*', '?', '[']);
+        dirs = dirs
+            .into_iter()
+            .flat_map(|dir| {
+                if !is_glob {
+                    return vec![dir.join(component)];
+                }
+                let mut matched: Vec<PathBuf> = fs::read_dir(root.join(&dir))
+                    .into_iter()
+                    .flatten()
+                    .flatten()
+                    .filter(|entry| entry.path().is_dir())
+                    .filter(|entry| {
+                        glob_match(
+                            component.as_bytes(),
+                            entry.file_name().to_string_lossy().as_bytes(),
+                        )
+                    })
+                    .map(|entry| dir.join(entry.file_name()))
+                    .collect();
+                matched.sort();
+                matched
+            })
+            .collect();
+    }
+    dirs
+}
+
+// Just enough TOML to read manifests: `key = "value"` inside a `[table]`
+fn toml_string(contents: &str, table: &str, key: &str) -> Option<String> {
+    let value = toml_value(contents, table, key)?;
+    parse_toml_strings(&value).into_iter().next()
+}
+
+// `key = ["a", "b"]` inside a `[table]`, possibly spanning several lines
+fn toml_string_array(contents: &str, table: &str, key: &str) -> Vec<String> {
+    toml_value(contents, table, key)
+        .map(|value| parse_toml_strings(&value))
+        .unwrap_or_default()
+}
+
+fn toml_value(contents: &str, table: &str, key: &str) -> Option<String> {
+    let mut in_table = false;
+    let mut lines = contents.lines().map(strip_toml_comment);
+    while let Some(line) = lines.next() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_table = line.trim_matches(|c| c == '[' || c == ']').trim() == table;
+            continue;
+        }
+        if !in_table {
+            continue;
+        }
+        let (line_key, value) = match line.split_once('=') {
+            Some(pair) => pair,
+            None => continue,
+        };
+        if line_key.trim() != key {
+            continue;
+        }
+
+        let mut value = value.trim().to_owned();
+        if value.starts_with('[') {
+            while !value.ends_with(']') {
+                match lines.next() {
+                    Some(next) => value.push_str(next.trim()),
+                    None => break,
+                }
+            }
+        }
+        return Some(value);
+    }
+    None
+}
+
+fn strip_toml_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' | '\'' => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+// All basic and literal strings in a value, in order
+fn parse_toml_strings(value: &str) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '"' || c == '\'' {
+            let string: String = chars.by_ref().take_while(|&next| next != c).collect();
+            strings.push(string);
+        }
+    }
+    strings
+}